@@ -0,0 +1,28 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::BufWriter,
+};
+
+use radolan::{
+    export::{write_esri_ascii, write_geotiff, PROJECTION_WKT},
+    projection::Grid,
+    Radolan,
+};
+
+/// writes `<file>.asc`, `<file>.prj` and `<file>.tif` next to a binary composite
+fn main() {
+    let path = env::args().nth(1).expect("usage: export <radolan file>");
+    let file = fs::read(&path).expect("File not found");
+    let radolan_file = Radolan::new(&file).unwrap();
+
+    let grid = Grid::from_dimension(&radolan_file.header().dimension).expect("unknown grid");
+    let raster = radolan_file.raster(-9999.0).unwrap();
+
+    let mut asc = BufWriter::new(File::create(format!("{path}.asc")).unwrap());
+    write_esri_ascii(&raster, &grid, &mut asc).unwrap();
+    fs::write(format!("{path}.prj"), PROJECTION_WKT).unwrap();
+
+    let mut tif = BufWriter::new(File::create(format!("{path}.tif")).unwrap());
+    write_geotiff(&raster, &grid, &mut tif).unwrap();
+}
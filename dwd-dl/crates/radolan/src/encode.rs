@@ -0,0 +1,163 @@
+//! Writer for the binary format, the inverse of [`Radolan::new`](crate::Radolan::new)
+
+use std::io::{self, Write};
+
+use crate::{
+    header::Header,
+    raster::Raster,
+    record::{Flags, Record},
+    END_OF_TEXT,
+};
+
+fn invalid_input(reason: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, reason)
+}
+
+/// Writes `header` and `records` (row by row from the top) as composite,
+/// the product length of the header is replaced by the length of the written file
+pub fn write_composite(
+    header: &Header,
+    records: &[Record],
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    let (rows, columns) = (
+        header.dimension.rows as usize,
+        header.dimension.columns as usize,
+    );
+    if records.len() != rows * columns {
+        return Err(invalid_input(format!(
+            "{} records don't match the grid with {}x{} cells",
+            records.len(),
+            rows,
+            columns
+        )));
+    }
+
+    let bytes = header.produktkennung.bytes_per_value();
+    let mut header = header.clone();
+    // the product length has a fixed width, so it doesn't change the header length
+    let header_length = header.to_string().len();
+    header.product_length = (header_length + 1 + records.len() * bytes) as u32;
+
+    let mut data = Vec::with_capacity(records.len() * bytes);
+    // the binary part starts with the bottom row
+    for (i, record) in records.chunks(columns).rev().flatten().enumerate() {
+        let encoded = match bytes {
+            1 => record.to_rvp6().map(|b| vec![b]),
+            _ => record.to_bytes().map(|b| b.to_vec()),
+        };
+        let encoded = encoded.ok_or_else(|| {
+            let (row, column) = (rows - 1 - i / columns, i % columns);
+            invalid_input(format!(
+                "{:?} in row {}, column {} can't be encoded in {}",
+                record, row, column, header.produktkennung
+            ))
+        })?;
+        data.extend_from_slice(&encoded);
+    }
+
+    writer.write_all(header.to_string().as_bytes())?;
+    writer.write_all(&[END_OF_TEXT])?;
+    writer.write_all(&data)
+}
+
+/// Writes the values of a raster with [`write_composite`], `nodata` becomes an error and
+/// negative values get the negative flag, one byte products take the values as RVP6 units
+pub fn write_raster(header: &Header, raster: &Raster, writer: &mut impl Write) -> io::Result<()> {
+    if raster.rows != header.dimension.rows || raster.columns != header.dimension.columns {
+        return Err(invalid_input(format!(
+            "raster with {}x{} cells doesn't match the header with {}x{} cells",
+            raster.rows, raster.columns, header.dimension.rows, header.dimension.columns
+        )));
+    }
+    let precision = match header.produktkennung.bytes_per_value() {
+        1 => 1.0,
+        _ => 10_f32.powi(-header.precision as i32),
+    };
+    let records = raster
+        .values
+        .iter()
+        .map(|&v| {
+            let scaled = (v.abs() * precision).round() as u16;
+            match v {
+                v if v == raster.nodata => Record::new(0, Flags::ERROR),
+                v if v < 0.0 => Record::new(scaled, Flags::NEGATIVE),
+                _ => Record::normal(scaled),
+            }
+        })
+        .collect::<Vec<_>>();
+    write_composite(header, &records, writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{header::Header, Radolan};
+
+    fn header(s: &str) -> Header {
+        Header::new(s).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let header = header(
+            "RW010050100000322BY      0VS 3SW   2.18.3PR E-01INT  60GP 900x 900MS 13<boo,ros,emd>",
+        );
+        let mut records = vec![Record::normal(0); 900 * 900];
+        records[0] = Record::normal(15);
+        records[1] = Record::new(2500, Flags::ERROR);
+        records[900 * 900 - 1] = Record::new(3, Flags::NEGATIVE);
+        records[900 * 450] = Record::new(4095, Flags::INTERPOLATED);
+
+        let mut file = Vec::new();
+        write_composite(&header, &records, &mut file).unwrap();
+        let radolan = Radolan::new(&file).unwrap();
+        radolan.validate().unwrap();
+        assert_eq!(radolan.header().product_length as usize, file.len());
+        assert_eq!(radolan.header().dimension, header.dimension);
+        for (i, record) in records.iter().enumerate() {
+            let (row, column) = ((i / 900) as u16, (i % 900) as u16);
+            assert_eq!(radolan.get_point(row, column).unwrap(), *record);
+        }
+
+        // writing the decoded raster again gives the same file, apart from the error values
+        let raster = radolan.raster(-1.0).unwrap();
+        let mut written = Vec::new();
+        write_raster(radolan.header(), &raster, &mut written).unwrap();
+        let written = Radolan::new(&written).unwrap();
+        assert_eq!(written.raster(-1.0).unwrap(), raster);
+        assert_eq!(
+            written.get_point(0, 1).unwrap(),
+            Record::new(0, Flags::ERROR)
+        );
+
+        assert!(write_composite(&header, &records[1..], &mut Vec::new()).is_err());
+        records[5] = Record::normal(0x1000);
+        assert!(write_composite(&header, &records, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_rvp6_round_trip() {
+        let header = header(
+            "RX022050100000120BY 810134VS 3SW   2.28.0PR E+00INT   5GP 900x 900MS 13<boo,ros,emd>",
+        );
+        let mut records = vec![Record::normal(65); 900 * 900];
+        records[7] = Record::new(249, Flags::CLUTTER);
+        records[8] = Record::new(250, Flags::ERROR);
+
+        let mut file = Vec::new();
+        write_composite(&header, &records, &mut file).unwrap();
+        assert_eq!(file.len(), header.to_string().len() + 1 + 900 * 900);
+        let radolan = Radolan::new(&file).unwrap();
+        radolan.validate().unwrap();
+        assert_eq!(radolan.dbz(0, 0).unwrap(), Some(0.0));
+        assert_eq!(
+            radolan.get_point(0, 7).unwrap(),
+            Record::new(249, Flags::CLUTTER)
+        );
+        assert_eq!(
+            radolan.get_point(0, 8).unwrap(),
+            Record::new(250, Flags::ERROR)
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Writers for a [`Raster`] in the stereographic projection of its [`Grid`], coordinates in meters
+
+use std::io::{self, Write};
+
+use crate::{projection::Grid, raster::Raster};
+
+/// The projection for the `.prj` file next to an ESRI ASCII grid
+pub const PROJECTION_WKT: &str = concat!(
+    r#"PROJCS["RADOLAN polar stereographic","#,
+    r#"GEOGCS["RADOLAN sphere",DATUM["D_RADOLAN",SPHEROID["RADOLAN",6370040.0,0.0]],"#,
+    r#"PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],"#,
+    r#"PROJECTION["Stereographic_North_Pole"],"#,
+    r#"PARAMETER["False_Easting",0.0],PARAMETER["False_Northing",0.0],"#,
+    r#"PARAMETER["Central_Meridian",10.0],PARAMETER["Standard_Parallel_1",60.0],"#,
+    r#"UNIT["Meter",1.0]]"#,
+);
+
+const CELLSIZE: f64 = 1000.0;
+
+/// km of the grid to m, rounded to mm to get rid of floating point noise
+fn meters(km: f64) -> f64 {
+    (km * 1e6).round() / 1e3
+}
+
+fn check_dimension(raster: &Raster, grid: &Grid) -> io::Result<()> {
+    if raster.rows != grid.rows || raster.columns != grid.columns {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "raster with {}x{} cells doesn't match the grid with {}x{} cells",
+                raster.rows, raster.columns, grid.rows, grid.columns
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// ESRI ASCII grid, the projection isn't part of the format, see [`PROJECTION_WKT`]
+pub fn write_esri_ascii(raster: &Raster, grid: &Grid, writer: &mut impl Write) -> io::Result<()> {
+    check_dimension(raster, grid)?;
+    let (x, y) = grid.lower_left();
+    writeln!(writer, "NCOLS {}", raster.columns)?;
+    writeln!(writer, "NROWS {}", raster.rows)?;
+    writeln!(writer, "XLLCORNER {}", meters(x))?;
+    writeln!(writer, "YLLCORNER {}", meters(y))?;
+    writeln!(writer, "CELLSIZE {CELLSIZE}")?;
+    writeln!(writer, "NODATA_VALUE {}", raster.nodata)?;
+    for row in 0..raster.rows {
+        let values = raster.row(row).iter().map(|v| v.to_string());
+        writeln!(writer, "{}", values.collect::<Vec<_>>().join(" "))?;
+    }
+    Ok(())
+}
+
+/// Value of a TIFF tag
+enum Field {
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Double(Vec<f64>),
+    Ascii(String),
+}
+
+impl Field {
+    /// `(type, count, little endian bytes)`
+    fn encode(&self) -> (u16, u32, Vec<u8>) {
+        match self {
+            Field::Short(v) => (
+                3,
+                v.len() as u32,
+                v.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            ),
+            Field::Long(v) => (
+                4,
+                v.len() as u32,
+                v.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            ),
+            Field::Double(v) => (
+                12,
+                v.len() as u32,
+                v.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            ),
+            Field::Ascii(s) => {
+                let mut bytes = s.as_bytes().to_vec();
+                bytes.push(0);
+                (2, bytes.len() as u32, bytes)
+            }
+        }
+    }
+}
+
+const GEO_DOUBLE_PARAMS: u16 = 34736;
+const GEO_ASCII_PARAMS: u16 = 34737;
+
+/// GeoKeys of the projection: `(key, location, count, value or index)`, sorted by key
+#[rustfmt::skip]
+const GEO_KEYS: [[u16; 4]; 20] = [
+    [1024, 0, 1, 1],                      // GTModelType: projected
+    [1025, 0, 1, 1],                      // GTRasterType: pixel is area
+    [1026, GEO_ASCII_PARAMS, 28, 0],      // GTCitation
+    [2048, 0, 1, 32767],                  // GeographicType: user defined
+    [2049, GEO_ASCII_PARAMS, 15, 28],     // GeogCitation
+    [2050, 0, 1, 32767],                  // GeogGeodeticDatum: user defined
+    [2051, 0, 1, 8901],                   // GeogPrimeMeridian: Greenwich
+    [2054, 0, 1, 9102],                   // GeogAngularUnits: degree
+    [2056, 0, 1, 32767],                  // GeogEllipsoid: user defined
+    [2057, GEO_DOUBLE_PARAMS, 1, 0],      // GeogSemiMajorAxis
+    [2058, GEO_DOUBLE_PARAMS, 1, 1],      // GeogSemiMinorAxis
+    [3072, 0, 1, 32767],                  // ProjectedCSType: user defined
+    [3074, 0, 1, 32767],                  // Projection: user defined
+    [3075, 0, 1, 15],                     // ProjCoordTrans: polar stereographic
+    [3076, 0, 1, 9001],                   // ProjLinearUnits: meter
+    [3081, GEO_DOUBLE_PARAMS, 1, 2],      // ProjNatOriginLat: latitude of true scale
+    [3082, GEO_DOUBLE_PARAMS, 1, 3],      // ProjFalseEasting
+    [3083, GEO_DOUBLE_PARAMS, 1, 4],      // ProjFalseNorthing
+    [3092, GEO_DOUBLE_PARAMS, 1, 5],      // ProjScaleAtNatOrigin
+    [3095, GEO_DOUBLE_PARAMS, 1, 6],      // ProjStraightVertPoleLong
+];
+const GEO_DOUBLES: [f64; 7] = [6370040.0, 6370040.0, 60.0, 0.0, 0.0, 1.0, 10.0];
+const GEO_ASCII: &str = "RADOLAN polar stereographic|RADOLAN sphere|";
+
+/// Little endian GeoTIFF with 32 bit float samples in a single strip
+pub fn write_geotiff(raster: &Raster, grid: &Grid, writer: &mut impl Write) -> io::Result<()> {
+    check_dimension(raster, grid)?;
+    let (x, y) = grid.lower_left();
+    // raster point (0, 0) is the upper left corner
+    let tiepoint = vec![0.0, 0.0, 0.0, meters(x), meters(y + grid.rows as f64), 0.0];
+    let image_size = raster.values.len() as u32 * 4;
+
+    let mut geo_keys = vec![1, 1, 0, GEO_KEYS.len() as u16];
+    geo_keys.extend(GEO_KEYS.iter().flatten());
+    let fields = [
+        (256, Field::Long(vec![raster.columns as u32])), // ImageWidth
+        (257, Field::Long(vec![raster.rows as u32])),    // ImageLength
+        (258, Field::Short(vec![32])),                   // BitsPerSample
+        (259, Field::Short(vec![1])),                    // Compression: none
+        (262, Field::Short(vec![1])),                    // PhotometricInterpretation: black is zero
+        (273, Field::Long(vec![0])),                     // StripOffsets, set below
+        (277, Field::Short(vec![1])),                    // SamplesPerPixel
+        (278, Field::Long(vec![raster.rows as u32])),    // RowsPerStrip
+        (279, Field::Long(vec![image_size])),            // StripByteCounts
+        (284, Field::Short(vec![1])),                    // PlanarConfiguration: chunky
+        (339, Field::Short(vec![3])),                    // SampleFormat: float
+        (33550, Field::Double(vec![CELLSIZE, CELLSIZE, 0.0])), // ModelPixelScale
+        (33922, Field::Double(tiepoint)),                // ModelTiepoint
+        (34735, Field::Short(geo_keys)),                 // GeoKeyDirectory
+        (GEO_DOUBLE_PARAMS, Field::Double(GEO_DOUBLES.to_vec())),
+        (GEO_ASCII_PARAMS, Field::Ascii(GEO_ASCII.to_string())),
+        (42113, Field::Ascii(raster.nodata.to_string())), // GDAL_NODATA
+    ];
+
+    // header, directory, values which don't fit into the directory, image
+    let directory_size = 2 + 12 * fields.len() as u32 + 4;
+    let mut extra = Vec::new();
+    let mut entries = fields
+        .iter()
+        .map(|(tag, field)| {
+            let (field_type, count, bytes) = field.encode();
+            (*tag, field_type, count, bytes)
+        })
+        .collect::<Vec<_>>();
+    for (_, _, _, bytes) in &mut entries {
+        if bytes.len() > 4 {
+            let offset = 8 + directory_size + extra.len() as u32;
+            extra.append(bytes);
+            // values start on a word boundary
+            if extra.len() % 2 == 1 {
+                extra.push(0);
+            }
+            *bytes = offset.to_le_bytes().to_vec();
+        }
+    }
+    let image_offset = 8 + directory_size + extra.len() as u32;
+    let strip_offsets = entries.iter_mut().find(|(tag, ..)| *tag == 273).unwrap();
+    strip_offsets.3 = image_offset.to_le_bytes().to_vec();
+
+    writer.write_all(b"II")?;
+    writer.write_all(&42u16.to_le_bytes())?;
+    writer.write_all(&8u32.to_le_bytes())?;
+    writer.write_all(&(entries.len() as u16).to_le_bytes())?;
+    for (tag, field_type, count, mut bytes) in entries {
+        bytes.resize(4, 0);
+        writer.write_all(&tag.to_le_bytes())?;
+        writer.write_all(&field_type.to_le_bytes())?;
+        writer.write_all(&count.to_le_bytes())?;
+        writer.write_all(&bytes)?;
+    }
+    // no further directory
+    writer.write_all(&0u32.to_le_bytes())?;
+    writer.write_all(&extra)?;
+    for value in &raster.values {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raster(grid: &Grid) -> Raster {
+        let cells = grid.rows as usize * grid.columns as usize;
+        Raster {
+            rows: grid.rows,
+            columns: grid.columns,
+            values: (0..cells).map(|i| (i % 7) as f32 / 10.0).collect(),
+            nodata: -9999.0,
+        }
+    }
+
+    #[test]
+    fn test_esri_ascii() {
+        let grid = Grid::NATIONAL;
+        let mut out = Vec::new();
+        write_esri_ascii(&raster(&grid), &grid, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let mut lines = out.lines();
+        assert_eq!(lines.next(), Some("NCOLS 900"));
+        assert_eq!(lines.next(), Some("NROWS 900"));
+        assert_eq!(lines.next(), Some("XLLCORNER -523462.2"));
+        assert_eq!(lines.next(), Some("YLLCORNER -4658645"));
+        assert_eq!(lines.next(), Some("CELLSIZE 1000"));
+        assert_eq!(lines.next(), Some("NODATA_VALUE -9999"));
+        assert!(lines.next().unwrap().starts_with("0 0.1 0.2 0.3"));
+        assert_eq!(lines.count(), 899);
+
+        let mut out = Vec::new();
+        assert!(write_esri_ascii(&raster(&grid), &Grid::EXTENDED, &mut out).is_err());
+    }
+
+    fn read_u16(file: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes([file[offset], file[offset + 1]])
+    }
+
+    fn read_u32(file: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(file[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_geotiff() {
+        let grid = Grid::EXTENDED;
+        let raster = raster(&grid);
+        let mut file = Vec::new();
+        write_geotiff(&raster, &grid, &mut file).unwrap();
+
+        assert_eq!(&file[..4], b"II\x2a\x00");
+        let directory = read_u32(&file, 4) as usize;
+        let entries = read_u16(&file, directory) as usize;
+        let entry = |tag: u16| {
+            (0..entries)
+                .map(|i| directory + 2 + 12 * i)
+                .find(|&e| read_u16(&file, e) == tag)
+                .unwrap()
+        };
+        assert_eq!(read_u32(&file, entry(256) + 8), 900);
+        assert_eq!(read_u32(&file, entry(257) + 8), 1100);
+
+        // tags are sorted
+        let tags = (0..entries).map(|i| read_u16(&file, directory + 2 + 12 * i));
+        assert!(tags.collect::<Vec<_>>().windows(2).all(|w| w[0] < w[1]));
+
+        let tiepoint = read_u32(&file, entry(33922) + 8) as usize;
+        let double =
+            |offset: usize| f64::from_le_bytes(file[offset..offset + 8].try_into().unwrap());
+        assert_eq!(double(tiepoint + 24), -443462.2);
+        assert_eq!(double(tiepoint + 32), -3658645.0);
+
+        let image = read_u32(&file, entry(273) + 8) as usize;
+        assert_eq!(file.len(), image + raster.values.len() * 4);
+        let second = f32::from_le_bytes(file[image + 4..image + 8].try_into().unwrap());
+        assert_eq!(second, 0.1);
+    }
+}
@@ -1,6 +1,5 @@
 use nom::{
-    bytes::complete::{tag, take},
-    character::complete::digit1,
+    bytes::complete::{tag, take, take_while, take_while1},
     combinator::{map_res, opt},
     error::{Error, ErrorKind},
     Err, Finish, IResult,
@@ -8,7 +7,7 @@ use nom::{
 use thiserror::Error;
 use time::{format_description::well_known::Iso8601, PrimitiveDateTime};
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct Header {
     pub produktkennung: Produktkennung,
     pub datetime: PrimitiveDateTime,
@@ -16,17 +15,28 @@ pub struct Header {
     pub product_length: u32, //produktlänge
     pub format_version: String,
     pub software_version: String,
-    pub precision: u8,          //genauigkeit
-    pub interval_duration: u16, //intervalldauer
-    pub unit: Option<u8>,       //masseinheit
+    /// values are multiplied with `10^precision`, e.g. `-2` for `PR E-02`
+    pub precision: i8, //genauigkeit
+    pub interval_duration: u16,              //intervalldauer
+    pub interval_unit: Option<IntervalUnit>, //einheit des intervalls
     pub dimension: Dimension,
+    /// minutes after `datetime` of a forecast (RADVOR, e.g. RV and RQ)
+    pub forecast_time: Option<u16>, //vorhersagezeitpunkt
     // MF = 00000001 für die erste Version der Qualität dieser Produkte steht.
     pub binary_representation: Option<u32>, //binäre Darstellung
-    pub radar_locations: Vec<String>,       //standorte
+    pub quantification: Option<u16>,        //quantifizierungsart
+    /// version of a reprocessed product, e.g. `2017.002` for RADKLIM
+    pub reprocessing: Option<String>,
+    pub radar_locations: Vec<String>, //standorte
     pub radar_location_contributions: Option<Vec<(String, u8)>>, //standort_beitrag
 }
 
 impl Header {
+    /// physical unit of the values, `None` for untested products
+    pub fn unit(&self) -> Option<Unit> {
+        self.produktkennung.unit()
+    }
+
     pub fn new(s: &str) -> Result<Self, HeaderParseError> {
         let (_, header) = parse_header(s)
             .finish()
@@ -35,21 +45,146 @@ impl Header {
     }
 }
 
+/// The header text as parsed by [`Header::new`]
+impl std::fmt::Display for Header {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = self.datetime;
+        write!(
+            f,
+            "{}{:02}{:02}{:02}{:>5}{:02}{:02}",
+            self.produktkennung,
+            d.day(),
+            d.hour(),
+            d.minute(),
+            self.location,
+            d.month() as u8,
+            d.year() % 100
+        )?;
+        write!(f, "BY{:>7}", self.product_length)?;
+        write!(f, "VS{:>2}", self.format_version)?;
+        write!(f, "SW{:>9}", self.software_version)?;
+        write!(f, "PR E{:+03}", self.precision)?;
+        write!(f, "INT{:>4}", self.interval_duration)?;
+        if let Some(unit) = self.interval_unit {
+            write!(f, "U{}", unit as u8)?;
+        }
+        let Dimension { rows, columns } = self.dimension;
+        write!(f, "GP{:>4}x{:>4}", rows, columns)?;
+        if let Some(forecast_time) = self.forecast_time {
+            write!(f, "VV {:03}", forecast_time)?;
+        }
+        if let Some(binary_representation) = self.binary_representation {
+            write!(f, "MF {:08}", binary_representation)?;
+        }
+        if let Some(quantification) = self.quantification {
+            write!(f, "QN {:03}", quantification)?;
+        }
+        if let Some(reprocessing) = &self.reprocessing {
+            write!(f, "VR{:>8}", reprocessing)?;
+        }
+        let locations = format!("<{}>", self.radar_locations.join(","));
+        write!(f, "MS{:>3}{}", locations.len(), locations)?;
+        if let Some(contributions) = &self.radar_location_contributions {
+            let contributions = contributions
+                .iter()
+                .map(|(location, contribution)| format!("{} {}", location, contribution))
+                .collect::<Vec<_>>();
+            let contributions = format!("<{}>", contributions.join(","));
+            write!(f, "ST{:>3}{}", contributions.len(), contributions)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum HeaderParseError {
     #[error("Parsing error in the input")]
     ParseError(String),
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum Produktkennung {
     YW,
     RW,
     SF,
+    RY,
+    /// reflectivity in RVP6 units, one byte per value
+    RX,
+    /// RX in the extended grid
+    WX,
+    RZ,
+    RV,
+    RQ,
+    SQ,
+    SH,
+    EW,
+    RE,
+    W1,
+    W2,
+    W3,
+    W4,
+    YH,
     NotTested(String),
 }
 
-#[derive(Debug, PartialEq)]
+impl std::fmt::Display for Produktkennung {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Produktkennung::NotTested(s) => write!(f, "{}", s),
+            produktkennung => write!(f, "{:?}", produktkennung),
+        }
+    }
+}
+
+/// `U` of the header, the unit of `INT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalUnit {
+    Minutes = 0,
+    Days = 1,
+}
+
+/// Physical unit of the values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// precipitation height of the interval
+    Mm,
+    MmPerHour,
+    Dbz,
+    /// reflectivity in RVP6 units, see [`crate::reflectivity::rvp6_to_dbz`]
+    Rvp6,
+}
+
+impl Unit {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Mm => "mm",
+            Unit::MmPerHour => "mm/h",
+            Unit::Dbz => "dBZ",
+            Unit::Rvp6 => "RVP6",
+        }
+    }
+}
+
+impl Produktkennung {
+    pub fn unit(&self) -> Option<Unit> {
+        match self {
+            Produktkennung::RX | Produktkennung::WX => Some(Unit::Rvp6),
+            Produktkennung::RZ => Some(Unit::MmPerHour),
+            Produktkennung::NotTested(_) => None,
+            _ => Some(Unit::Mm),
+        }
+    }
+
+    /// bytes of one value in the binary part
+    pub fn bytes_per_value(&self) -> usize {
+        match self {
+            Produktkennung::RX | Produktkennung::WX => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Dimension {
     pub rows: u16,
     pub columns: u16,
@@ -63,14 +198,16 @@ fn parse_header(s: &str) -> IResult<&str, Header> {
     let (s, software_version) = parse_software_version(s)?;
     let (s, precision) = parse_precision(s)?;
     let (s, interval_duration) = parse_interval_duration(s)?;
-    let (s, unit) = opt(parse_unit)(s)?;
+    let (s, interval_unit) = opt(parse_interval_unit)(s)?;
     let (s, dimension) = parse_dimension(s)?;
+    let (s, forecast_time) = opt(parse_forecast_time)(s)?;
     let (s, binary_representation) = opt(parse_binary_representation)(s)?;
-    let (s, _) = opt(parse_undocumented_vr)(s)?;
+    let (s, quantification) = opt(parse_quantification)(s)?;
+    let (s, reprocessing) = opt(parse_reprocessing)(s)?;
     let (s, radar_locations) = parse_radar_locations(s)?;
     let (s, radar_location_contributions) = opt(parse_radar_location_contributions)(s)?;
     s.is_empty()
-        .then(|| ())
+        .then_some(())
         .ok_or(Err::Error(Error::new("Input not Empty", ErrorKind::Fail)))?;
 
     Ok((
@@ -84,9 +221,12 @@ fn parse_header(s: &str) -> IResult<&str, Header> {
             software_version,
             precision,
             interval_duration,
-            unit,
+            interval_unit,
             dimension,
+            forecast_time,
             binary_representation,
+            quantification,
+            reprocessing,
             radar_locations,
             radar_location_contributions,
         },
@@ -94,18 +234,41 @@ fn parse_header(s: &str) -> IResult<&str, Header> {
 }
 
 fn parse_produktkennung(input: &str) -> IResult<&str, Produktkennung> {
-    let (input, produktkennung) = nom::branch::alt((tag("YW"), tag("RW"), tag("SF")))(input)?;
+    let (input, produktkennung) = take(2usize)(input)?;
     Ok((
         input,
         match produktkennung {
             "YW" => Produktkennung::YW,
             "RW" => Produktkennung::RW,
             "SF" => Produktkennung::SF,
+            "RY" => Produktkennung::RY,
+            "RX" => Produktkennung::RX,
+            "WX" => Produktkennung::WX,
+            "RZ" => Produktkennung::RZ,
+            "RV" => Produktkennung::RV,
+            "RQ" => Produktkennung::RQ,
+            "SQ" => Produktkennung::SQ,
+            "SH" => Produktkennung::SH,
+            "EW" => Produktkennung::EW,
+            "RE" => Produktkennung::RE,
+            "W1" => Produktkennung::W1,
+            "W2" => Produktkennung::W2,
+            "W3" => Produktkennung::W3,
+            "W4" => Produktkennung::W4,
+            "YH" => Produktkennung::YH,
             s => Produktkennung::NotTested(s.to_string()),
         },
     ))
 }
 
+/// a number which is padded with spaces to a varying width, e.g. `BY 810134` and `BY1620130`
+fn parse_padded_number<T: std::str::FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(
+        take_while1(|c: char| c == ' ' || c.is_ascii_digit()),
+        |s: &str| s.trim().parse::<T>(),
+    )(input)
+}
+
 fn parse_datetime_and_location(input: &str) -> IResult<&str, (PrimitiveDateTime, String)> {
     let (input, day) = take(2usize)(input)?;
     let (input, hour) = take(2usize)(input)?;
@@ -123,7 +286,7 @@ fn parse_datetime_and_location(input: &str) -> IResult<&str, (PrimitiveDateTime,
 
 fn parse_product_length(input: &str) -> IResult<&str, u32> {
     let (input, _) = tag("BY")(input)?;
-    map_res(digit1, |s: &str| s.parse::<u32>())(input)
+    parse_padded_number(input)
 }
 
 fn parse_format_version(input: &str) -> IResult<&str, String> {
@@ -132,32 +295,45 @@ fn parse_format_version(input: &str) -> IResult<&str, String> {
     Ok((input, format_version.trim().to_string()))
 }
 
+/// e.g. `SW   2.18.3` and `SW   2.9.0`
 fn parse_software_version(input: &str) -> IResult<&str, String> {
     let (input, _) = tag("SW")(input)?;
-    let (input, format_version) = take(9usize)(input)?;
-    Ok((input, format_version.trim().to_string()))
+    let (input, software_version) =
+        take_while(|c: char| c == ' ' || c == '.' || c.is_ascii_digit())(input)?;
+    Ok((input, software_version.trim().to_string()))
 }
 
-fn parse_precision(input: &str) -> IResult<&str, u8> {
+/// the exponent of `E-01`, `E-02`, `E+00`, ...
+fn parse_precision(input: &str) -> IResult<&str, i8> {
     let (input, _) = tag("PR")(input)?;
-    let (input, precision) = take(5usize)(input)?;
-    let precision = match precision.trim() {
-        "E-00" => 1,
-        "E-01" => 10,
-        "E-02" => 100,
-        _ => unreachable!(),
-    };
-    Ok((input, precision))
+    let (input, _) = take_while(|c| c == ' ')(input)?;
+    let (input, _) = tag("E")(input)?;
+    map_res(take(3usize), |s: &str| s.parse::<i8>())(input)
 }
 
+/// minutes, e.g. `INT   5`, `INT1440` and `INT10080`
 fn parse_interval_duration(input: &str) -> IResult<&str, u16> {
     let (input, _) = tag("INT")(input)?;
-    map_res(take(4usize), |s: &str| s.trim().parse::<u16>())(input)
+    parse_padded_number(input)
+}
+
+fn parse_forecast_time(input: &str) -> IResult<&str, u16> {
+    let (input, _) = tag("VV")(input)?;
+    parse_padded_number(input)
 }
 
-fn parse_unit(input: &str) -> IResult<&str, u8> {
+fn parse_quantification(input: &str) -> IResult<&str, u16> {
+    let (input, _) = tag("QN")(input)?;
+    parse_padded_number(input)
+}
+
+fn parse_interval_unit(input: &str) -> IResult<&str, IntervalUnit> {
     let (input, _) = tag("U")(input)?;
-    map_res(take(1usize), |s: &str| s.trim().parse::<u8>())(input)
+    map_res(take(1usize), |s: &str| match s {
+        "0" => Ok(IntervalUnit::Minutes),
+        "1" => Ok(IntervalUnit::Days),
+        _ => Err(()),
+    })(input)
 }
 
 fn parse_dimension(input: &str) -> IResult<&str, Dimension> {
@@ -176,10 +352,11 @@ fn parse_binary_representation(input: &str) -> IResult<&str, u32> {
     map_res(take(9usize), |s: &str| s.trim().parse::<u32>())(input)
 }
 
-fn parse_undocumented_vr(input: &str) -> IResult<&str, ()> {
+/// e.g. `VR2017.002`
+fn parse_reprocessing(input: &str) -> IResult<&str, String> {
     let (input, _) = tag("VR")(input)?;
-    let (input, _) = take(8usize)(input)?;
-    Ok((input, ()))
+    let (input, reprocessing) = take(8usize)(input)?;
+    Ok((input, reprocessing.trim().to_string()))
 }
 
 fn parse_radar_locations(input: &str) -> IResult<&str, Vec<String>> {
@@ -241,14 +418,17 @@ mod tests {
                 product_length: 1980164,
                 format_version: "3".to_string(),
                 software_version: "2.18.3".to_string(),
-                precision: 100,
+                precision: -2,
                 interval_duration: 5,
-                unit: Some(0),
+                interval_unit: Some(IntervalUnit::Minutes),
                 dimension: Dimension {
                     rows: 1100,
                     columns: 900
                 },
+                forecast_time: None,
                 binary_representation: Some(0),
+                quantification: None,
+                reprocessing: Some("2017.002".to_string()),
                 radar_locations: vec![
                     "boo".to_string(),
                     "ros".to_string(),
@@ -286,14 +466,17 @@ mod tests {
                 product_length: 1980160,
                 format_version: "3".to_string(),
                 software_version: "2.18.3".to_string(),
-                precision: 10,
+                precision: -1,
                 interval_duration: 60,
-                unit: Some(0),
+                interval_unit: Some(IntervalUnit::Minutes),
                 dimension: Dimension {
                     rows: 1100,
                     columns: 900
                 },
+                forecast_time: None,
                 binary_representation: Some(1),
+                quantification: None,
+                reprocessing: Some("2017.002".to_string()),
                 radar_locations: vec![
                     "boo".to_string(),
                     "ros".to_string(),
@@ -330,14 +513,17 @@ mod tests {
                 product_length: 1620267,
                 format_version: "3".to_string(),
                 software_version: "2.21.0".to_string(),
-                precision: 10,
+                precision: -1,
                 interval_duration: 1440,
-                unit: None,
+                interval_unit: None,
                 dimension: Dimension {
                     rows: 900,
                     columns: 900
                 },
+                forecast_time: None,
                 binary_representation: None,
+                quantification: None,
+                reprocessing: None,
                 radar_locations: vec![
                     "asb".to_string(),
                     "boo".to_string(),
@@ -379,4 +565,236 @@ mod tests {
             }
         );
     }
+
+    /// `(header, produktkennung, precision, interval, rows, columns, forecast time, quantification)`
+    #[allow(clippy::type_complexity)]
+    const SAMPLES: &[(&str, Produktkennung, i8, u16, u16, u16, Option<u16>, Option<u16>)] = &[
+        (
+            "RY022050100000120BY1620162VS 3SW   2.28.0PR E-02INT   5GP 900x 900MS 65<boo,ros,emd,hnr,umd,pro,ess,fld,drs,neu,nhb,oft,eis,tur,isn,fbg>",
+            Produktkennung::RY,
+            -2,
+            5,
+            900,
+            900,
+            None,
+            None,
+        ),
+        (
+            "RX022050100000120BY 810134VS 3SW   2.28.0PR E+00INT   5GP 900x 900MS 65<boo,ros,emd,hnr,umd,pro,ess,fld,drs,neu,nhb,oft,eis,tur,isn,fbg>",
+            Produktkennung::RX,
+            0,
+            5,
+            900,
+            900,
+            None,
+            None,
+        ),
+        (
+            "WX022050100000120BY 990163VS 5SW   2.28.0PR E+00INT   5GP1100x 900MS 65<boo,ros,emd,hnr,umd,pro,ess,fld,drs,neu,nhb,oft,eis,tur,isn,fbg>",
+            Produktkennung::WX,
+            0,
+            5,
+            1100,
+            900,
+            None,
+            None,
+        ),
+        (
+            "RZ022050100000120BY1620162VS 3SW   2.28.0PR E-02INT   5GP 900x 900MS 13<boo,ros,emd>",
+            Produktkennung::RZ,
+            -2,
+            5,
+            900,
+            900,
+            None,
+            None,
+        ),
+        (
+            "RV022050100000120BY2640235VS 5SW   2.28.0PR E-02INT   5U0GP1200x1100VV 120MF 00000008QN 016MS 65<boo,ros,emd,hnr,umd,pro,ess,fld,drs,neu,nhb,oft,eis,tur,isn,fbg>",
+            Produktkennung::RV,
+            -2,
+            5,
+            1200,
+            1100,
+            Some(120),
+            Some(16),
+        ),
+        (
+            "RQ022050100000120BY1620235VS 3SW   2.28.0PR E-01INT  60GP 900x 900VV 060MF 00000002QN 001MS 65<boo,ros,emd,hnr,umd,pro,ess,fld,drs,neu,nhb,oft,eis,tur,isn,fbg>",
+            Produktkennung::RQ,
+            -1,
+            60,
+            900,
+            900,
+            Some(60),
+            Some(1),
+        ),
+        (
+            "SQ022050100000120BY1620162VS 3SW   2.28.0PR E-01INT 360GP 900x 900MS 13<boo,ros,emd>",
+            Produktkennung::SQ,
+            -1,
+            360,
+            900,
+            900,
+            None,
+            None,
+        ),
+        (
+            "SH022050100000120BY1620162VS 3SW   2.28.0PR E-01INT 720GP 900x 900MS 13<boo,ros,emd>",
+            Produktkennung::SH,
+            -1,
+            720,
+            900,
+            900,
+            None,
+            None,
+        ),
+        (
+            "EW022050100000120BY1620162VS 3SW   2.28.0PR E-01INT10080GP 900x 900MS 13<boo,ros,emd>",
+            Produktkennung::EW,
+            -1,
+            10080,
+            900,
+            900,
+            None,
+            None,
+        ),
+        (
+            "RE022050100000120BY1620162VS 3SW   2.28.0PR E-02INT   5GP 900x 900MS 13<boo,ros,emd>",
+            Produktkennung::RE,
+            -2,
+            5,
+            900,
+            900,
+            None,
+            None,
+        ),
+        (
+            "W1022050100000120BY1620162VS 3SW   2.28.0PR E-01INT10080GP 900x 900MS 13<boo,ros,emd>",
+            Produktkennung::W1,
+            -1,
+            10080,
+            900,
+            900,
+            None,
+            None,
+        ),
+        (
+            "W2022050100000120BY1620162VS 3SW   2.28.0PR E-01INT20160GP 900x 900MS 13<boo,ros,emd>",
+            Produktkennung::W2,
+            -1,
+            20160,
+            900,
+            900,
+            None,
+            None,
+        ),
+        (
+            "W3022050100000120BY1620162VS 3SW   2.28.0PR E-01INT30240GP 900x 900MS 13<boo,ros,emd>",
+            Produktkennung::W3,
+            -1,
+            30240,
+            900,
+            900,
+            None,
+            None,
+        ),
+        (
+            "W4022050100000120BY1620162VS 3SW   2.28.0PR E-01INT40320GP 900x 900MS 13<boo,ros,emd>",
+            Produktkennung::W4,
+            -1,
+            40320,
+            900,
+            900,
+            None,
+            None,
+        ),
+        (
+            "YH022050100000120BY1980164VS 3SW   2.28.0PR E-02INT   5U0GP1100x 900MS 13<boo,ros,emd>",
+            Produktkennung::YH,
+            -2,
+            5,
+            1100,
+            900,
+            None,
+            None,
+        ),
+    ];
+
+    #[test]
+    fn samples() {
+        for (
+            input,
+            produktkennung,
+            precision,
+            interval_duration,
+            rows,
+            columns,
+            forecast_time,
+            quantification,
+        ) in SAMPLES
+        {
+            let header = Header::new(input).unwrap_or_else(|e| panic!("{input}: {e:?}"));
+            assert_eq!(&header.produktkennung, produktkennung, "{input}");
+            assert_eq!(&header.precision, precision, "{input}");
+            assert_eq!(&header.interval_duration, interval_duration, "{input}");
+            assert_eq!(
+                header.dimension,
+                Dimension {
+                    rows: *rows,
+                    columns: *columns
+                },
+                "{input}"
+            );
+            assert_eq!(&header.forecast_time, forecast_time, "{input}");
+            assert_eq!(&header.quantification, quantification, "{input}");
+            assert_eq!(header.software_version, "2.28.0", "{input}");
+        }
+    }
+
+    #[test]
+    fn rx_one_byte() {
+        let header = Header::new(SAMPLES[1].0).unwrap();
+        assert_eq!(header.product_length, 810134);
+        assert_eq!(header.produktkennung.bytes_per_value(), 1);
+        assert_eq!(Produktkennung::RW.bytes_per_value(), 2);
+    }
+
+    #[test]
+    fn not_tested() {
+        let input =
+            "XY022050100000120BY1620162VS 3SW   2.28.0PR E-01INT  60GP 900x 900MS 13<boo,ros,emd>";
+        let header = Header::new(input).unwrap();
+        assert_eq!(
+            header.produktkennung,
+            Produktkennung::NotTested("XY".to_string())
+        );
+
+        assert_eq!(header.unit(), None);
+
+        let input =
+            "RW022050100000120BY1620162VS 3SW   2.28.0PR E-03INT  60GP 900x 900MS 13<boo,ros,emd>";
+        let header = Header::new(input).unwrap();
+        assert_eq!(header.precision, -3);
+        assert_eq!(header.unit(), Some(Unit::Mm));
+        assert!(header.to_string().contains("PR E-03INT"));
+
+        let input =
+            "RW022050100000120BY1620162VS 3SW   2.28.0PR E-x1INT  60GP 900x 900MS 13<boo,ros,emd>";
+        assert!(Header::new(input).is_err());
+    }
+
+    #[test]
+    fn display() {
+        let inputs = SAMPLES.iter().map(|s| s.0).chain([
+            "YW010000100000117BY1980164VS 3SW   2.18.3PR E-02INT   5U0GP1100x 900MF 00000000VR2017.002MS 69<boo,ros,emd,hnr,umd,pro,ess,fld,drs,neu,nhb,oft,eis,tur,isn,fbg,mem>",
+            "SF010050100000119BY1620267VS 3SW   2.21.0PR E-01INT1440GP 900x 900MS 70<asb,boo,ros,hnr,umd,pro,ess,fld,drs,neu,nhb,oft,eis,tur,isn,fbg,mem> ST120<asb 24,boo 24,drs 24,eis 24,ess 24,fbg 24,fld 24,hnr 24,isn 24,mem 24,neu 24,nhb 24,oft 24,pro 24,ros 24,tur 24,umd 24>",
+        ]);
+        for input in inputs {
+            let header = Header::new(input).unwrap();
+            assert_eq!(Header::new(&header.to_string()).unwrap(), header);
+        }
+        // the padding of the samples is kept
+        assert_eq!(Header::new(SAMPLES[4].0).unwrap().to_string(), SAMPLES[4].0);
+    }
 }
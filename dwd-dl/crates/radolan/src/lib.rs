@@ -1,11 +1,20 @@
 use std::str::Utf8Error;
 
-use header::Header;
+use header::{Dimension, Header, Produktkennung};
+use raster::Raster;
 use record::Record;
+use reflectivity::ZR;
 use thiserror::Error;
 
+pub mod encode;
+pub mod export;
 pub mod header;
+pub mod projection;
+pub mod raster;
+#[cfg(feature = "mmap")]
+pub mod reader;
 pub mod record;
+pub mod reflectivity;
 
 const END_OF_TEXT: u8 = 0x03; // ETX
 
@@ -23,17 +32,45 @@ impl<'a> Radolan<'a> {
             .ok_or(RadolanReadError::EndOfTextMissing)?;
 
         let header_str = std::str::from_utf8(&file[..header_end])
-            .map_err(|err| RadolanReadError::Utf8Error(err))?
+            .map_err(RadolanReadError::Utf8Error)?
             .to_string();
+        let header =
+            Header::new(header_str.as_str()).map_err(RadolanReadError::HeaderParseError)?;
 
-        // check if long enough
-
-        Ok(Radolan {
+        let radolan = Radolan {
             data: file,
             header_end,
-            header: Header::new(header_str.as_str())
-                .map_err(|err| RadolanReadError::HeaderParseError(err))?,
-        })
+            header,
+        };
+        let expected = radolan.data_end();
+        if file.len() < expected {
+            return Err(RadolanReadError::Truncated {
+                expected,
+                actual: file.len(),
+            });
+        }
+        Ok(radolan)
+    }
+
+    /// end of the binary part, `header + ETX + rows * columns * bytes per value`
+    fn data_end(&self) -> usize {
+        let Dimension { rows, columns } = self.header.dimension;
+        self.header_end
+            + 1
+            + rows as usize * columns as usize * self.header.produktkennung.bytes_per_value()
+    }
+
+    /// Checks the file against the product length of the header, e.g. to detect a damaged
+    /// download, the values aren't decoded
+    pub fn validate(&self) -> Result<(), RadolanReadError> {
+        let expected = self.header.product_length as usize;
+        if self.data.len() < expected {
+            return Err(RadolanReadError::Truncated {
+                expected,
+                actual: self.data.len(),
+            });
+        }
+        Ok(())
     }
 
     pub fn get_point(&self, row: u16, column: u16) -> Result<Record, (u8, u16)> {
@@ -47,14 +84,104 @@ impl<'a> Radolan<'a> {
         let column = column as usize;
         // data set is beginning with the point at the bottom left, but we want to index from top left
         let row = self.header.dimension.rows as usize - 1 - row;
-        let record_width = 2;
+        let record_width = self.header.produktkennung.bytes_per_value();
         let offset = (row * self.header.dimension.columns as usize + column) * record_width
             + self.header_end
             + 1;
         let offset_end = offset + record_width;
 
-        let record = &self.data[offset..offset_end];
-        Ok(Record::parse(record)?)
+        let record = self.data.get(offset..offset_end).ok_or((0, 0))?;
+        match record_width {
+            1 => Ok(Record::parse_rvp6(record[0])),
+            _ => Ok(Record::parse(record)),
+        }
+    }
+
+    /// Records of a row from the left, decoded while iterating without copying the row
+    pub fn row_records(
+        &self,
+        row: u16,
+    ) -> Result<impl Iterator<Item = Result<Record, RadolanReadError>> + '_, RadolanReadError> {
+        let Dimension { rows, columns } = self.header.dimension;
+        if row >= rows {
+            return Err(RadolanReadError::OutOfGrid { row, column: 0 });
+        }
+        let width = self.header.produktkennung.bytes_per_value();
+        let length = columns as usize * width;
+        // data set is beginning with the row at the bottom
+        let start = self.header_end + 1 + (rows - 1 - row) as usize * length;
+        let bytes = &self.data[start..start + length];
+        Ok(bytes.chunks_exact(width).map(move |record| match width {
+            1 => Ok(Record::parse_rvp6(record[0])),
+            _ => Ok(Record::parse(record)),
+        }))
+    }
+
+    /// Records of `(row, column)` points in the order of `points`
+    pub fn points<'p>(
+        &'p self,
+        points: &'p [(u16, u16)],
+    ) -> impl Iterator<Item = Result<Record, RadolanReadError>> + 'p {
+        points.iter().map(|&(row, column)| {
+            self.get_point(row, column)
+                .map_err(|_| RadolanReadError::OutOfGrid { row, column })
+        })
+    }
+
+    /// Decodes the whole composite, errors and clutter become `nodata`
+    pub fn raster(&self, nodata: f32) -> Result<Raster, RadolanReadError> {
+        let Dimension { rows, columns } = self.header.dimension;
+        let mut values = Vec::with_capacity(rows as usize * columns as usize);
+        for row in 0..rows {
+            for column in 0..columns {
+                let record = self
+                    .get_point(row, column)
+                    .map_err(|_| RadolanReadError::OutOfGrid { row, column })?;
+                values.push(record.value(self.header.precision).unwrap_or(nodata));
+            }
+        }
+        Ok(Raster {
+            rows,
+            columns,
+            values,
+            nodata,
+        })
+    }
+
+    /// Reflectivity in dBZ of one byte products (RX, WX), `None` for clutter and cells without data
+    pub fn dbz(&self, row: u16, column: u16) -> Result<Option<f32>, RadolanReadError> {
+        if self.header.produktkennung.bytes_per_value() != 1 {
+            return Err(RadolanReadError::NotReflectivity(
+                self.header.produktkennung.clone(),
+            ));
+        }
+        let record = self
+            .get_point(row, column)
+            .map_err(|_| RadolanReadError::OutOfGrid { row, column })?;
+        Ok(match record.flags.is_empty() {
+            true => Some(reflectivity::rvp6_to_dbz(record.value as u8)),
+            false => None,
+        })
+    }
+
+    /// Rain rates in mm/h of a reflectivity composite, clutter and cells without data become `nodata`
+    pub fn rain_rate_raster(&self, zr: ZR, nodata: f32) -> Result<Raster, RadolanReadError> {
+        let Dimension { rows, columns } = self.header.dimension;
+        let mut values = Vec::with_capacity(rows as usize * columns as usize);
+        for row in 0..rows {
+            for column in 0..columns {
+                values.push(match self.dbz(row, column)? {
+                    Some(dbz) => zr.rain_rate(dbz),
+                    None => nodata,
+                });
+            }
+        }
+        Ok(Raster {
+            rows,
+            columns,
+            values,
+            nodata,
+        })
     }
 
     pub fn header(&self) -> &header::Header {
@@ -70,4 +197,63 @@ pub enum RadolanReadError {
     Utf8Error(Utf8Error),
     #[error("Header is not Valid UTF-8")]
     HeaderParseError(header::HeaderParseError),
+    #[error("row {row}, column {column} is outside of the grid")]
+    OutOfGrid { row: u16, column: u16 },
+    #[error("file has {actual} bytes, but {expected} are expected")]
+    Truncated { expected: usize, actual: usize },
+    #[error("{0:?} is no reflectivity product")]
+    NotReflectivity(Produktkennung),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the product length matches the file
+    fn composite() -> Vec<u8> {
+        let header =
+            "RW010050100000322BY1620085VS 3SW   2.18.3PR E-01INT  60GP 900x 900MS 13<boo,ros,emd>";
+        let mut file = header.as_bytes().to_vec();
+        file.push(END_OF_TEXT);
+        file.resize(file.len() + 900 * 900 * 2, 0);
+        file
+    }
+
+    #[test]
+    fn test_validate() {
+        let file = composite();
+        assert_eq!(file.len(), 1620085);
+        Radolan::new(&file).unwrap().validate().unwrap();
+
+        let truncated = &file[..file.len() - 1];
+        assert!(matches!(
+            Radolan::new(truncated),
+            Err(RadolanReadError::Truncated {
+                expected: 1620085,
+                actual: 1620084
+            })
+        ));
+
+        let radolan = Radolan::new(&file).unwrap();
+        assert_eq!(radolan.row_records(899).unwrap().count(), 900);
+        assert!(radolan.row_records(900).is_err());
+        assert_eq!(radolan.points(&[(0, 0), (899, 899)]).count(), 2);
+
+        // several flags of a value are valid, the values aren't decoded
+        let mut flagged = file.clone();
+        let start = flagged.len() - 900 * 900 * 2;
+        flagged[start + 1] = 0x90;
+        let radolan = Radolan::new(&flagged).unwrap();
+        radolan.validate().unwrap();
+        assert!(radolan.get_point(899, 0).unwrap().flag().is_nodata());
+
+        // larger product length than the file
+        let mut longer = file.clone();
+        longer[19..22].copy_from_slice(b"199");
+        let radolan = Radolan::new(&longer).unwrap();
+        assert!(matches!(
+            radolan.validate(),
+            Err(RadolanReadError::Truncated { .. })
+        ));
+    }
 }
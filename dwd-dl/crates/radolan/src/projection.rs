@@ -0,0 +1,165 @@
+//! Polar stereographic projection of the RADOLAN grids
+//!
+//! The projection plane touches a sphere (R = 6370.04 km) at 60°N, the y axis points along 10°E.
+//! Rows are counted from the top (north), like in [`crate::Radolan::get_point`].
+
+use crate::header::Dimension;
+
+const EARTH_RADIUS: f64 = 6370.04;
+const STANDARD_PARALLEL: f64 = 60.0;
+const CENTRAL_MERIDIAN: f64 = 10.0;
+
+/// Projects WGS84 degrees onto the stereographic plane in km
+pub fn to_stereographic(lat: f64, lon: f64) -> (f64, f64) {
+    let (phi, lambda) = (lat.to_radians(), (lon - CENTRAL_MERIDIAN).to_radians());
+    let m = (1.0 + STANDARD_PARALLEL.to_radians().sin()) / (1.0 + phi.sin());
+    let x = EARTH_RADIUS * m * phi.cos() * lambda.sin();
+    let y = -EARTH_RADIUS * m * phi.cos() * lambda.cos();
+    (x, y)
+}
+
+/// Inverse of [`to_stereographic`], returns `(lat, lon)` in degrees
+pub fn from_stereographic(x: f64, y: f64) -> (f64, f64) {
+    let k = (EARTH_RADIUS * (1.0 + STANDARD_PARALLEL.to_radians().sin())).powi(2);
+    let r2 = x * x + y * y;
+    let lat = ((k - r2) / (k + r2)).asin().to_degrees();
+    let lon = x.atan2(-y).to_degrees() + CENTRAL_MERIDIAN;
+    (lat, lon)
+}
+
+/// A RADOLAN grid with 1 km cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    pub rows: u16,
+    pub columns: u16,
+    /// lower left corner on the stereographic plane in km
+    origin: (f64, f64),
+}
+
+impl Grid {
+    /// national composite, e.g. RW and SF
+    pub const NATIONAL: Grid = Grid {
+        rows: 900,
+        columns: 900,
+        origin: (-523.4622, -4658.645),
+    };
+
+    /// extended national composite, e.g. YW and the 2017 reprocessing
+    pub const EXTENDED: Grid = Grid {
+        rows: 1100,
+        columns: 900,
+        origin: (-443.4622, -4758.645),
+    };
+
+    /// `None` if the dimension isn't one of the known grids
+    pub fn from_dimension(dimension: &Dimension) -> Option<Self> {
+        [Self::NATIONAL, Self::EXTENDED]
+            .into_iter()
+            .find(|grid| grid.rows == dimension.rows && grid.columns == dimension.columns)
+    }
+
+    /// `(row, column)` of the cell containing the position, `None` if it's outside of the grid
+    pub fn cell(&self, lat: f64, lon: f64) -> Option<(u16, u16)> {
+        let (x, y) = to_stereographic(lat, lon);
+        let column = (x - self.origin.0).floor();
+        let row_from_bottom = (y - self.origin.1).floor();
+        if !(0.0..self.columns as f64).contains(&column)
+            || !(0.0..self.rows as f64).contains(&row_from_bottom)
+        {
+            return None;
+        }
+        Some((self.rows - 1 - row_from_bottom as u16, column as u16))
+    }
+
+    /// lower left corner on the stereographic plane in km
+    pub fn lower_left(&self) -> (f64, f64) {
+        self.origin
+    }
+
+    /// `(column, row)` in fractional cells, the cell `(r, c)` spans `r..r + 1` and `c..c + 1`
+    pub fn position(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (x, y) = to_stereographic(lat, lon);
+        (x - self.origin.0, self.rows as f64 - (y - self.origin.1))
+    }
+
+    /// `(lat, lon)` of the center of a cell
+    pub fn center(&self, row: u16, column: u16) -> (f64, f64) {
+        let row_from_bottom = (self.rows - 1 - row) as f64;
+        let x = self.origin.0 + column as f64 + 0.5;
+        let y = self.origin.1 + row_from_bottom + 0.5;
+        from_stereographic(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64), tolerance: f64) {
+        assert!(
+            (actual.0 - expected.0).abs() < tolerance && (actual.1 - expected.1).abs() < tolerance,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_corners() {
+        // corners from the RADOLAN format description
+        let grid = Grid::NATIONAL;
+        assert_close(
+            from_stereographic(grid.origin.0, grid.origin.1),
+            (46.9526, 3.5889),
+            1e-3,
+        );
+        let (x, y) = (grid.origin.0 + 900.0, grid.origin.1 + 900.0);
+        assert_close(from_stereographic(x, y), (54.7405, 15.7208), 1e-3);
+
+        let grid = Grid::EXTENDED;
+        assert_close(
+            from_stereographic(grid.origin.0, grid.origin.1),
+            (46.1929, 4.6759),
+            1e-3,
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (x, y) = to_stereographic(52.52, 13.405);
+        assert_close(from_stereographic(x, y), (52.52, 13.405), 1e-9);
+
+        for grid in [Grid::NATIONAL, Grid::EXTENDED] {
+            let (row, column) = grid.cell(52.52, 13.405).unwrap();
+            let (lat, lon) = grid.center(row, column);
+            assert_eq!(grid.cell(lat, lon), Some((row, column)));
+            assert_close((lat, lon), (52.52, 13.405), 0.01);
+            assert_close(
+                grid.position(lat, lon),
+                (column as f64 + 0.5, row as f64 + 0.5),
+                1e-6,
+            );
+        }
+    }
+
+    #[test]
+    fn test_outside() {
+        assert_eq!(Grid::NATIONAL.cell(40.0, 10.0), None);
+        assert_eq!(Grid::NATIONAL.cell(52.0, 30.0), None);
+        // the top left cell
+        let (lat, lon) = Grid::NATIONAL.center(0, 0);
+        assert_eq!(Grid::NATIONAL.cell(lat, lon), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_from_dimension() {
+        let dimension = Dimension {
+            rows: 1100,
+            columns: 900,
+        };
+        assert_eq!(Grid::from_dimension(&dimension), Some(Grid::EXTENDED));
+        let dimension = Dimension {
+            rows: 1200,
+            columns: 1100,
+        };
+        assert_eq!(Grid::from_dimension(&dimension), None);
+    }
+}
@@ -0,0 +1,84 @@
+/// Decoded values of a whole composite, row by row from the top (north)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Raster {
+    pub rows: u16,
+    pub columns: u16,
+    pub values: Vec<f32>,
+    /// value of cells without data
+    pub nodata: f32,
+}
+
+impl Raster {
+    /// `None` for cells without data or outside of the raster
+    pub fn get(&self, row: u16, column: u16) -> Option<f32> {
+        if row >= self.rows || column >= self.columns {
+            return None;
+        }
+        let value = self.values[row as usize * self.columns as usize + column as usize];
+        (value != self.nodata).then_some(value)
+    }
+
+    /// values of a row, counted from the top
+    pub fn row(&self, row: u16) -> &[f32] {
+        let columns = self.columns as usize;
+        &self.values[row as usize * columns..(row as usize + 1) * columns]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{reflectivity::ZR, Radolan};
+
+    #[test]
+    fn test_raster() {
+        let header =
+            "RW010050100000322BY1620130VS 3SW   2.18.3PR E-01INT  60GP 900x 900MS 13<boo,ros,emd>";
+        let mut file = header.as_bytes().to_vec();
+        file.push(0x03);
+        let data_start = file.len();
+        file.resize(data_start + 900 * 900 * 2, 0);
+        // the data starts with the bottom left cell
+        file[data_start..data_start + 2].copy_from_slice(&[15, 0]);
+        file[data_start + 2..data_start + 4].copy_from_slice(&[0, 0b0010_0000]);
+
+        let raster = Radolan::new(&file).unwrap().raster(-1.0).unwrap();
+        assert_eq!(raster.values.len(), 900 * 900);
+        assert_eq!(raster.get(899, 0), Some(1.5));
+        assert_eq!(raster.get(899, 1), None);
+        assert_eq!(raster.row(899)[1], -1.0);
+        assert_eq!(raster.get(0, 0), Some(0.0));
+        assert_eq!(raster.get(900, 0), None);
+
+        // interpolated and error
+        file[data_start + 1] = 0b0011_0000;
+        let raster = Radolan::new(&file).unwrap().raster(-1.0).unwrap();
+        assert_eq!(raster.get(899, 0), None);
+    }
+
+    #[test]
+    fn test_rain_rate_raster() {
+        let header =
+            "RX022050100000120BY 810134VS 3SW   2.28.0PR E+00INT   5GP 900x 900MS 13<boo,ros,emd>";
+        let mut file = header.as_bytes().to_vec();
+        file.push(0x03);
+        let data_start = file.len();
+        file.resize(data_start + 900 * 900, 0);
+        // 23 dBZ, clutter and no data
+        file[data_start..data_start + 3].copy_from_slice(&[111, 249, 250]);
+
+        let radolan = Radolan::new(&file).unwrap();
+        assert_eq!(radolan.dbz(899, 0).unwrap(), Some(23.0));
+        assert_eq!(radolan.dbz(899, 1).unwrap(), None);
+        let raster = radolan.rain_rate_raster(ZR::MarshallPalmer, -1.0).unwrap();
+        assert!((raster.get(899, 0).unwrap() - 0.9976).abs() < 1e-3);
+        assert_eq!(raster.get(899, 1), None);
+        assert_eq!(raster.get(899, 2), None);
+
+        let rw =
+            "RW010050100000322BY1620130VS 3SW   2.18.3PR E-01INT  60GP 900x 900MS 13<boo,ros,emd>";
+        let mut file = rw.as_bytes().to_vec();
+        file.push(0x03);
+        file.resize(file.len() + 900 * 900 * 2, 0);
+        assert!(Radolan::new(&file).unwrap().dbz(0, 0).is_err());
+    }
+}
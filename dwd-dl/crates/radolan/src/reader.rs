@@ -0,0 +1,169 @@
+//! Memory mapped composites, e.g. of a local mirror, the bytes are only read when accessed
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use memmap2::Mmap;
+
+use crate::{Radolan, RadolanReadError};
+
+fn map(path: &Path) -> io::Result<Mmap> {
+    let file = fs::File::open(path)?;
+    // SAFETY: the file must not be modified while it is mapped, as for every reader of a mirror
+    unsafe { Mmap::map(&file) }
+}
+
+/// A composite in a memory mapped file
+pub struct MappedRadolan {
+    mmap: Mmap,
+}
+
+impl MappedRadolan {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            mmap: map(path.as_ref())?,
+        })
+    }
+
+    /// Parses the header, the values are decoded on access
+    pub fn radolan(&self) -> Result<Radolan<'_>, RadolanReadError> {
+        Radolan::new(&self.mmap)
+    }
+}
+
+/// Uncompressed composites of a directory sorted by name, each file is mapped when it is reached
+pub fn read_dir(
+    path: impl AsRef<Path>,
+) -> io::Result<impl Iterator<Item = io::Result<(PathBuf, MappedRadolan)>>> {
+    let mut paths = fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.retain(|p| p.is_file());
+    paths.sort_unstable();
+    Ok(paths
+        .into_iter()
+        .map(|path| MappedRadolan::open(&path).map(|file| (path, file))))
+}
+
+/// A memory mapped uncompressed tar archive of composites, the members aren't copied
+#[cfg(feature = "tar")]
+pub struct MappedTar {
+    mmap: Mmap,
+    /// name and byte range of each member
+    members: Vec<(String, std::ops::Range<usize>)>,
+}
+
+#[cfg(feature = "tar")]
+impl MappedTar {
+    /// Maps the archive and lists its members, only the tar headers are read
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mmap = map(path.as_ref())?;
+        let mut members = Vec::new();
+        for entry in tar::Archive::new(&mmap[..]).entries()? {
+            let entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let start = entry.raw_file_position() as usize;
+            let end = start + entry.size() as usize;
+            if end > mmap.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "tar member exceeds the archive",
+                ));
+            }
+            let name = entry.path()?.to_string_lossy().to_string();
+            members.push((name, start..end));
+        }
+        Ok(Self { mmap, members })
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Members in archive order, the header of each is parsed when it is reached
+    pub fn composites(
+        &self,
+    ) -> impl Iterator<Item = (&str, Result<Radolan<'_>, RadolanReadError>)> {
+        self.members
+            .iter()
+            .map(|(name, range)| (name.as_str(), Radolan::new(&self.mmap[range.clone()])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode::write_composite, header::Header, record::Record};
+
+    fn composite(value: u16) -> Vec<u8> {
+        let header = Header::new(
+            "RW010050100000322BY      0VS 3SW   2.18.3PR E-01INT  60GP 900x 900MS 13<boo,ros,emd>",
+        )
+        .unwrap();
+        let mut file = Vec::new();
+        write_composite(&header, &vec![Record::normal(value); 900 * 900], &mut file).unwrap();
+        file
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("radolan-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_read_dir() {
+        let dir = temp_dir("dir");
+        fs::write(dir.join("b"), composite(2)).unwrap();
+        fs::write(dir.join("a"), composite(1)).unwrap();
+
+        let values = read_dir(&dir)
+            .unwrap()
+            .map(|file| {
+                let (_, file) = file.unwrap();
+                let radolan = file.radolan().unwrap();
+                let mut row = radolan.row_records(10).unwrap();
+                row.next().unwrap().unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![Record::normal(1), Record::normal(2)]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn test_tar() {
+        let dir = temp_dir("tar");
+        let path = dir.join("composites.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&path).unwrap());
+        for (name, value) in [("first", 1), ("second", 2)] {
+            let data = composite(value);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, &data[..]).unwrap();
+        }
+        builder.into_inner().unwrap();
+
+        let tar = MappedTar::open(&path).unwrap();
+        assert_eq!(tar.names().collect::<Vec<_>>(), vec!["first", "second"]);
+        let points = tar
+            .composites()
+            .map(|(_, radolan)| {
+                let radolan = radolan.unwrap();
+                radolan.validate().unwrap();
+                let mut points = radolan.points(&[(5, 5)]);
+                points.next().unwrap().unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(points, vec![Record::normal(1), Record::normal(2)]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
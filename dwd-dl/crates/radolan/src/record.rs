@@ -1,35 +1,218 @@
-#[derive(Debug)]
-pub enum Record {
-    Normal(u16),       // 0000
-    Interpolated(u16), // 0001 ;Hail if Produkt: RE
-    Error(u16),        // 0010
-    Neg(u16),          // 0100
-    Scope(u16),        // 1000
+use std::ops::BitOr;
+
+/// Value of a cell with its flag bits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Record {
+    /// 12 bit value, RVP6 units in one byte products
+    pub value: u16,
+    pub flags: Flags,
+}
+
+/// Flag bits (upper nibble) of a two byte value, several can be set at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Flags(u8);
+
+impl Flags {
+    pub const NONE: Flags = Flags(0b0000);
+    /// hail in RE
+    pub const INTERPOLATED: Flags = Flags(0b0001);
+    pub const ERROR: Flags = Flags(0b0010);
+    pub const NEGATIVE: Flags = Flags(0b0100);
+    /// RVP6 249 in one byte products
+    pub const CLUTTER: Flags = Flags(0b1000);
+
+    pub fn from_bits(bits: u8) -> Self {
+        Flags(bits & 0b1111)
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(&self, other: Flags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for Flags {
+    type Output = Flags;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Flags(self.0 | rhs.0)
+    }
+}
+
+/// Meaning of the flag bits of a value, the most severe one if several are set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flag {
+    Normal,
+    Interpolated,
+    Error,
+    Negative,
+    Clutter,
+}
+
+impl Flag {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Flag::Normal => "normal",
+            Flag::Interpolated => "interpolated",
+            Flag::Error => "error",
+            Flag::Negative => "negative",
+            Flag::Clutter => "clutter",
+        }
+    }
+
+    /// the value carries no measurement
+    pub fn is_nodata(&self) -> bool {
+        matches!(self, Flag::Error | Flag::Clutter)
+    }
 }
 
 impl Record {
-    pub(super) fn parse(bytes: &[u8]) -> Result<Self, (u8, u16)> {
-        let data_type = bytes[1] >> 4;
+    pub fn new(value: u16, flags: Flags) -> Self {
+        Record { value, flags }
+    }
+
+    /// value without flags
+    pub fn normal(value: u16) -> Self {
+        Record::new(value, Flags::NONE)
+    }
+
+    pub(super) fn parse(bytes: &[u8]) -> Self {
         let value = ((bytes[1] & 0b0000_1111) as u16) << 8 | bytes[0] as u16;
-        let record = match data_type {
-            0b0000 => Record::Normal(value),
-            0b0001 => Record::Interpolated(value),
-            0b0010 => Record::Error(value),
-            0b0100 => Record::Neg(value),
-            0b1000 => Record::Scope(value),
-            _ => return Err((data_type, value)),
+        Record::new(value, Flags::from_bits(bytes[1] >> 4))
+    }
+
+    /// one byte products (RX, WX), 249 is clutter and 250 no data
+    pub(super) fn parse_rvp6(byte: u8) -> Self {
+        match byte {
+            249 => Record::new(byte as u16, Flags::CLUTTER),
+            250 => Record::new(byte as u16, Flags::ERROR),
+            v => Record::normal(v as u16),
+        }
+    }
+
+    /// inverse of [`Record::parse`], `None` if the value doesn't fit into 12 bits
+    pub(super) fn to_bytes(self) -> Option<[u8; 2]> {
+        if self.value > 0x0fff {
+            return None;
+        }
+        Some([
+            self.value as u8,
+            self.flags.bits() << 4 | (self.value >> 8) as u8,
+        ])
+    }
+
+    /// inverse of [`Record::parse_rvp6`], `None` for flags which don't exist in one byte products
+    pub(super) fn to_rvp6(self) -> Option<u8> {
+        match self.flags {
+            Flags::NONE if self.value < 249 => Some(self.value as u8),
+            Flags::CLUTTER => Some(249),
+            Flags::ERROR => Some(250),
+            _ => None,
+        }
+    }
+
+    pub fn flag(&self) -> Flag {
+        let flags = self.flags;
+        if flags.contains(Flags::ERROR) {
+            Flag::Error
+        } else if flags.contains(Flags::CLUTTER) {
+            Flag::Clutter
+        } else if flags.contains(Flags::NEGATIVE) {
+            Flag::Negative
+        } else if flags.contains(Flags::INTERPOLATED) {
+            Flag::Interpolated
+        } else {
+            Flag::Normal
+        }
+    }
+
+    /// `None` for errors and clutter
+    pub fn value(&self, precision: i8) -> Option<f32> {
+        match self.flag().is_nodata() {
+            true => None,
+            false => Some(self.default_f32(precision)),
+        }
+    }
+
+    /// errors and clutter are `-9`, `precision` is the exponent of the header
+    pub fn default_f32(&self, precision: i8) -> f32 {
+        // dividing keeps e.g. `15 * 10^-1` at exactly `1.5`
+        let scaled = match precision < 0 {
+            true => self.value as f32 / 10_f32.powi(-precision as i32),
+            false => self.value as f32 * 10_f32.powi(precision as i32),
         };
-        Ok(record)
+        match self.flag() {
+            Flag::Error | Flag::Clutter => -9_f32,
+            Flag::Negative => -scaled,
+            Flag::Normal | Flag::Interpolated => scaled,
+        }
     }
+}
 
-    pub fn default_f32(&self, precision: u8) -> f32 {
-        let precision = precision as f32;
-        match self {
-            Record::Normal(v) => *v as f32 / precision,
-            Record::Interpolated(v) => *v as f32 / precision,
-            Record::Error(_) => -9_f32,
-            Record::Neg(v) => -(*v as f32 / precision),
-            Record::Scope(v) => *v as f32 / precision,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags() {
+        let record = Record::parse(&[15, 0b0100_0000]);
+        assert_eq!(record.flag(), Flag::Negative);
+        assert_eq!(record.value(-1), Some(-1.5));
+
+        let record = Record::parse(&[15, 0b0001_0000]);
+        assert_eq!(record.flag(), Flag::Interpolated);
+        assert_eq!(record.value(-1), Some(1.5));
+
+        let record = Record::parse(&[0xc4, 0b0010_1001]);
+        assert_eq!(record.flag(), Flag::Error);
+        assert_eq!(record.value(-1), None);
+        assert_eq!(record.default_f32(-1), -9.0);
+
+        let record = Record::parse(&[0x2a, 0b1000_0000]);
+        assert_eq!(record, Record::new(42, Flags::CLUTTER));
+        assert_eq!(record.flag(), Flag::Clutter);
+        assert!(record.flag().is_nodata());
+        assert_eq!(record.value(-1), None);
+
+        // interpolated and clutter
+        let record = Record::parse(&[0x2a, 0b1001_0000]);
+        assert!(record.flags.contains(Flags::INTERPOLATED));
+        assert!(record.flags.contains(Flags::CLUTTER));
+        assert_eq!(record.flag(), Flag::Clutter);
+        assert_eq!(record.value(-1), None);
+
+        // interpolated negative value
+        let record = Record::parse(&[15, 0b0101_0000]);
+        assert_eq!(record.flags, Flags::INTERPOLATED | Flags::NEGATIVE);
+        assert_eq!(record.value(-1), Some(-1.5));
+
+        assert_eq!(Record::parse_rvp6(249).flag(), Flag::Clutter);
+        assert!(Record::parse_rvp6(249).flag().is_nodata());
+        assert_eq!(Record::parse_rvp6(100).value(0), Some(100.0));
+        assert_eq!(Record::normal(3).value(2), Some(300.0));
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        for bytes in [
+            [15, 0b0100_0000],
+            [0xc4, 0b0010_1001],
+            [0xff, 0b1000_1111],
+            [0x2a, 0b1001_0000],
+        ] {
+            assert_eq!(Record::parse(&bytes).to_bytes(), Some(bytes));
+        }
+        assert_eq!(Record::normal(0x1000).to_bytes(), None);
+        for byte in [0, 248, 249, 250] {
+            assert_eq!(Record::parse_rvp6(byte).to_rvp6(), Some(byte));
         }
+        assert_eq!(Record::new(1, Flags::NEGATIVE).to_rvp6(), None);
     }
 }
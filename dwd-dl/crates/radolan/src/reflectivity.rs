@@ -0,0 +1,65 @@
+//! Reflectivity of the one byte products (RX, WX) and its conversion to rain rates
+
+/// RVP6 units to dBZ
+pub fn rvp6_to_dbz(rvp6: u8) -> f32 {
+    rvp6 as f32 / 2.0 - 32.5
+}
+
+/// Z-R relation `Z = a * R^b`, Z in mm^6/m^3 and R in mm/h
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZR {
+    /// `Z = 200 * R^1.6`
+    MarshallPalmer,
+    /// RADOLAN: `Z = 125 * R^1.4` up to 36.5 dBZ, `Z = 200 * R^1.6` up to 44 dBZ, `Z = 77 * R^1.9` above
+    Dwd,
+    Custom {
+        a: f32,
+        b: f32,
+    },
+}
+
+impl ZR {
+    fn coefficients(&self, dbz: f32) -> (f32, f32) {
+        match self {
+            ZR::MarshallPalmer => (200.0, 1.6),
+            ZR::Dwd if dbz <= 36.5 => (125.0, 1.4),
+            ZR::Dwd if dbz <= 44.0 => (200.0, 1.6),
+            ZR::Dwd => (77.0, 1.9),
+            ZR::Custom { a, b } => (*a, *b),
+        }
+    }
+
+    /// rain rate in mm/h
+    pub fn rain_rate(&self, dbz: f32) -> f32 {
+        let (a, b) = self.coefficients(dbz);
+        (10_f32.powf(dbz / 10.0) / a).powf(1.0 / b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_dbz() {
+        assert_eq!(rvp6_to_dbz(0), -32.5);
+        assert_eq!(rvp6_to_dbz(65), 0.0);
+        assert_eq!(rvp6_to_dbz(155), 45.0);
+    }
+
+    #[test]
+    fn test_rain_rate() {
+        // Z = 200 is 1 mm/h for Marshall-Palmer
+        let dbz = 10.0 * 200_f32.log10();
+        assert_close(ZR::MarshallPalmer.rain_rate(dbz), 1.0);
+        assert_close(ZR::Custom { a: 200.0, b: 1.6 }.rain_rate(dbz), 1.0);
+        assert_close(ZR::Dwd.rain_rate(dbz), (200_f32 / 125.0).powf(1.0 / 1.4));
+
+        assert_close(ZR::Dwd.rain_rate(40.0), ZR::MarshallPalmer.rain_rate(40.0));
+        assert_close(ZR::Dwd.rain_rate(50.0), (1e5_f32 / 77.0).powf(1.0 / 1.9));
+    }
+}
@@ -18,7 +18,7 @@ fn cmp_radolan_file() {
             let (row, column) = (row as usize, column as usize);
 
             let value_ascii = ascii_vec[row][column];
-            let value_bin = point.default_f32(100);
+            let value_bin = point.default_f32(-2);
 
             // sometimes the DWD rounds up, sometimes down, so we have to check booth
             // also there is only one decimal place (like 0.1)
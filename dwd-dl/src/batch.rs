@@ -0,0 +1,467 @@
+//! Requests of several stations over the same timespan
+//!
+//! The stations are downloaded one after another with clones of one request, which share
+//! the progress, the cancellation and the directory listings.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use time::PrimitiveDateTime;
+
+use crate::{
+    dwd_source::Common,
+    error::{DwdError, Result},
+    products::{
+        climate::ClimateCommonRequestData, observation::ObservationCommonRequestData,
+        precipitation::PrecipitationCommonRequestData,
+    },
+    progress::ProgressUpdate,
+    stations::{StationCatalog, StationProduct},
+    util::{geo::LatLon, interval::Interval},
+};
+
+/// Stations of a batch request
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub enum StationSelection {
+    /// ids with or without leading zeros
+    Ids(Vec<String>),
+    /// every station of the product in the federal state, e.g. `Bayern`
+    State(String),
+    /// every station of the product inside the box spanned by the south west and the north east corner
+    BoundingBox {
+        south_west: LatLon,
+        north_east: LatLon,
+    },
+}
+
+impl StationSelection {
+    /// Station ids with 5 digits, the station list of `product` is only downloaded for regions
+    pub fn resolve(
+        &self,
+        product: StationProduct,
+        timespan: Interval<PrimitiveDateTime>,
+    ) -> Result<Vec<String>> {
+        let stations = match self {
+            StationSelection::Ids(ids) => {
+                let mut stations = Vec::new();
+                for id in ids.iter().map(|id| id.trim()).filter(|id| !id.is_empty()) {
+                    let id = format!("{:0>5}", id);
+                    if !stations.contains(&id) {
+                        stations.push(id);
+                    }
+                }
+                stations
+            }
+            _ => self.select(&StationCatalog::download(product)?, timespan),
+        };
+        match stations.is_empty() {
+            true => Err(DwdError::NoStations(format!("{:?}", self))),
+            false => Ok(stations),
+        }
+    }
+
+    /// Stations of the region with data in `timespan`, every listed station for [`StationSelection::Ids`]
+    pub fn select(
+        &self,
+        catalog: &StationCatalog,
+        timespan: Interval<PrimitiveDateTime>,
+    ) -> Vec<String> {
+        let available = |availability: Interval<_>| {
+            Interval::<PrimitiveDateTime>::from(availability)
+                .intersection(&timespan)
+                .is_some()
+        };
+        let stations: Vec<_> = match self {
+            StationSelection::Ids(ids) => ids.iter().filter_map(|id| catalog.by_id(id)).collect(),
+            StationSelection::State(state) => catalog
+                .stations()
+                .iter()
+                .filter(|s| s.state.eq_ignore_ascii_case(state.trim()))
+                .filter(|s| available(s.availability))
+                .collect(),
+            StationSelection::BoundingBox {
+                south_west,
+                north_east,
+            } => catalog
+                .in_bounding_box(*south_west, *north_east)
+                .filter(|s| available(s.availability))
+                .collect(),
+        };
+        stations.into_iter().map(|s| s.id.clone()).collect()
+    }
+}
+
+/// Output of a batch request
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub enum BatchOutput {
+    /// one file, the rows of all stations below one header
+    #[default]
+    Combined,
+    /// one file per station, see [`station_path`]
+    PerStation,
+}
+
+/// A request of products with one file per station
+pub trait StationRequest: Common + Clone {
+    fn station(&self) -> &str;
+
+    /// the same request for another station
+    fn with_station(&self, station: &str) -> Self {
+        let mut request = self.clone();
+        *request.station_mut() = station.to_string();
+        request
+    }
+
+    fn station_mut(&mut self) -> &mut String;
+}
+
+impl StationRequest for ClimateCommonRequestData {
+    fn station(&self) -> &str {
+        &self.station
+    }
+
+    fn station_mut(&mut self) -> &mut String {
+        &mut self.station
+    }
+}
+
+impl StationRequest for PrecipitationCommonRequestData {
+    fn station(&self) -> &str {
+        &self.station
+    }
+
+    fn station_mut(&mut self) -> &mut String {
+        &mut self.station
+    }
+}
+
+impl StationRequest for ObservationCommonRequestData {
+    fn station(&self) -> &str {
+        &self.station
+    }
+
+    fn station_mut(&mut self) -> &mut String {
+        &mut self.station
+    }
+}
+
+/// Writes the output of every station with `write` into `writer`,
+/// the header line is only kept for the first station
+///
+/// A station which fails is left out and its error returned in the list of skipped stations,
+/// the batch only fails if it is cancelled, the output can't be written or every station fails.
+pub fn write_combined<R: StationRequest>(
+    writer: &mut dyn Write,
+    request: &R,
+    stations: &[String],
+    mut write: impl FnMut(&mut dyn Write, &R) -> Result<()>,
+) -> Result<Vec<DwdError>> {
+    let mut failed = Vec::new();
+    let mut header = true;
+    for i in 0..stations.len() {
+        // buffered, so a failed station doesn't leave a part of its rows
+        let mut buffer = Vec::new();
+        if let Err(err) = write(&mut buffer, &station_request(request, stations, i)) {
+            failed.push(skip_station(&stations[i], err)?);
+            continue;
+        }
+        match header {
+            true => writer.write_all(&buffer)?,
+            false => SkipFirstLine::new(writer).write_all(&buffer)?,
+        }
+        header = false;
+    }
+    skipped(failed, stations.len())
+}
+
+/// Writes the output of every station with `write` into its own file next to `path`
+///
+/// Failed stations are skipped like in [`write_combined`], their files are removed.
+pub fn write_per_station<R: StationRequest>(
+    path: &Path,
+    request: &R,
+    stations: &[String],
+    mut write: impl FnMut(&mut dyn Write, &R) -> Result<()>,
+) -> Result<(Vec<PathBuf>, Vec<DwdError>)> {
+    let mut paths = Vec::new();
+    let mut failed = Vec::new();
+    for (i, station) in stations.iter().enumerate() {
+        let path = station_path(path, station);
+        let mut writer = io::BufWriter::new(fs::File::create(&path)?);
+        match write(&mut writer, &station_request(request, stations, i)) {
+            Ok(()) => {
+                writer.flush()?;
+                paths.push(path);
+            }
+            Err(err) => {
+                drop(writer);
+                fs::remove_file(&path)?;
+                failed.push(skip_station(station, err)?);
+            }
+        }
+    }
+    Ok((paths, skipped(failed, stations.len())?))
+}
+
+/// The error of a skipped station, a cancellation ends the whole batch
+fn skip_station(station: &str, err: DwdError) -> Result<DwdError> {
+    match err {
+        DwdError::Cancelled => Err(err),
+        err => Ok(DwdError::station(station, err)),
+    }
+}
+
+/// The errors of the skipped stations, the error of the first station if none succeeded
+fn skipped(mut failed: Vec<DwdError>, stations: usize) -> Result<Vec<DwdError>> {
+    match failed.len() == stations && stations > 0 {
+        true => Err(failed.swap_remove(0)),
+        false => Ok(failed),
+    }
+}
+
+/// The request of the `index`th station, which is reported as the next one
+fn station_request<R: StationRequest>(request: &R, stations: &[String], index: usize) -> R {
+    request.common().progress.report(ProgressUpdate::Station {
+        station: stations[index].clone(),
+        index,
+        count: stations.len(),
+    });
+    request.with_station(&stations[index])
+}
+
+/// `data.csv` to `data_00044.csv`
+pub fn station_path(path: &Path, station: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}_{}.{}", stem, station, extension.to_string_lossy()),
+        None => format!("{}_{}", stem, station),
+    };
+    path.with_file_name(name)
+}
+
+/// Drops everything up to and including the first line break
+struct SkipFirstLine<'a> {
+    inner: &'a mut dyn Write,
+    skipping: bool,
+}
+
+impl<'a> SkipFirstLine<'a> {
+    fn new(inner: &'a mut dyn Write) -> Self {
+        Self {
+            inner,
+            skipping: true,
+        }
+    }
+}
+
+impl Write for SkipFirstLine<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.skipping {
+            return self.inner.write(buf);
+        }
+        if let Some(end) = buf.iter().position(|&b| b == b'\n') {
+            self.skipping = false;
+            self.inner.write_all(&buf[end + 1..])?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use crate::{
+        dwd_source::CommonRequestData, products::climate::ClimateResolution, progress::Progress,
+        stations::StationCatalog,
+    };
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct Request(String, CommonRequestData);
+
+    impl Request {
+        fn new(progress: Progress) -> Self {
+            let common = CommonRequestData {
+                timespan: Interval {
+                    start: datetime!(2020-01-01 00:00),
+                    end: datetime!(2021-01-01 00:00),
+                },
+                progress,
+                cancel: Default::default(),
+                listings: Default::default(),
+            };
+            Self(String::new(), common)
+        }
+    }
+
+    impl Common for Request {
+        fn common(&self) -> &CommonRequestData {
+            &self.1
+        }
+    }
+
+    impl StationRequest for Request {
+        fn station(&self) -> &str {
+            &self.0
+        }
+
+        fn station_mut(&mut self) -> &mut String {
+            &mut self.0
+        }
+    }
+
+    #[test]
+    fn test_write_combined() {
+        let stations = ["00044".to_string(), "00073".to_string()];
+        let (s, r) = crossbeam_channel::unbounded();
+        let request = Request::new(Progress::new(s));
+        let mut out = Vec::new();
+        let skipped = write_combined(&mut out, &request, &stations, |w, r| {
+            write!(w, "STATIONS_ID\tVALUE\n{}\t1\n", r.station())?;
+            writeln!(w, "{}\t2", r.station())?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "STATIONS_ID\tVALUE\n00044\t1\n00044\t2\n00073\t1\n00073\t2\n"
+        );
+        assert!(skipped.is_empty());
+        assert_eq!(
+            r.try_iter().collect::<Vec<_>>(),
+            [
+                ProgressUpdate::Station {
+                    station: "00044".into(),
+                    index: 0,
+                    count: 2
+                },
+                ProgressUpdate::Station {
+                    station: "00073".into(),
+                    index: 1,
+                    count: 2
+                },
+            ]
+        );
+    }
+
+    /// writes a header and a row, but fails for 00073 after the header
+    fn write_or_fail(w: &mut dyn Write, r: &Request) -> Result<()> {
+        writeln!(w, "STATIONS_ID\tVALUE")?;
+        match r.station() {
+            "00073" => Err(DwdError::NoStations(r.station().to_string())),
+            station => Ok(writeln!(w, "{}\t1", station)?),
+        }
+    }
+
+    #[test]
+    fn test_skip_failed_stations() {
+        let request = Request::new(Progress::default());
+        let stations = [
+            "00073".to_string(),
+            "00044".to_string(),
+            "00078".to_string(),
+        ];
+        let mut out = Vec::new();
+        let skipped = write_combined(&mut out, &request, &stations, write_or_fail).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "STATIONS_ID\tVALUE\n00044\t1\n00078\t1\n"
+        );
+        assert!(matches!(
+            &skipped[..],
+            [DwdError::Station { station, .. }] if station == "00073"
+        ));
+
+        let dir = std::env::temp_dir().join(format!("dwd-dl-batch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (paths, skipped) =
+            write_per_station(&dir.join("data.csv"), &request, &stations, write_or_fail).unwrap();
+        assert_eq!(
+            paths,
+            [dir.join("data_00044.csv"), dir.join("data_00078.csv")]
+        );
+        assert_eq!(skipped.len(), 1);
+        assert!(!dir.join("data_00073.csv").exists());
+        fs::remove_dir_all(dir).unwrap();
+
+        // only failed stations, named in the error
+        let err = write_combined(&mut Vec::new(), &request, &stations[..1], write_or_fail);
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "station 00073: no station matches 00073"
+        );
+
+        // a cancellation isn't skipped
+        let err = write_combined(&mut Vec::new(), &request, &stations, |_, _| {
+            Err(DwdError::Cancelled)
+        });
+        assert!(matches!(err, Err(DwdError::Cancelled)));
+    }
+
+    #[test]
+    fn test_station_path() {
+        assert_eq!(
+            station_path(Path::new("/tmp/data.csv"), "00044"),
+            Path::new("/tmp/data_00044.csv")
+        );
+        assert_eq!(
+            station_path(Path::new("data"), "00044"),
+            Path::new("data_00044")
+        );
+    }
+
+    #[test]
+    fn test_select() {
+        let catalog = StationCatalog::parse(
+            "Stations_id von_datum bis_datum Stationshoehe geoBreite geoLaenge Stationsname Bundesland
+----------- --------- --------- ------------- --------- --------- ----------------------------------------- ----------
+00044 19690101 20240101             44     52.9336    8.2370 Großenkneten                             Niedersachsen
+00073 19510101 19781231            374     48.6159   13.0506 Aldersbach-Kriestorf                     Bayern
+00078 19610101 20240101             64     52.4853    7.9126 Alfhausen                                Niedersachsen
+",
+        )
+        .unwrap();
+        let timespan = Interval {
+            start: datetime!(2020-01-01 00:00),
+            end: datetime!(2021-01-01 00:00),
+        };
+
+        let state = StationSelection::State("niedersachsen".into());
+        assert_eq!(state.select(&catalog, timespan), ["00044", "00078"]);
+
+        // 00073 has no data in the timespan
+        let bounding_box = StationSelection::BoundingBox {
+            south_west: LatLon {
+                lat: 48.0,
+                lon: 8.0,
+            },
+            north_east: LatLon {
+                lat: 53.0,
+                lon: 14.0,
+            },
+        };
+        assert_eq!(bounding_box.select(&catalog, timespan), ["00044"]);
+
+        let ids = StationSelection::Ids(vec!["44".into(), " 00044".into(), "".into(), "73".into()]);
+        assert_eq!(
+            ids.resolve(
+                StationProduct::Climate(ClimateResolution::ClimateDaily),
+                timespan
+            )
+            .unwrap(),
+            ["00044", "00073"]
+        );
+    }
+}
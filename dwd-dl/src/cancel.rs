@@ -0,0 +1,30 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::error::{DwdError, Result};
+
+/// Stops a running download, checked before every file and archive member
+///
+/// Clones share the same state, so the token can be cancelled from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `Err(DwdError::Cancelled)` once the token was cancelled
+    pub fn check(&self) -> Result<()> {
+        match self.is_cancelled() {
+            true => Err(DwdError::Cancelled),
+            false => Ok(()),
+        }
+    }
+}
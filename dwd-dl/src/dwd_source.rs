@@ -1,16 +1,31 @@
-use std::fmt::Debug;
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    sync::{mpsc, Arc, Mutex, PoisonError},
+};
 
 use time::PrimitiveDateTime;
 
-use crate::util::{
-    download::{create_client, download_file},
-    file::File,
-    interval::{Interval, Overlaps},
+use crate::{
+    cancel::CancellationToken,
+    error::{DwdError, Result},
+    max_parallel_downloads,
+    progress::{Progress, ProgressUpdate},
+    util::{
+        download::{create_client, download_file, refetch_file},
+        file::File,
+        interval::{Interval, Overlaps},
+        listing::Listings,
+    },
 };
 
 #[derive(Debug, Clone)]
 pub struct CommonRequestData {
     pub timespan: Interval<PrimitiveDateTime>,
+    pub progress: Progress,
+    pub cancel: CancellationToken,
+    /// directory listings, shared by the stations of a batch
+    pub listings: Listings,
 }
 
 #[derive(Debug)]
@@ -29,38 +44,359 @@ pub trait Common {
 
 pub trait Sources
 where
-    Self: Common,
+    Self: Common + Clone + Send + Sync + 'static,
 {
-    type Record: Timespan + Debug;
+    type Record: Timespan + Debug + Send + 'static;
 
     fn sources(&self) -> Vec<Box<dyn DwdSource<Record = Self::Record, RequestData = Self>>>;
 }
 
-pub trait DwdSource {
-    type Record: Timespan + Debug;
-    type RequestData: Common;
+pub trait DwdSource: Send + Sync {
+    type Record: Timespan + Debug + Send;
+    type RequestData: Common + Sync;
 
-    fn urls(&self, request_data: &Self::RequestData) -> Vec<UrlTimeIntervall>;
+    fn urls(&self, request_data: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>>;
 
-    fn extract_data(&self, request_data: &Self::RequestData, file: File) -> Vec<Self::Record>;
+    fn extract_data(
+        &self,
+        request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>>;
 
-    fn send(&self, request_data: &Self::RequestData) -> Vec<Self::Record> {
+    fn send(&self, request_data: &Self::RequestData) -> Result<Vec<Self::Record>> {
+        let urls = source_urls(self, request_data)?;
         let client = create_client();
-        let urls = self.urls(request_data);
-        dbg!(&urls);
-        urls.iter()
-            .inspect(|x| println!("BEFORE: {:?}", x.url))
-            .filter(|url| {
-                url.interval
-                    .map_or(true, |i| i.overlaps(&request_data.common().timespan))
-            })
-            .inspect(|x| println!("FILTER: {:?}", x.url))
-            .map(|url| download_file(&url.url, Some(&client)))
-            .flat_map(|file| self.extract_data(request_data, file))
-            // .inspect(|x| println!("{:?}", x.timespan()))
-            .skip_while(|d| d.timespan().start < request_data.common().timespan.start)
-            .take_while(|d| d.timespan().start < request_data.common().timespan.end)
-            .collect::<Vec<_>>()
+        std::thread::scope(|scope| {
+            let pool = DownloadPool::new(
+                urls.len(),
+                |url| fetch(self, request_data, &client, url),
+                |worker| {
+                    scope.spawn(worker);
+                },
+            );
+            let mut records = SourceRecords::new(urls, request_data.common().timespan, pool);
+            let cancel = &request_data.common().cancel;
+            std::iter::from_fn(|| records.next(cancel)).collect()
+        })
+    }
+}
+
+/// Urls of the files of `source` overlapping the requested timespan
+fn source_urls<S>(source: &S, request_data: &S::RequestData) -> Result<Vec<UrlTimeIntervall>>
+where
+    S: DwdSource + ?Sized,
+{
+    let timespan = request_data.common().timespan;
+    let urls = source
+        .urls(request_data)?
+        .into_iter()
+        .filter(|url| url.interval.is_none_or(|i| i.overlaps(&timespan)))
+        .collect::<Vec<_>>();
+    request_data
+        .common()
+        .progress
+        .report(ProgressUpdate::Listing { files: urls.len() });
+    Ok(urls)
+}
+
+/// Downloads and decodes one file of `source`
+fn fetch<S>(
+    source: &S,
+    request_data: &S::RequestData,
+    client: &ureq::Agent,
+    url: &UrlTimeIntervall,
+) -> Result<Vec<S::Record>>
+where
+    S: DwdSource + ?Sized,
+{
+    let cancel = &request_data.common().cancel;
+    cancel.check()?;
+    let progress = &request_data.common().progress;
+    let file = download_file(&url.url, Some(client), progress, cancel)?;
+    let records = match source.extract_data(request_data, file) {
+        // e.g. a cached file of an interrupted download, tried once more
+        Err(DwdError::CorruptArchive { .. }) => {
+            let file = refetch_file(&url.url, Some(client), progress, cancel)?;
+            source.extract_data(request_data, file)?
+        }
+        records => records?,
+    };
+    progress.report(ProgressUpdate::Records {
+        url: url.url.clone(),
+        count: records.len(),
+    });
+    Ok(records)
+}
+
+type Job<R> = (UrlTimeIntervall, mpsc::SyncSender<Result<Vec<R>>>);
+
+/// Worker threads which download and decode the started files, the results are taken
+/// in the order the files were started
+///
+/// The workers exit once the pool is dropped and their current file is done.
+struct DownloadPool<R> {
+    jobs: mpsc::Sender<Job<R>>,
+    pending: VecDeque<mpsc::Receiver<Result<Vec<R>>>>,
+}
+
+impl<R: Send> DownloadPool<R> {
+    /// `spawn` runs a worker on a new thread, at most `max_parallel_downloads` and `files` are spawned
+    fn new<'a, F>(
+        files: usize,
+        work: F,
+        mut spawn: impl FnMut(Box<dyn FnOnce() + Send + 'a>),
+    ) -> Self
+    where
+        F: Fn(&UrlTimeIntervall) -> Result<Vec<R>> + Send + Sync + 'a,
+        R: 'a,
+    {
+        let (jobs, receiver) = mpsc::channel::<Job<R>>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let work = Arc::new(work);
+        for _ in 0..files.min(max_parallel_downloads()) {
+            let receiver = receiver.clone();
+            let work = work.clone();
+            spawn(Box::new(move || loop {
+                let job = receiver
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .recv();
+                match job {
+                    // the receiver is gone if the pool was dropped in the meantime
+                    Ok((url, result)) => _ = result.send(work(&url)),
+                    Err(_) => break,
+                }
+            }));
+        }
+        Self {
+            jobs,
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn start(&mut self, url: UrlTimeIntervall) {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        self.jobs
+            .send((url, sender))
+            .expect("download workers are running while the pool exists");
+        self.pending.push_back(receiver);
+    }
+
+    fn in_flight(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Waits for the oldest started file, `None` if no file is started
+    fn next(&mut self) -> Option<Result<Vec<R>>> {
+        let receiver = self.pending.pop_front()?;
+        Some(receiver.recv().expect("download worker panicked"))
+    }
+}
+
+/// Lazily downloads the files of one source, `max_parallel_downloads` are in flight at a time
+///
+/// Leading records before the requested timespan are skipped, the first record after it ends the source.
+struct SourceRecords<R> {
+    urls: std::vec::IntoIter<UrlTimeIntervall>,
+    pool: DownloadPool<R>,
+    records: std::vec::IntoIter<R>,
+    timespan: Interval<PrimitiveDateTime>,
+    started: bool,
+    /// a file reached the end of the timespan, the following files aren't started
+    reached_end: bool,
+    done: bool,
+}
+
+impl<R: Timespan + Send> SourceRecords<R> {
+    fn new(
+        urls: Vec<UrlTimeIntervall>,
+        timespan: Interval<PrimitiveDateTime>,
+        pool: DownloadPool<R>,
+    ) -> Self {
+        let mut records = Self {
+            urls: urls.into_iter(),
+            pool,
+            records: Vec::new().into_iter(),
+            timespan,
+            started: false,
+            reached_end: false,
+            done: false,
+        };
+        records.start_files();
+        records
+    }
+
+    /// Starts files until `max_parallel_downloads` are in flight
+    fn start_files(&mut self) {
+        while !self.reached_end && self.pool.in_flight() < max_parallel_downloads() {
+            let Some(url) = self.urls.next() else {
+                break;
+            };
+            match url.interval {
+                Some(interval) if interval.start >= self.timespan.end => self.reached_end = true,
+                _ => self.pool.start(url),
+            }
+        }
+    }
+
+    fn next(&mut self, cancel: &CancellationToken) -> Option<Result<R>> {
+        while !self.done {
+            if let Some(record) = self.records.next() {
+                if !self.started && record.timespan().start < self.timespan.start {
+                    continue;
+                }
+                if record.timespan().start >= self.timespan.end {
+                    self.done = true;
+                    break;
+                }
+                self.started = true;
+                return Some(Ok(record));
+            }
+
+            if let Err(err) = cancel.check() {
+                self.done = true;
+                return Some(Err(err));
+            }
+            match self.pool.next() {
+                Some(Ok(records)) => {
+                    let end = self.timespan.end;
+                    if records.last().is_some_and(|r| r.timespan().start >= end) {
+                        self.reached_end = true;
+                    }
+                    self.start_files();
+                    self.records = records.into_iter();
+                }
+                Some(Err(err)) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+                None => self.done = true,
+            }
+        }
+        None
+    }
+}
+
+type BoxedSource<R> = Box<dyn DwdSource<Record = <R as Sources>::Record, RequestData = R>>;
+
+/// Records of all sources of a request in chronological order, see [`DwdProduct::download_iter`]
+///
+/// Sources are only queried once the previous one is exhausted, the iterator ends after the first error.
+pub struct DownloadIter<'a, R: Sources> {
+    request: &'a R,
+    /// shared with the download workers, which outlive a call of `next`
+    shared: Arc<R>,
+    client: ureq::Agent,
+    sources: std::vec::IntoIter<BoxedSource<R>>,
+    source_count: usize,
+    current: Option<SourceRecords<R::Record>>,
+    /// end of the last record of the previous sources
+    last_timestamp: Option<PrimitiveDateTime>,
+    last_end: Option<PrimitiveDateTime>,
+    skipping: bool,
+    done: bool,
+}
+
+impl<'a, R: Sources> DownloadIter<'a, R> {
+    fn new(request: &'a R) -> Self {
+        let sources = request.sources();
+        Self {
+            request,
+            shared: Arc::new(request.clone()),
+            client: create_client(),
+            source_count: sources.len(),
+            sources: sources.into_iter(),
+            current: None,
+            last_timestamp: None,
+            last_end: None,
+            skipping: true,
+            done: false,
+        }
+    }
+
+    fn source_records(&self, source: BoxedSource<R>) -> Result<SourceRecords<R::Record>> {
+        let source: Arc<dyn DwdSource<Record = R::Record, RequestData = R>> = Arc::from(source);
+        let urls = source_urls(source.as_ref(), self.request)?;
+        let request = self.shared.clone();
+        let client = self.client.clone();
+        let pool = DownloadPool::new(
+            urls.len(),
+            move |url| fetch(source.as_ref(), &request, &client, url),
+            |worker| {
+                std::thread::spawn(worker);
+            },
+        );
+        Ok(SourceRecords::new(
+            urls,
+            self.request.common().timespan,
+            pool,
+        ))
+    }
+}
+
+impl<R: Sources> Iterator for DownloadIter<'_, R> {
+    type Item = Result<R::Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let timespan = self.request.common().timespan;
+        while !self.done {
+            let Some(records) = &mut self.current else {
+                let Some(source) = self.sources.next() else {
+                    self.done = true;
+                    break;
+                };
+                if self.last_timestamp.is_some_and(|l| l >= timespan.end) {
+                    self.done = true;
+                    break;
+                }
+                if let Err(err) = self.request.common().cancel.check() {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+                self.request
+                    .common()
+                    .progress
+                    .report(ProgressUpdate::Source {
+                        index: self.source_count - self.sources.len() - 1,
+                        count: self.source_count,
+                    });
+                match self.source_records(source) {
+                    Ok(records) => self.current = Some(records),
+                    Err(err) => {
+                        self.done = true;
+                        return Some(Err(err));
+                    }
+                }
+                self.skipping = true;
+                continue;
+            };
+
+            match records.next(&self.request.common().cancel) {
+                Some(Ok(record)) => {
+                    // skip what the previous sources already covered
+                    let start = record.timespan().start;
+                    let covered = match self.last_timestamp {
+                        Some(l) => l >= start,
+                        None => timespan.start > start,
+                    };
+                    if self.skipping && covered {
+                        continue;
+                    }
+                    self.skipping = false;
+                    self.last_end = Some(record.timespan().end);
+                    return Some(Ok(record));
+                }
+                Some(Err(err)) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+                None => {
+                    self.current = None;
+                    self.last_timestamp = self.last_end;
+                }
+            }
+        }
+        None
     }
 }
 
@@ -71,28 +407,127 @@ pub trait DwdProduct {
     fn download(
         &self,
         request: &Self::Request,
-    ) -> Vec<<<Self as DwdProduct>::Request as Sources>::Record> {
-        let mut last_timestamp = None;
-        let mut records = Vec::new();
-        let sources = request.sources();
-        for source in sources {
-            if last_timestamp.is_some_and(|l| l >= request.common().timespan.end) {
-                break;
-            }
-            let current_records = source.send(request);
-            let mut iter = current_records
-                .into_iter()
-                .skip_while(|d| match last_timestamp {
-                    Some(l) => l >= d.timespan().start,
-                    None => request.common().timespan.start > d.timespan().start,
-                });
-            records.extend(&mut iter);
-            if let Some(last) = records.last() {
-                last_timestamp = Some(last.timespan().end);
-            };
+    ) -> Result<Vec<<<Self as DwdProduct>::Request as Sources>::Record>> {
+        self.download_iter(request).collect()
+    }
+
+    /// Like [`DwdProduct::download`], but the files are only downloaded while iterating
+    fn download_iter<'a>(&self, request: &'a Self::Request) -> DownloadIter<'a, Self::Request> {
+        DownloadIter::new(request)
+    }
+
+    fn downloadx(&self, request: Self::Request) -> Result<Self::Response>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use time::{ext::NumericalDuration, macros::datetime};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct Record(PrimitiveDateTime);
+
+    impl Timespan for Record {
+        fn timespan(&self) -> Interval<PrimitiveDateTime> {
+            Interval::new(self.0, self.0).unwrap()
         }
-        records
     }
 
-    fn downloadx(&self, request: Self::Request) -> Self::Response;
+    /// one record per url at the hour given by the url
+    fn urls(hours: std::ops::Range<i64>) -> Vec<UrlTimeIntervall> {
+        hours
+            .map(|hour| UrlTimeIntervall {
+                url: hour.to_string(),
+                interval: None,
+            })
+            .collect()
+    }
+
+    fn record(url: &UrlTimeIntervall) -> Record {
+        Record(datetime!(2023-01-01 00:00) + url.url.parse::<i64>().unwrap().hours())
+    }
+
+    #[test]
+    fn test_pool_order() {
+        std::thread::scope(|scope| {
+            let mut pool = DownloadPool::new(
+                3,
+                |url| {
+                    // the first file is the slowest
+                    if url.url == "0" {
+                        std::thread::sleep(Duration::from_millis(50));
+                    }
+                    Ok(vec![record(url)])
+                },
+                |worker| {
+                    scope.spawn(worker);
+                },
+            );
+            for url in urls(0..3) {
+                pool.start(url);
+            }
+            assert_eq!(pool.in_flight(), 3);
+            let hours = std::iter::from_fn(|| pool.next())
+                .map(|records| records.unwrap()[0].0.hour())
+                .collect::<Vec<_>>();
+            assert_eq!(hours, [0, 1, 2]);
+        });
+    }
+
+    #[test]
+    fn test_source_records_stop_at_end() {
+        let started = AtomicUsize::new(0);
+        let timespan =
+            Interval::new(datetime!(2023-01-01 00:00), datetime!(2023-01-01 02:00)).unwrap();
+        let records = std::thread::scope(|scope| {
+            let urls = urls(0..20);
+            let pool = DownloadPool::new(
+                urls.len(),
+                |url| {
+                    started.fetch_add(1, Ordering::SeqCst);
+                    Ok(vec![record(url)])
+                },
+                |worker| {
+                    scope.spawn(worker);
+                },
+            );
+            let mut records = SourceRecords::new(urls, timespan, pool);
+            let cancel = CancellationToken::default();
+            std::iter::from_fn(|| records.next(&cancel)).collect::<Result<Vec<_>>>()
+        })
+        .unwrap();
+
+        assert_eq!(records.len(), 2);
+        // the file with the end and the files in flight after it
+        assert_eq!(started.into_inner(), 2 + max_parallel_downloads());
+
+        // files known to start after the timespan aren't started
+        let mut urls = urls(0..2);
+        urls.push(UrlTimeIntervall {
+            url: "5".into(),
+            interval: Some(
+                Interval::new(datetime!(2023-01-01 05:00), datetime!(2023-01-01 06:00)).unwrap(),
+            ),
+        });
+        std::thread::scope(|scope| {
+            let pool = DownloadPool::new(
+                urls.len(),
+                |url| {
+                    assert_ne!(url.url, "5");
+                    Ok(vec![record(url)])
+                },
+                |worker| {
+                    scope.spawn(worker);
+                },
+            );
+            let records = SourceRecords::new(urls, timespan, pool);
+            assert_eq!(records.pool.in_flight(), 2);
+        });
+    }
 }
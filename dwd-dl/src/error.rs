@@ -0,0 +1,95 @@
+use std::{fmt::Display, io};
+
+use thiserror::Error;
+
+pub type Result<T, E = DwdError> = std::result::Result<T, E>;
+
+/// Errors which can occur while downloading and decoding a DWD product
+#[derive(Error, Debug)]
+pub enum DwdError {
+    #[error("`{url}` responded with HTTP status {status}")]
+    HttpStatus { url: String, status: u16 },
+    #[error("request to `{url}` failed: {reason}")]
+    Transport { url: String, reason: String },
+    #[error("couldn't parse directory listing `{url}`: {reason}")]
+    ListingParse { url: String, reason: String },
+    #[error("archive `{file}` is corrupted: {reason}")]
+    CorruptArchive { file: String, reason: String },
+    #[error("archive `{file}` contains no `{member}` member")]
+    MissingMember { file: String, member: String },
+    #[error("couldn't parse record in `{file}`: {reason}")]
+    RecordParse { file: String, reason: String },
+    #[error("couldn't write cache entry `{path}`: {reason}")]
+    Cache { path: String, reason: String },
+    #[error("`{file}` has a {found} grid, the resolution expects {expected}")]
+    GridMismatch {
+        file: String,
+        expected: String,
+        found: String,
+    },
+    #[error("no station matches {0}")]
+    NoStations(String),
+    #[error("station {station}: {source}")]
+    Station {
+        station: String,
+        source: Box<DwdError>,
+    },
+    #[error("download was cancelled")]
+    Cancelled,
+    #[error("couldn't write output: {0}")]
+    Write(#[from] io::Error),
+}
+
+impl DwdError {
+    pub(crate) fn transport(url: &str, reason: impl Display) -> Self {
+        Self::Transport {
+            url: url.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub(crate) fn listing_parse(url: &str, reason: impl Display) -> Self {
+        Self::ListingParse {
+            url: url.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub(crate) fn corrupt_archive(file: &str, reason: impl Display) -> Self {
+        Self::CorruptArchive {
+            file: file.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub(crate) fn station(station: &str, source: DwdError) -> Self {
+        Self::Station {
+            station: station.to_string(),
+            source: Box::new(source),
+        }
+    }
+
+    pub(crate) fn record_parse(file: &str, reason: impl Display) -> Self {
+        Self::RecordParse {
+            file: file.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// A value (date, column, ...) in a file name or a data line couldn't be parsed
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("`{input}` is not a valid {expected}")]
+pub struct ParseError {
+    pub input: String,
+    pub expected: &'static str,
+}
+
+impl ParseError {
+    pub(crate) fn new(input: &str, expected: &'static str) -> Self {
+        Self {
+            input: input.to_string(),
+            expected,
+        }
+    }
+}
@@ -1,7 +1,15 @@
-use std::sync::OnceLock;
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
+pub mod batch;
+pub mod cancel;
 pub mod dwd_source;
+pub mod error;
 pub mod products;
+pub mod progress;
+pub mod stations;
 pub mod util;
 
 static DWD_URL: OnceLock<String> = OnceLock::new();
@@ -18,3 +26,39 @@ pub fn base_url() -> &'static str {
         })
     })
 }
+
+static CACHE_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Directory of the persistent download cache, caching is disabled if `None`
+///
+/// Defaults to the ENV `DWD_CACHE_DIR`
+pub fn cache_dir() -> Option<&'static Path> {
+    CACHE_DIR
+        .get_or_init(|| std::env::var_os("DWD_CACHE_DIR").map(PathBuf::from))
+        .as_deref()
+}
+
+/// Overrides the cache directory, fails if it was already set or used
+pub fn set_cache_dir(dir: PathBuf) -> Result<(), PathBuf> {
+    CACHE_DIR.set(Some(dir)).map_err(Option::unwrap)
+}
+
+static MAX_PARALLEL_DOWNLOADS: OnceLock<usize> = OnceLock::new();
+
+/// Number of files of one source which are downloaded and decoded at the same time
+///
+/// Defaults to the ENV `DWD_PARALLEL_DOWNLOADS` or 4
+pub fn max_parallel_downloads() -> usize {
+    *MAX_PARALLEL_DOWNLOADS.get_or_init(|| {
+        std::env::var("DWD_PARALLEL_DOWNLOADS")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(4)
+            .max(1)
+    })
+}
+
+/// Overrides the number of parallel downloads, fails if it was already set or used
+pub fn set_max_parallel_downloads(n: usize) -> Result<(), usize> {
+    MAX_PARALLEL_DOWNLOADS.set(n.max(1))
+}
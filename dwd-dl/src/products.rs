@@ -1,4 +1,5 @@
 pub mod climate;
 pub mod evaporation;
+pub mod observation;
 pub mod precipitation;
 pub mod radolan;
@@ -1,8 +1,14 @@
+use std::io::Write;
+
 use time::PrimitiveDateTime;
 
 use crate::{
     dwd_source::{Common, CommonRequestData, DwdProduct, DwdSource, Sources, Timespan},
-    util::{interval::Interval, time::parse_yyyymmdd_into_date_time},
+    error::{DwdError, ParseError, Result},
+    util::{
+        compression::zip, csv::nth_column, file::File, interval::Interval, output::write_to_string,
+        time::parse_yyyymmdd_into_date_time,
+    },
 };
 
 use self::resolutions::{
@@ -12,9 +18,16 @@ use self::resolutions::{
 };
 
 mod formats;
+mod records;
 mod resolutions;
 
-pub use formats::ClimateFormat;
+pub use formats::{
+    ClimateColumn, ClimateFormat, ClimateFormatConfig, ClimateWriter, DateFormat, DecimalSeparator,
+};
+pub use records::{
+    AnnualValues, ClimateValues, Column, DailyValues, MonthlyValues, ANNUAL_COLUMNS, DAILY_COLUMNS,
+    MONTHLY_COLUMNS,
+};
 pub use resolutions::ClimateResolution;
 
 #[derive(Debug, Clone)]
@@ -32,8 +45,11 @@ impl Common for ClimateCommonRequestData {
 
 #[derive(Debug, Clone)]
 pub struct ClimateRecord {
-    timespan: Interval<PrimitiveDateTime>,
-    data: String,
+    pub timespan: Interval<PrimitiveDateTime>,
+    pub station: u32,
+    pub values: ClimateValues,
+    /// the original line, written as is by [`ClimateFormat::Standard`]
+    line: String,
 }
 
 impl Timespan for ClimateRecord {
@@ -64,6 +80,15 @@ impl Sources for ClimateCommonRequestData {
 }
 
 impl ClimateResolution {
+    /// value columns of the resolution, without `STATIONS_ID` and the dates
+    pub fn columns(&self) -> &'static [Column] {
+        match self {
+            ClimateResolution::ClimateDaily => &DAILY_COLUMNS,
+            ClimateResolution::ClimateMonthly => &MONTHLY_COLUMNS,
+            ClimateResolution::ClimateAnnual => &ANNUAL_COLUMNS,
+        }
+    }
+
     pub fn header(&self) -> String {
         match self {
             ClimateResolution::ClimateDaily => "STATIONS_ID;MESS_DATUM;QN_3;  FX;  FM;QN_4; RSK;RSKF; SDK;SHK_TAG;  NM; VPM;  PM; TMK; UPM; TXK; TNK; TGK;eor",
@@ -79,7 +104,7 @@ impl DwdProduct for ClimateProduct {
     type Request = ClimateCommonRequestData;
     type Response = Vec<ClimateRecord>;
 
-    fn downloadx(&self, request: Self::Request) -> Self::Response {
+    fn downloadx(&self, request: Self::Request) -> Result<Self::Response> {
         self.download(&request)
     }
 }
@@ -88,20 +113,55 @@ pub fn climate_data_to_string(
     records: Vec<ClimateRecord>,
     resolution: &ClimateResolution,
 ) -> String {
-    let mut string = String::new();
-    string.push_str(&resolution.header());
-    string.push('\n');
+    write_to_string(|writer| {
+        write_climate_data(writer, &mut records.into_iter().map(Ok), resolution)
+    })
+}
+
+/// Streaming variant of [`climate_data_to_string`], stops at the first failed record
+pub fn write_climate_data(
+    writer: &mut dyn Write,
+    records: &mut dyn Iterator<Item = Result<ClimateRecord>>,
+    resolution: &ClimateResolution,
+) -> Result<()> {
+    writeln!(writer, "{}", resolution.header())?;
     for record in records {
-        string.push_str(&record.data);
-        string.push('\n');
+        writeln!(writer, "{}", record?.line)?;
     }
-    string
+    Ok(())
+}
+
+/// Parses every line (except the header) of the `produkt` file inside the station zip
+fn extract_records(
+    file: File,
+    extract_timespan: fn(&str) -> Result<Interval<PrimitiveDateTime>, ParseError>,
+    parse_values: fn(&str) -> Result<ClimateValues, ParseError>,
+) -> Result<Vec<ClimateRecord>> {
+    let name = file.name.clone();
+    let data = zip::extract_text(file, "produkt")?;
+    data.lines()
+        .skip(1)
+        .map(|line| {
+            Ok(ClimateRecord {
+                timespan: extract_timespan(line)?,
+                station: records::station(line)?,
+                values: parse_values(line)?,
+                line: line.to_string(),
+            })
+        })
+        .collect::<Result<_, ParseError>>()
+        .map_err(|err| DwdError::record_parse(&name, err))
+}
+
+/// for STATIONS_ID;MESS_DATUM;...
+fn extract_date(s: &str) -> Result<Interval<PrimitiveDateTime>, ParseError> {
+    let time = parse_yyyymmdd_into_date_time(nth_column(s, 1)?)?;
+    Ok(Interval::new(time, time).unwrap())
 }
 
 /// for STATIONS_ID;MESS_DATUM_BEGINN;MESS_DATUM_ENDE; ...
-fn extract_timespan(s: &str) -> Interval<PrimitiveDateTime> {
-    let times = s.split(';').skip(1).take(2).collect::<Vec<_>>();
-    let start = parse_yyyymmdd_into_date_time(times[0]).unwrap();
-    let end = parse_yyyymmdd_into_date_time(times[1]).unwrap();
-    Interval::new(start, end).unwrap()
+fn extract_timespan(s: &str) -> Result<Interval<PrimitiveDateTime>, ParseError> {
+    let start = parse_yyyymmdd_into_date_time(nth_column(s, 1)?)?;
+    let end = parse_yyyymmdd_into_date_time(nth_column(s, 2)?)?;
+    Interval::new(start, end).map_err(|_| ParseError::new(s, "line with a valid interval"))
 }
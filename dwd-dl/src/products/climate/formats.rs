@@ -1,17 +1,265 @@
+use std::io::{self, Write};
+
 use serde::Deserialize;
+use time::{macros::format_description, Date};
+
+use crate::{
+    error::Result,
+    util::{output::try_write_to_string, time::format_date_iso},
+};
+
+use super::{climate_data_to_string, write_climate_data, ClimateRecord, ClimateResolution, Column};
 
-use super::{climate_data_to_string, ClimateRecord, ClimateResolution};
+/// Writes the header and the records into `writer`
+pub type ClimateWriter = fn(
+    &mut dyn Write,
+    &mut dyn Iterator<Item = Result<ClimateRecord>>,
+    &ClimateResolution,
+    &ClimateFormatConfig,
+) -> Result<()>;
 
 #[derive(Debug, Deserialize, Clone, Copy)]
 #[cfg_attr(feature = "specta", derive(specta::Type))]
 pub enum ClimateFormat {
     Standard,
+    /// the columns of [`ClimateFormatConfig`] with units in the header
+    Columns,
 }
 
 impl ClimateFormat {
-    pub fn format_method(&self) -> fn(Vec<ClimateRecord>, &ClimateResolution) -> String {
+    /// the method fails for columns which the resolution doesn't have
+    pub fn format_method(
+        &self,
+    ) -> fn(Vec<ClimateRecord>, &ClimateResolution, &ClimateFormatConfig) -> Result<String> {
+        match self {
+            ClimateFormat::Standard => |r, res, _| Ok(climate_data_to_string(r, res)),
+            ClimateFormat::Columns => format_columns,
+        }
+    }
+
+    pub fn write_method(&self) -> ClimateWriter {
+        match self {
+            ClimateFormat::Standard => |w, r, res, _| write_climate_data(w, r, res),
+            ClimateFormat::Columns => write_columns,
+        }
+    }
+}
+
+/// Options of [`ClimateFormat::Columns`], ignored by [`ClimateFormat::Standard`]
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct ClimateFormatConfig {
+    /// in output order, every column of the resolution if empty
+    #[serde(default)]
+    pub columns: Vec<ClimateColumn>,
+    #[serde(default)]
+    pub decimal_separator: DecimalSeparator,
+    #[serde(default)]
+    pub date_format: DateFormat,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct ClimateColumn {
+    /// name in the DWD header, e.g. `TMK`
+    pub name: String,
+    /// replaces `name [unit]` in the header
+    #[serde(default)]
+    pub header: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub enum DecimalSeparator {
+    #[default]
+    Point,
+    Comma,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub enum DateFormat {
+    /// YYYY-MM-DD
+    #[default]
+    Iso,
+    /// DD.MM.YYYY
+    German,
+    /// YYYYMMDD, as in the DWD files
+    Dwd,
+}
+
+impl DateFormat {
+    fn format(&self, date: Date) -> String {
         match self {
-            ClimateFormat::Standard => climate_data_to_string,
+            DateFormat::Iso => format_date_iso(date),
+            DateFormat::German => date
+                .format(format_description!("[day].[month].[year]"))
+                .unwrap(),
+            DateFormat::Dwd => date
+                .format(format_description!("[year][month][day]"))
+                .unwrap(),
         }
     }
 }
+
+impl ClimateFormatConfig {
+    /// header and index into [`super::ClimateValues::values`] of the selected columns
+    fn selection(&self, resolution: &ClimateResolution) -> Result<Vec<(String, usize)>> {
+        let columns = resolution.columns();
+        let header = |column: &Column| match column.unit {
+            Some(unit) => format!("{} [{}]", column.name, unit),
+            None => column.name.to_string(),
+        };
+        if self.columns.is_empty() {
+            return Ok(columns.iter().map(header).zip(0..).collect());
+        }
+        self.columns
+            .iter()
+            .map(|selected| {
+                let name = selected.name.trim();
+                let index = columns
+                    .iter()
+                    .position(|c| c.name.eq_ignore_ascii_case(name))
+                    .ok_or_else(|| {
+                        let reason = format!("unknown column {name} for {resolution}");
+                        io::Error::new(io::ErrorKind::InvalidInput, reason)
+                    })?;
+                let header = match &selected.header {
+                    Some(h) if !h.trim().is_empty() => h.clone(),
+                    _ => header(&columns[index]),
+                };
+                Ok((header, index))
+            })
+            .collect()
+    }
+
+    /// missing values are empty
+    fn format_value(&self, value: Option<f32>) -> String {
+        let Some(value) = value else {
+            return String::new();
+        };
+        let value = value.to_string();
+        match self.decimal_separator {
+            DecimalSeparator::Point => value,
+            DecimalSeparator::Comma => value.replace('.', ","),
+        }
+    }
+}
+
+pub fn format_columns(
+    records: Vec<ClimateRecord>,
+    resolution: &ClimateResolution,
+    config: &ClimateFormatConfig,
+) -> Result<String> {
+    try_write_to_string(|writer| {
+        write_columns(writer, &mut records.into_iter().map(Ok), resolution, config)
+    })
+}
+
+/// `;` separated table of the selected columns, daily values have one date,
+/// monthly and annual values the first and last day
+pub fn write_columns(
+    writer: &mut dyn Write,
+    records: &mut dyn Iterator<Item = Result<ClimateRecord>>,
+    resolution: &ClimateResolution,
+    config: &ClimateFormatConfig,
+) -> Result<()> {
+    let selection = config.selection(resolution)?;
+    let mut header = vec!["Station".to_string()];
+    match resolution {
+        ClimateResolution::ClimateDaily => header.push("Date".into()),
+        _ => header.extend(["Start".into(), "End".into()]),
+    }
+    header.extend(selection.iter().map(|(h, _)| h.clone()));
+    writeln!(writer, "{}", header.join(";"))?;
+
+    for record in records {
+        let record = record?;
+        let mut row = vec![record.station.to_string()];
+        row.push(config.date_format.format(record.timespan.start().date()));
+        if !matches!(resolution, ClimateResolution::ClimateDaily) {
+            row.push(config.date_format.format(record.timespan.end().date()));
+        }
+        let values = record.values.values();
+        row.extend(
+            selection
+                .iter()
+                .map(|(_, i)| config.format_value(values[*i])),
+        );
+        writeln!(writer, "{}", row.join(";"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::products::climate::{extract_date, extract_records, ClimateValues};
+    use crate::util::file::File;
+
+    fn records(lines: &[&str]) -> Vec<ClimateRecord> {
+        let mut data = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut data));
+            zip.start_file("produkt_klima_tag.txt", Default::default())
+                .unwrap();
+            writeln!(zip, "{}", ClimateResolution::ClimateDaily.header()).unwrap();
+            for line in lines {
+                writeln!(zip, "{line}").unwrap();
+            }
+            zip.finish().unwrap();
+        }
+        let file = File::new("tageswerte_KL_04271.zip".into(), data.into());
+        extract_records(file, extract_date, ClimateValues::daily).unwrap()
+    }
+
+    #[test]
+    fn test_columns() {
+        let records = records(&[
+            "       4271;20230105;   10;  22.6;   9.9;    3;   0.5;   6;    0.000;   0;   7.8;   7.5; 1009.75;    6.0;   79.38;    9.9;    3.1;   -1.4;eor",
+            "       4271;20230106;   10;  -999;   9.9;    3;   2.5;   6;    0.000;   0;   7.8;   7.5; 1009.75;   -0.5;   79.38;    9.9;    3.1;   -1.4;eor",
+        ]);
+        let resolution = ClimateResolution::ClimateDaily;
+        let config = ClimateFormatConfig {
+            columns: vec![
+                ClimateColumn {
+                    name: "tmk".into(),
+                    header: None,
+                },
+                ClimateColumn {
+                    name: "RSK".into(),
+                    header: Some("Niederschlag".into()),
+                },
+                ClimateColumn {
+                    name: "FX".into(),
+                    header: None,
+                },
+            ],
+            decimal_separator: DecimalSeparator::Comma,
+            date_format: DateFormat::German,
+        };
+        assert_eq!(
+            format_columns(records.clone(), &resolution, &config).unwrap(),
+            "Station;Date;TMK [°C];Niederschlag;FX [m/s]\n\
+             4271;05.01.2023;6;0,5;22,6\n\
+             4271;06.01.2023;-0,5;2,5;\n"
+        );
+
+        let all = format_columns(records.clone(), &resolution, &Default::default()).unwrap();
+        assert!(all.starts_with("Station;Date;QN_3;FX [m/s];FM [m/s];QN_4;RSK [mm];"));
+        assert!(all.contains("\n4271;2023-01-05;10;22.6;9.9;3;0.5;6;0;0;7.8;"));
+
+        let unknown = ClimateFormatConfig {
+            columns: vec![ClimateColumn {
+                name: "MO_TT".into(),
+                header: None,
+            }],
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let mut iter = records.clone().into_iter().map(Ok);
+        assert!(write_columns(&mut out, &mut iter, &resolution, &unknown).is_err());
+        let format = ClimateFormat::Columns.format_method();
+        assert!(format(records, &resolution, &unknown).is_err());
+    }
+}
@@ -0,0 +1,344 @@
+//! Typed rows of the KL (climate) `produkt` files, missing values (`-999`) are `None`
+
+use crate::{
+    error::ParseError,
+    util::csv::{parse_column, parse_optional_column},
+};
+
+/// The values of one row, depending on the resolution
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClimateValues {
+    Daily(DailyValues),
+    Monthly(MonthlyValues),
+    Annual(AnnualValues),
+}
+
+/// `STATIONS_ID;MESS_DATUM;QN_3;FX;FM;QN_4;RSK;RSKF;SDK;SHK_TAG;NM;VPM;PM;TMK;UPM;TXK;TNK;TGK`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyValues {
+    /// quality level of the wind values
+    pub qn_3: Option<u8>,
+    /// daily maximum of wind gust in m/s
+    pub fx: Option<f32>,
+    /// daily mean of wind velocity in m/s
+    pub fm: Option<f32>,
+    /// quality level of the other values
+    pub qn_4: Option<u8>,
+    /// daily precipitation height in mm
+    pub rsk: Option<f32>,
+    /// precipitation form
+    pub rskf: Option<u8>,
+    /// daily sunshine duration in h
+    pub sdk: Option<f32>,
+    /// daily snow depth in cm
+    pub shk_tag: Option<f32>,
+    /// daily mean of cloud cover in 1/8
+    pub nm: Option<f32>,
+    /// daily mean of vapor pressure in hPa
+    pub vpm: Option<f32>,
+    /// daily mean of pressure in hPa
+    pub pm: Option<f32>,
+    /// daily mean of temperature in °C
+    pub tmk: Option<f32>,
+    /// daily mean of relative humidity in %
+    pub upm: Option<f32>,
+    /// daily maximum of temperature at 2 m in °C
+    pub txk: Option<f32>,
+    /// daily minimum of temperature at 2 m in °C
+    pub tnk: Option<f32>,
+    /// daily minimum of air temperature at 5 cm above ground in °C
+    pub tgk: Option<f32>,
+}
+
+/// `STATIONS_ID;MESS_DATUM_BEGINN;MESS_DATUM_ENDE;QN_4;MO_N;MO_TT;MO_TX;MO_TN;MO_FK;MX_TX;MX_FX;MX_TN;MO_SD_S;QN_6;MO_RR;MX_RS`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthlyValues {
+    pub qn_4: Option<u8>,
+    /// monthly mean of cloud cover in 1/8
+    pub mo_n: Option<f32>,
+    /// monthly mean of daily temperature means in °C
+    pub mo_tt: Option<f32>,
+    /// monthly mean of daily temperature maxima in °C
+    pub mo_tx: Option<f32>,
+    /// monthly mean of daily temperature minima in °C
+    pub mo_tn: Option<f32>,
+    /// monthly mean of daily wind speed in Bft
+    pub mo_fk: Option<f32>,
+    /// monthly maximum of daily temperature maxima in °C
+    pub mx_tx: Option<f32>,
+    /// monthly maximum of daily wind gust in m/s
+    pub mx_fx: Option<f32>,
+    /// monthly minimum of daily temperature minima in °C
+    pub mx_tn: Option<f32>,
+    /// monthly sum of sunshine duration in h
+    pub mo_sd_s: Option<f32>,
+    pub qn_6: Option<u8>,
+    /// monthly sum of precipitation height in mm
+    pub mo_rr: Option<f32>,
+    /// monthly maximum of daily precipitation height in mm
+    pub mx_rs: Option<f32>,
+}
+
+/// `STATIONS_ID;MESS_DATUM_BEGINN;MESS_DATUM_ENDE;QN_4;JA_N;JA_TT;JA_TX;JA_TN;JA_FK;JA_SD_S;JA_MX_FX;JA_MX_TX;JA_MX_TN;QN_6;JA_RR;JA_MX_RS`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnualValues {
+    pub qn_4: Option<u8>,
+    /// annual mean of cloud cover in 1/8
+    pub ja_n: Option<f32>,
+    /// annual mean of daily temperature means in °C
+    pub ja_tt: Option<f32>,
+    /// annual mean of daily temperature maxima in °C
+    pub ja_tx: Option<f32>,
+    /// annual mean of daily temperature minima in °C
+    pub ja_tn: Option<f32>,
+    /// annual mean of daily wind speed in Bft
+    pub ja_fk: Option<f32>,
+    /// annual sum of sunshine duration in h
+    pub ja_sd_s: Option<f32>,
+    /// annual maximum of daily wind gust in m/s
+    pub ja_mx_fx: Option<f32>,
+    /// annual maximum of daily temperature maxima in °C
+    pub ja_mx_tx: Option<f32>,
+    /// annual minimum of daily temperature minima in °C
+    pub ja_mx_tn: Option<f32>,
+    pub qn_6: Option<u8>,
+    /// annual sum of precipitation height in mm
+    pub ja_rr: Option<f32>,
+    /// annual maximum of daily precipitation height in mm
+    pub ja_mx_rs: Option<f32>,
+}
+
+impl ClimateValues {
+    pub fn daily(line: &str) -> Result<Self, ParseError> {
+        Ok(Self::Daily(DailyValues {
+            qn_3: parse_optional_column(line, 2)?,
+            fx: parse_optional_column(line, 3)?,
+            fm: parse_optional_column(line, 4)?,
+            qn_4: parse_optional_column(line, 5)?,
+            rsk: parse_optional_column(line, 6)?,
+            rskf: parse_optional_column(line, 7)?,
+            sdk: parse_optional_column(line, 8)?,
+            shk_tag: parse_optional_column(line, 9)?,
+            nm: parse_optional_column(line, 10)?,
+            vpm: parse_optional_column(line, 11)?,
+            pm: parse_optional_column(line, 12)?,
+            tmk: parse_optional_column(line, 13)?,
+            upm: parse_optional_column(line, 14)?,
+            txk: parse_optional_column(line, 15)?,
+            tnk: parse_optional_column(line, 16)?,
+            tgk: parse_optional_column(line, 17)?,
+        }))
+    }
+
+    pub fn monthly(line: &str) -> Result<Self, ParseError> {
+        Ok(Self::Monthly(MonthlyValues {
+            qn_4: parse_optional_column(line, 3)?,
+            mo_n: parse_optional_column(line, 4)?,
+            mo_tt: parse_optional_column(line, 5)?,
+            mo_tx: parse_optional_column(line, 6)?,
+            mo_tn: parse_optional_column(line, 7)?,
+            mo_fk: parse_optional_column(line, 8)?,
+            mx_tx: parse_optional_column(line, 9)?,
+            mx_fx: parse_optional_column(line, 10)?,
+            mx_tn: parse_optional_column(line, 11)?,
+            mo_sd_s: parse_optional_column(line, 12)?,
+            qn_6: parse_optional_column(line, 13)?,
+            mo_rr: parse_optional_column(line, 14)?,
+            mx_rs: parse_optional_column(line, 15)?,
+        }))
+    }
+
+    pub fn annual(line: &str) -> Result<Self, ParseError> {
+        Ok(Self::Annual(AnnualValues {
+            qn_4: parse_optional_column(line, 3)?,
+            ja_n: parse_optional_column(line, 4)?,
+            ja_tt: parse_optional_column(line, 5)?,
+            ja_tx: parse_optional_column(line, 6)?,
+            ja_tn: parse_optional_column(line, 7)?,
+            ja_fk: parse_optional_column(line, 8)?,
+            ja_sd_s: parse_optional_column(line, 9)?,
+            ja_mx_fx: parse_optional_column(line, 10)?,
+            ja_mx_tx: parse_optional_column(line, 11)?,
+            ja_mx_tn: parse_optional_column(line, 12)?,
+            qn_6: parse_optional_column(line, 13)?,
+            ja_rr: parse_optional_column(line, 14)?,
+            ja_mx_rs: parse_optional_column(line, 15)?,
+        }))
+    }
+}
+
+/// A value column of the `produkt` file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Column {
+    /// name in the DWD header, e.g. `TMK`
+    pub name: &'static str,
+    pub unit: Option<&'static str>,
+}
+
+const fn column(name: &'static str, unit: Option<&'static str>) -> Column {
+    Column { name, unit }
+}
+
+/// columns of [`DailyValues`] in file order
+pub const DAILY_COLUMNS: [Column; 16] = [
+    column("QN_3", None),
+    column("FX", Some("m/s")),
+    column("FM", Some("m/s")),
+    column("QN_4", None),
+    column("RSK", Some("mm")),
+    column("RSKF", None),
+    column("SDK", Some("h")),
+    column("SHK_TAG", Some("cm")),
+    column("NM", Some("1/8")),
+    column("VPM", Some("hPa")),
+    column("PM", Some("hPa")),
+    column("TMK", Some("°C")),
+    column("UPM", Some("%")),
+    column("TXK", Some("°C")),
+    column("TNK", Some("°C")),
+    column("TGK", Some("°C")),
+];
+
+/// columns of [`MonthlyValues`] in file order
+pub const MONTHLY_COLUMNS: [Column; 13] = [
+    column("QN_4", None),
+    column("MO_N", Some("1/8")),
+    column("MO_TT", Some("°C")),
+    column("MO_TX", Some("°C")),
+    column("MO_TN", Some("°C")),
+    column("MO_FK", Some("Bft")),
+    column("MX_TX", Some("°C")),
+    column("MX_FX", Some("m/s")),
+    column("MX_TN", Some("°C")),
+    column("MO_SD_S", Some("h")),
+    column("QN_6", None),
+    column("MO_RR", Some("mm")),
+    column("MX_RS", Some("mm")),
+];
+
+/// columns of [`AnnualValues`] in file order
+pub const ANNUAL_COLUMNS: [Column; 13] = [
+    column("QN_4", None),
+    column("JA_N", Some("1/8")),
+    column("JA_TT", Some("°C")),
+    column("JA_TX", Some("°C")),
+    column("JA_TN", Some("°C")),
+    column("JA_FK", Some("Bft")),
+    column("JA_SD_S", Some("h")),
+    column("JA_MX_FX", Some("m/s")),
+    column("JA_MX_TX", Some("°C")),
+    column("JA_MX_TN", Some("°C")),
+    column("QN_6", None),
+    column("JA_RR", Some("mm")),
+    column("JA_MX_RS", Some("mm")),
+];
+
+impl ClimateValues {
+    /// values in the order of [`DAILY_COLUMNS`], [`MONTHLY_COLUMNS`] or [`ANNUAL_COLUMNS`]
+    pub fn values(&self) -> Vec<Option<f32>> {
+        let qn = |qn: Option<u8>| qn.map(f32::from);
+        match self {
+            ClimateValues::Daily(v) => vec![
+                qn(v.qn_3),
+                v.fx,
+                v.fm,
+                qn(v.qn_4),
+                v.rsk,
+                qn(v.rskf),
+                v.sdk,
+                v.shk_tag,
+                v.nm,
+                v.vpm,
+                v.pm,
+                v.tmk,
+                v.upm,
+                v.txk,
+                v.tnk,
+                v.tgk,
+            ],
+            ClimateValues::Monthly(v) => vec![
+                qn(v.qn_4),
+                v.mo_n,
+                v.mo_tt,
+                v.mo_tx,
+                v.mo_tn,
+                v.mo_fk,
+                v.mx_tx,
+                v.mx_fx,
+                v.mx_tn,
+                v.mo_sd_s,
+                qn(v.qn_6),
+                v.mo_rr,
+                v.mx_rs,
+            ],
+            ClimateValues::Annual(v) => vec![
+                qn(v.qn_4),
+                v.ja_n,
+                v.ja_tt,
+                v.ja_tx,
+                v.ja_tn,
+                v.ja_fk,
+                v.ja_sd_s,
+                v.ja_mx_fx,
+                v.ja_mx_tx,
+                v.ja_mx_tn,
+                qn(v.qn_6),
+                v.ja_rr,
+                v.ja_mx_rs,
+            ],
+        }
+    }
+}
+
+/// `STATIONS_ID` of a line
+pub(super) fn station(line: &str) -> Result<u32, ParseError> {
+    parse_column(line, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily() {
+        let line = "       4271;20230105;   10;  22.6;   9.9;    3;   0.5;   6;    0.000;   0;   7.8;   7.5; 1009.75;    6.0;   79.38;    9.9;    3.1;   -1.4;eor";
+        let ClimateValues::Daily(values) = ClimateValues::daily(line).unwrap() else {
+            unreachable!()
+        };
+        assert_eq!(values.qn_3, Some(10));
+        assert_eq!(values.fx, Some(22.6));
+        assert_eq!(values.rskf, Some(6));
+        assert_eq!(values.pm, Some(1009.75));
+        assert_eq!(values.tgk, Some(-1.4));
+        assert_eq!(station(line), Ok(4271));
+
+        let values = ClimateValues::Daily(values).values();
+        assert_eq!(values.len(), DAILY_COLUMNS.len());
+        assert_eq!(values[11], Some(6.0));
+        assert_eq!(DAILY_COLUMNS[11].name, "TMK");
+    }
+
+    #[test]
+    fn test_missing() {
+        let line = "         44;20200101;20201231;   10;  -999;  10.89;   15.42;    6.36;-999;-999;-999;  35.0;  -4.9;-999;-999;-999;eor";
+        let ClimateValues::Annual(values) = ClimateValues::annual(line).unwrap() else {
+            unreachable!()
+        };
+        assert_eq!(values.qn_4, Some(10));
+        assert_eq!(values.ja_n, None);
+        assert_eq!(values.ja_tt, Some(10.89));
+        assert_eq!(values.ja_mx_tn, Some(-4.9));
+        assert_eq!(values.qn_6, None);
+        assert_eq!(values.ja_mx_rs, None);
+
+        let line = "         44;20221201;20221231;    3;  -999;   2.82;    5.09;    0.19;-999;  15.8;-999;  -9.4;  37.05;    1;    84.7;   19.5;eor";
+        let ClimateValues::Monthly(values) = ClimateValues::monthly(line).unwrap() else {
+            unreachable!()
+        };
+        assert_eq!(values.mo_fk, None);
+        assert_eq!(values.mo_tt, Some(2.82));
+        assert_eq!(values.mo_rr, Some(84.7));
+
+        assert!(ClimateValues::monthly("44;20221201;20221231;x").is_err());
+    }
+}
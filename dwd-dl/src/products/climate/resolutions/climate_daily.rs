@@ -1,14 +1,13 @@
 use crate::{
     base_url,
     dwd_source::{self, UrlTimeIntervall},
-    products::climate::{ClimateCommonRequestData, ClimateRecord},
+    error::{DwdError, Result},
+    products::climate::{
+        extract_date, extract_records, ClimateCommonRequestData, ClimateRecord, ClimateValues,
+    },
     util::{
-        compression::zip,
-        download::download_text,
         file::File,
-        interval::Interval,
         regex::{extract_interval_d8_d8, links_in_text},
-        time::parse_yyyymmdd_into_date_time,
     },
 };
 
@@ -20,12 +19,12 @@ impl dwd_source::DwdSource for ClimateDailyHistorical {
     type Record = ClimateRecord;
     type RequestData = ClimateCommonRequestData;
 
-    fn urls(&self, request_data: &Self::RequestData) -> Vec<UrlTimeIntervall> {
+    fn urls(&self, request_data: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>> {
         let url = format!(
             "{}climate_environment/CDC/observations_germany/climate/daily/kl/historical/",
             base_url()
         );
-        let html = download_text(&url, None);
+        let html = request_data.common.listings.get(&url)?;
         let regex = format!(
             r"tageswerte_KL_{}_\d{{8}}_\d{{8}}_hist.zip",
             request_data.station
@@ -34,28 +33,24 @@ impl dwd_source::DwdSource for ClimateDailyHistorical {
         let links = links_in_text(&html, &regex);
         links
             .iter()
-            .map(|link| UrlTimeIntervall {
-                url: format!("{}{}", url, link),
-                interval: Some(extract_interval_d8_d8(link).unwrap().into()),
+            .map(|link| {
+                let interval = extract_interval_d8_d8(link)
+                    .map_err(|err| DwdError::listing_parse(&url, err))?;
+                Ok(UrlTimeIntervall {
+                    url: format!("{}{}", url, link),
+                    interval: Some(interval.into()),
+                })
             })
             .inspect(|x| println!("{:?}", x))
-            .collect::<Vec<_>>()
+            .collect()
     }
 
-    fn extract_data(&self, _request_data: &Self::RequestData, file: File) -> Vec<Self::Record> {
-        let bytes = zip::extract_file(file.data, "produkt");
-        let data = String::from_utf8(bytes).unwrap();
-        data.lines()
-            .skip(1)
-            .map(|line| {
-                let time = parse_yyyymmdd_into_date_time(line.split(';').nth(1).unwrap()).unwrap();
-                let timespan = Interval::new(time, time).unwrap();
-                Self::Record {
-                    data: line.to_string(),
-                    timespan,
-                }
-            })
-            .collect()
+    fn extract_data(
+        &self,
+        _request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>> {
+        extract_records(file, extract_date, ClimateValues::daily)
     }
 }
 
@@ -67,38 +62,31 @@ impl dwd_source::DwdSource for ClimateDailyRecent {
     type Record = ClimateRecord;
     type RequestData = ClimateCommonRequestData;
 
-    fn urls(&self, request_data: &Self::RequestData) -> Vec<UrlTimeIntervall> {
+    fn urls(&self, request_data: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>> {
         let url = format!(
             "{}climate_environment/CDC/observations_germany/climate/daily/kl/recent/",
             base_url()
         );
-        let html = download_text(&url, None);
+        let html = request_data.common.listings.get(&url)?;
         let regex = format!(r"tageswerte_KL_{}_akt.zip", request_data.station);
 
         let links = links_in_text(&html, &regex);
-        links
+        let links = links
             .iter()
             .map(|link| UrlTimeIntervall {
                 url: format!("{}{}", url, link),
                 interval: None,
             })
             .inspect(|x| println!("{:?}", x))
-            .collect::<Vec<_>>()
+            .collect::<Vec<_>>();
+        Ok(links)
     }
 
-    fn extract_data(&self, _request_data: &Self::RequestData, file: File) -> Vec<Self::Record> {
-        let bytes = zip::extract_file(file.data, "produkt");
-        let data = String::from_utf8(bytes).unwrap();
-        data.lines()
-            .skip(1)
-            .map(|line| {
-                let time = parse_yyyymmdd_into_date_time(line.split(';').nth(1).unwrap()).unwrap();
-                let timespan = Interval::new(time, time).unwrap();
-                Self::Record {
-                    data: line.to_string(),
-                    timespan,
-                }
-            })
-            .collect()
+    fn extract_data(
+        &self,
+        _request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>> {
+        extract_records(file, extract_date, ClimateValues::daily)
     }
 }
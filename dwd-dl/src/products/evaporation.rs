@@ -2,9 +2,13 @@ use time::{Date, PrimitiveDateTime};
 
 use crate::{
     dwd_source::{Common, CommonRequestData, DwdProduct, DwdSource, Sources, Timespan},
-    util::{interval::Interval, point::Point},
+    error::{DwdError, ParseError, Result},
+    progress::ProgressUpdate,
+    util::{compression::universal::MultiLayerFolder, interval::Interval, point::Point},
 };
 
+use self::decode::{GridHeader, GridPosition};
+
 pub mod decode;
 pub mod formats;
 mod resolutions;
@@ -15,7 +19,7 @@ pub use resolutions::EvaporationResolution;
 #[derive(Debug, Clone)]
 pub struct EvaporationRequest {
     pub common: CommonRequestData,
-    pub coordinates: Vec<Point<usize>>,
+    pub coordinates: Vec<GridPosition>,
     pub resolution: EvaporationResolution,
 }
 
@@ -28,6 +32,11 @@ impl Common for EvaporationRequest {
 #[derive(Debug, Clone)]
 pub struct Record {
     pub time: Date,
+    /// grid of the file
+    pub header: GridHeader,
+    /// cells of the requested coordinates, in their order
+    pub cells: Vec<Point<usize>>,
+    /// values of the cells
     pub data: Vec<f32>,
 }
 
@@ -60,7 +69,6 @@ impl Sources for EvaporationRequest {
 
 #[derive(Debug)]
 pub struct EvaporationResponse {
-    pub coordinates: Vec<Point<usize>>,
     pub records: Vec<Record>,
 }
 
@@ -70,11 +78,38 @@ impl DwdProduct for Product {
     type Request = EvaporationRequest;
     type Response = EvaporationResponse;
 
-    fn downloadx(&self, request: Self::Request) -> Self::Response {
-        let records = self.download(&request);
-        EvaporationResponse {
-            coordinates: request.coordinates,
-            records,
-        }
+    fn downloadx(&self, request: Self::Request) -> Result<Self::Response> {
+        let records = self.download(&request)?;
+        Ok(EvaporationResponse { records })
+    }
+}
+
+/// Decodes every ASCII grid inside `folder` and extracts the requested coordinates
+fn extract_records(
+    request: &EvaporationRequest,
+    folder: MultiLayerFolder,
+    date_from_name: fn(&str) -> Result<Date, ParseError>,
+) -> Result<Vec<Record>> {
+    let archive = folder.name().to_string();
+    let mut records = Vec::new();
+    for file in folder {
+        let file = file?;
+        request.common.progress.report(ProgressUpdate::Member {
+            archive: archive.clone(),
+            member: file.name.clone(),
+        });
+        let parse_error = |err| DwdError::record_parse(&file.name, err);
+        let time = date_from_name(&file.name).map_err(parse_error)?;
+        let ascii = std::str::from_utf8(&file.data)
+            .map_err(|err| DwdError::record_parse(&file.name, err))?;
+        let grid = decode::decode(ascii, &request.coordinates).map_err(parse_error)?;
+
+        records.push(Record {
+            time,
+            header: grid.header,
+            cells: grid.cells,
+            data: grid.data,
+        });
     }
+    Ok(records)
 }
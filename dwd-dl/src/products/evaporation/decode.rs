@@ -1,38 +1,237 @@
-use std::str::Lines;
+use std::str::{FromStr, Lines};
 
-use crate::util::point::Point;
+use crate::{
+    error::ParseError,
+    util::{gauss_krueger::to_gauss_krueger, geo::LatLon, point::Point},
+};
 
-// TODO check header, extract no_data values => use an enum
-pub fn decode(data: &str, coordinates: &[Point<usize>]) -> Vec<f32> {
-    let lines = data.lines();
-    extract_points(coordinates, lines)
+/// Header of an ESRI ASCII grid, coordinates in meters of Gauss-Krüger zone 3
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridHeader {
+    pub columns: usize,
+    pub rows: usize,
+    /// lower left corner of the lower left cell
+    pub x_lower_left: f64,
+    pub y_lower_left: f64,
+    pub cellsize: f64,
+    pub nodata: Option<f64>,
 }
 
-pub fn extract_points(coordinates: &[Point<usize>], mut lines: Lines<'_>) -> Vec<f32> {
-    let coordinates = sort_coordinates_y(coordinates);
-    // offset top right corner (1,1) but index starts at 0
-    let coordinates = coordinates
+impl GridHeader {
+    /// grid of the `grids_germany` rasters
+    pub const GERMANY: GridHeader = GridHeader {
+        columns: 654,
+        rows: 866,
+        x_lower_left: 3280414.0,
+        y_lower_left: 5237500.0,
+        cellsize: 1000.0,
+        nodata: Some(-999.0),
+    };
+
+    /// Parses the leading `KEY value` lines, returns the header and the data after it
+    pub fn parse(data: &str) -> Result<(Self, &str), ParseError> {
+        let (mut columns, mut rows, mut x, mut y, mut cellsize, mut nodata) =
+            (None, None, None, None, None, None);
+        let mut center = false;
+        let mut rest = data;
+        while let Some((key, value)) = header_line(rest) {
+            let number = || {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| ParseError::new(value, "number"))
+            };
+            let count = || {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| ParseError::new(value, "grid size"))
+            };
+            match key.to_ascii_uppercase().as_str() {
+                "NCOLS" => columns = Some(count()?),
+                "NROWS" => rows = Some(count()?),
+                "XLLCORNER" => x = Some(number()?),
+                "YLLCORNER" => y = Some(number()?),
+                "XLLCENTER" => (x, center) = (Some(number()?), true),
+                "YLLCENTER" => (y, center) = (Some(number()?), true),
+                "CELLSIZE" => cellsize = Some(number()?),
+                "NODATA_VALUE" => nodata = Some(number()?),
+                _ => return Err(ParseError::new(key, "grid header key")),
+            }
+            rest = rest
+                .split_once('\n')
+                .map(|(_, rest)| rest)
+                .unwrap_or_default();
+        }
+
+        let missing = |key| ParseError::new(data.lines().next().unwrap_or_default(), key);
+        let cellsize = cellsize.ok_or_else(|| missing("CELLSIZE"))?;
+        let offset = if center { cellsize / 2.0 } else { 0.0 };
+        let header = Self {
+            columns: columns.ok_or_else(|| missing("NCOLS"))?,
+            rows: rows.ok_or_else(|| missing("NROWS"))?,
+            x_lower_left: x.ok_or_else(|| missing("XLLCORNER"))? - offset,
+            y_lower_left: y.ok_or_else(|| missing("YLLCORNER"))? - offset,
+            cellsize,
+            nodata,
+        };
+        Ok((header, rest))
+    }
+
+    /// 1-based cell (x = column, y = row from the top) of a position
+    pub fn resolve(&self, position: &GridPosition) -> Result<Point<usize>, ParseError> {
+        let (easting, northing) = match *position {
+            GridPosition::Cell(cell) => {
+                return match (1..=self.columns).contains(&cell.x)
+                    && (1..=self.rows).contains(&cell.y)
+                {
+                    true => Ok(cell),
+                    false => Err(ParseError::new(
+                        &format!("{},{}", cell.x, cell.y),
+                        "grid point",
+                    )),
+                };
+            }
+            GridPosition::GaussKrueger { easting, northing } => (easting, northing),
+            GridPosition::Wgs84(position) => to_gauss_krueger(position, 3),
+        };
+        let column = ((easting - self.x_lower_left) / self.cellsize).floor();
+        let row_from_bottom = ((northing - self.y_lower_left) / self.cellsize).floor();
+        if !(0.0..self.columns as f64).contains(&column)
+            || !(0.0..self.rows as f64).contains(&row_from_bottom)
+        {
+            return Err(ParseError::new(
+                &format!("{easting},{northing}"),
+                "position inside of the grid",
+            ));
+        }
+        Ok(Point::new(
+            column as usize + 1,
+            self.rows - row_from_bottom as usize,
+        ))
+    }
+}
+
+/// `(key, value)` if the first line of `data` is a header line
+fn header_line(data: &str) -> Option<(&str, &str)> {
+    let mut tokens = data.lines().next()?.split_whitespace();
+    let key = tokens.next()?;
+    if !key.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some((key, tokens.next().unwrap_or_default()))
+}
+
+/// A requested position in a grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridPosition {
+    /// 1-based column and row from the top
+    Cell(Point<usize>),
+    /// meters of Gauss-Krüger zone 3 (DHDN)
+    GaussKrueger { easting: f64, northing: f64 },
+    /// degrees of WGS84, or ETRS89 which only differs by centimeters
+    Wgs84(LatLon),
+}
+
+impl FromStr for GridPosition {
+    type Err = ParseError;
+
+    /// `cell:column,row`, `gk:easting,northing` or `wgs84:lat,lon`
+    ///
+    /// Without a prefix an integer pair is a cell and a pair with a decimal point is `lat,lon`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::new(s, "grid position");
+        let (prefix, pair) = match s.split_once(':') {
+            Some((prefix, pair)) => (Some(prefix.trim().to_ascii_lowercase()), pair),
+            None => (None, s),
+        };
+        let (a, b) = pair.split_once(',').ok_or_else(invalid)?;
+        let (a, b) = (a.trim(), b.trim());
+        let cell = || match (a.parse::<usize>(), b.parse::<usize>()) {
+            (Ok(x), Ok(y)) => Ok(Self::Cell(Point::new(x, y))),
+            _ => Err(invalid()),
+        };
+        let wgs84 = || pair.parse().map(Self::Wgs84).map_err(|_| invalid());
+        match prefix.as_deref() {
+            Some("cell") => cell(),
+            Some("gk") => Ok(Self::GaussKrueger {
+                easting: a.parse().map_err(|_| invalid())?,
+                northing: b.parse().map_err(|_| invalid())?,
+            }),
+            Some("wgs84") => wgs84(),
+            Some(_) => Err(invalid()),
+            None if a.contains('.') || b.contains('.') => wgs84(),
+            None => cell(),
+        }
+    }
+}
+
+/// Requested cells of a decoded grid
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridValues {
+    pub header: GridHeader,
+    /// cells of the positions, in the order of the request
+    pub cells: Vec<Point<usize>>,
+    /// values of the cells, in the order of the request
+    pub data: Vec<f32>,
+}
+
+/// Resolves the positions against the header of the grid and extracts their values,
+/// grids without a header are on [`GridHeader::GERMANY`]
+pub fn decode(data: &str, positions: &[GridPosition]) -> Result<GridValues, ParseError> {
+    let (header, data) = match header_line(data) {
+        Some(_) => GridHeader::parse(data)?,
+        None => (GridHeader::GERMANY, data),
+    };
+    let cells = positions
         .iter()
-        .map(|p| Point::new(p.x - 1, p.y - 1))
-        .collect::<Vec<_>>();
+        .map(|position| header.resolve(position))
+        .collect::<Result<Vec<_>, _>>()?;
+    let data = extract_points(&cells, data.lines())?;
+    Ok(GridValues {
+        header,
+        cells,
+        data,
+    })
+}
+
+/// Values of the 1-based coordinates in their order, a cell may be requested several times
+pub fn extract_points(
+    coordinates: &[Point<usize>],
+    mut lines: Lines<'_>,
+) -> Result<Vec<f32>, ParseError> {
+    let out_of_grid = |p: Point<usize>| ParseError::new(&format!("{},{}", p.x, p.y), "grid point");
+    // the grid is walked once from the top left, every cell only once
+    let mut cells = sort_coordinates_y(coordinates);
+    cells.dedup();
 
-    let mut res = Vec::new();
+    let mut values = Vec::with_capacity(cells.len());
     let mut current_coord = Point::new(0, 0);
-    let mut current_line = lines.next().unwrap().split_whitespace();
-    for coord in coordinates {
+    let mut current_line = lines.next().unwrap_or_default().split_whitespace();
+    // offset top right corner (1,1) but index starts at 0
+    for coord in cells.iter().map(|p| Point::new(p.x - 1, p.y - 1)) {
         if coord.y != current_coord.y {
             current_line = lines
                 .nth(coord.y - current_coord.y - 1)
-                .unwrap()
+                .ok_or_else(|| out_of_grid(coord))?
                 .split_whitespace();
             current_coord = Point::new(0, coord.y);
         }
-        let el = current_line.nth(coord.x - current_coord.x);
+        let el = current_line
+            .nth(coord.x - current_coord.x)
+            .ok_or_else(|| out_of_grid(coord))?;
         current_coord.x = coord.x + 1;
-        res.push(el.unwrap().parse::<f32>().unwrap() / 10.);
+        let value = el
+            .parse::<f32>()
+            .map_err(|_| ParseError::new(el, "number"))?;
+        values.push(value / 10.);
     }
 
-    res
+    let value = |p: &Point<usize>| {
+        let i = cells
+            .binary_search_by(|c| c.y.cmp(&p.y).then(c.x.cmp(&p.x)))
+            .unwrap();
+        values[i]
+    };
+    Ok(coordinates.iter().map(value).collect())
 }
 
 pub fn sort_coordinates_y<T>(coordinates: &[Point<T>]) -> Vec<Point<T>>
@@ -66,7 +265,128 @@ mod tests {
             Point::new(4, 4),
         ];
         let data = "1 2 3 4\n5 6 7 8\n9 10 11 12\n13 14 15 16";
-        let res = extract_points(&coordinates, data.lines());
+        let res = extract_points(&coordinates, data.lines()).unwrap();
         assert_eq!(vec![0.1, 0.6, 0.7, 1.6], res);
+
+        let res = extract_points(&[Point::new(1, 5)], data.lines());
+        assert!(res.is_err());
+
+        // the order of the request, repeated cells get the same value
+        let coordinates = [Point::new(3, 2), Point::new(1, 1), Point::new(3, 2)];
+        let res = extract_points(&coordinates, data.lines()).unwrap();
+        assert_eq!(vec![0.7, 0.1, 0.7], res);
+    }
+
+    const GRID: &str = "NCOLS 3\nNROWS 2\nXLLCORNER 3500000\nYLLCORNER 5500000\nCELLSIZE 1000\nNODATA_VALUE -999\n1 2 3\n4 5 6\n";
+
+    #[test]
+    fn test_header() {
+        let (header, rest) = GridHeader::parse(GRID).unwrap();
+        assert_eq!(
+            header,
+            GridHeader {
+                columns: 3,
+                rows: 2,
+                x_lower_left: 3500000.0,
+                y_lower_left: 5500000.0,
+                cellsize: 1000.0,
+                nodata: Some(-999.0),
+            }
+        );
+        assert_eq!(rest, "1 2 3\n4 5 6\n");
+
+        let centered =
+            "ncols 3\nnrows 2\nxllcenter 3500500\nyllcenter 5500500\ncellsize 1000\n1 2 3";
+        let (centered, _) = GridHeader::parse(centered).unwrap();
+        assert_eq!(centered.x_lower_left, 3500000.0);
+        assert_eq!(centered.nodata, None);
+
+        assert!(GridHeader::parse("NCOLS 3\nNROWS 2\n1 2 3").is_err());
+    }
+
+    #[test]
+    fn test_decode_skips_header() {
+        let positions = ["3,2".parse().unwrap(), "1,1".parse().unwrap()];
+        let res = decode(GRID, &positions).unwrap();
+        assert_eq!(res.header, GridHeader::parse(GRID).unwrap().0);
+        assert_eq!(res.cells, vec![Point::new(3, 2), Point::new(1, 1)]);
+        assert_eq!(vec![0.6, 0.1], res.data);
+    }
+
+    #[test]
+    fn test_decode_resolves_against_header() {
+        // the cell of the position differs between the parsed and the default grid
+        let position = "gk:3500100,5501999".parse().unwrap();
+        let res = decode(GRID, &[position]).unwrap();
+        assert_eq!(res.cells, vec![Point::new(1, 1)]);
+        assert_eq!(vec![0.1], res.data);
+
+        assert!(decode(GRID, &["gk:3280414,5237500".parse().unwrap()]).is_err());
+
+        // two positions in the same cell
+        let positions = [
+            "gk:3500100,5501999".parse().unwrap(),
+            "gk:3500900,5501100".parse().unwrap(),
+        ];
+        let res = decode(GRID, &positions).unwrap();
+        assert_eq!(res.cells, vec![Point::new(1, 1); 2]);
+        assert_eq!(vec![0.1, 0.1], res.data);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let (header, _) = GridHeader::parse(GRID).unwrap();
+        let resolve = |s: &str| header.resolve(&s.parse().unwrap());
+        assert_eq!(resolve("2,1").unwrap(), Point::new(2, 1));
+        assert!(resolve("4,1").is_err());
+        // upper row is the northern one
+        assert_eq!(resolve("gk:3500100,5501999").unwrap(), Point::new(1, 1));
+        assert_eq!(resolve("gk:3502999.5,5500000").unwrap(), Point::new(3, 2));
+        assert!(resolve("gk:3503000,5500000").is_err());
+
+        // Frankfurt am Main
+        let position = GridPosition::from_str("50.1109,8.6821").unwrap();
+        assert_eq!(
+            GridHeader::GERMANY.resolve(&position).unwrap(),
+            Point::new(196 + 1, 866 - 315)
+        );
+    }
+
+    #[test]
+    fn test_grid_position() {
+        assert_eq!(
+            "12,34".parse::<GridPosition>().unwrap(),
+            GridPosition::Cell(Point::new(12, 34))
+        );
+        assert_eq!(
+            "cell:52,13".parse::<GridPosition>().unwrap(),
+            GridPosition::Cell(Point::new(52, 13))
+        );
+        assert_eq!(
+            "gk:3477334.8, 5552790".parse::<GridPosition>().unwrap(),
+            GridPosition::GaussKrueger {
+                easting: 3477334.8,
+                northing: 5552790.0
+            }
+        );
+        assert_eq!(
+            "52.52,13.405".parse::<GridPosition>().unwrap(),
+            GridPosition::Wgs84(LatLon {
+                lat: 52.52,
+                lon: 13.405
+            })
+        );
+        assert_eq!(
+            "wgs84:52,13".parse::<GridPosition>().unwrap(),
+            GridPosition::Wgs84(LatLon {
+                lat: 52.0,
+                lon: 13.0
+            })
+        );
+        assert!("abc".parse::<GridPosition>().is_err());
+        assert!("3477334.8,5552790".parse::<GridPosition>().is_err());
+        assert!("utm:1,2".parse::<GridPosition>().is_err());
+        assert!("cell:1.5,2".parse::<GridPosition>().is_err());
+        assert!("95.0,13.0".parse::<GridPosition>().is_err());
     }
 }
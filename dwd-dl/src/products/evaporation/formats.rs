@@ -1,76 +1,212 @@
-use std::collections::HashMap;
+use std::io::{self, Write};
 
 use serde::Deserialize;
+use time::{Date, Month};
 
-use crate::util::time::{format_date_american, format_date_iso};
-
-use super::{
-    decode::{sort_coordinates_x, sort_coordinates_y},
-    EvaporationResponse,
+use crate::{
+    error::Result,
+    util::{
+        gauss_krueger::from_gauss_krueger,
+        netcdf::{axes, Cube, Values},
+        output::write_to_string,
+        point::Point,
+        time::{format_date_american, format_date_iso},
+    },
 };
 
+use super::{decode::GridHeader, EvaporationResponse, Record};
+
 #[derive(Debug, Deserialize, Clone, Copy)]
 #[cfg_attr(feature = "specta", derive(specta::Type))]
 pub enum EvaporationFormat {
     Default,
     SwmmRainfallData,
+    /// CF NetCDF cube over the rows and columns of the coordinates
+    NetCdf,
 }
 
+/// Writes the records into `writer` while they are downloaded
+pub type EvaporationWriter =
+    fn(&mut dyn Write, &mut dyn Iterator<Item = Result<Record>>) -> Result<()>;
+
 impl EvaporationFormat {
-    pub fn format_method(&self) -> fn(EvaporationResponse) -> String {
+    pub fn write_method(&self) -> EvaporationWriter {
         match self {
-            EvaporationFormat::Default => format_default,
-            EvaporationFormat::SwmmRainfallData => format_swmm_rainfall_data,
+            EvaporationFormat::Default => write_default,
+            EvaporationFormat::SwmmRainfallData => write_swmm_rainfall_data,
+            EvaporationFormat::NetCdf => write_netcdf,
         }
     }
 }
 
 pub fn format_default(evaporation: EvaporationResponse) -> String {
-    let header = ["x_y", "Date", "Value"];
-    let mut str = header.join("\t");
-    str.push('\n');
+    write_to_string(|writer| write_default(writer, &mut evaporation.records.into_iter().map(Ok)))
+}
 
-    let coords = sort_coordinates_y(&evaporation.coordinates);
-    let coords_idx = coords
-        .iter()
-        .enumerate()
-        .map(|(i, p)| (p, i))
-        .collect::<HashMap<_, _>>();
-    let coords_x = sort_coordinates_x(&evaporation.coordinates);
-    let idx_table = coords_x
-        .iter()
-        .map(|p| *coords_idx.get(p).unwrap())
-        .collect::<Vec<_>>();
+// TODO check on start if only one Point is given
+pub fn format_swmm_rainfall_data(evaporation: EvaporationResponse) -> String {
+    write_to_string(|writer| {
+        write_swmm_rainfall_data(writer, &mut evaporation.records.into_iter().map(Ok))
+    })
+}
+
+/// Streaming variant of [`format_default`]
+///
+/// The rows are grouped by coordinate, so the records of several coordinates are kept until the
+/// download is done, a single coordinate is written while downloading. Every row is named after
+/// the cell of its own file.
+pub fn write_default(
+    writer: &mut dyn Write,
+    records: &mut dyn Iterator<Item = Result<Record>>,
+) -> Result<()> {
+    let header = ["x_y", "Date", "Value"];
+    writeln!(writer, "{}", header.join("\t"))?;
+    let mut write_row = |coord: &Point<usize>, record: &Record, value: f32| {
+        writeln!(
+            writer,
+            "{:0>4}_{:0>4}\t{}\t{:.1}",
+            coord.x,
+            coord.y,
+            format_date_iso(record.time),
+            value
+        )
+    };
 
-    let mut tmp = Vec::new();
-    for (idx, coord) in coords_x.iter().enumerate() {
-        for record in &evaporation.records {
-            tmp.push(format!("{:0>4}_{:0>4}", coord.x, coord.y));
-            tmp.push(format_date_iso(record.time));
-            tmp.push(format!("{:.1}", record.data[idx_table[idx]]));
-            str.push_str(&tmp.join("\t"));
-            str.push('\n');
-            tmp.clear();
+    let mut kept = Vec::new();
+    for record in records {
+        let record = record?;
+        match record.cells.as_slice() {
+            [cell] => write_row(cell, &record, record.data[0])?,
+            _ => kept.push(record),
         }
     }
-    str
+    let Some(first) = kept.first() else {
+        return Ok(());
+    };
+    // the coordinates in the order of the cells of the first file
+    let mut order = (0..first.cells.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| (first.cells[i].x, first.cells[i].y));
+    for i in order {
+        for record in &kept {
+            write_row(&record.cells[i], record, record.data[i])?;
+        }
+    }
+    Ok(())
 }
 
-// TODO check on start if only one Point is given
-pub fn format_swmm_rainfall_data(evaporation: EvaporationResponse) -> String {
+/// Streaming variant of [`format_swmm_rainfall_data`], only the first cell is written
+pub fn write_swmm_rainfall_data(
+    writer: &mut dyn Write,
+    records: &mut dyn Iterator<Item = Result<Record>>,
+) -> Result<()> {
     let header = ["MM/DD/YYYY", "hh:mm", "Value"];
-    let mut str = header.join("\t");
-    str.push('\n');
+    writeln!(writer, "{}", header.join("\t"))?;
 
     let time = "00:00";
-    let mut tmp = Vec::new();
-    for record in evaporation.records {
-        tmp.push(format_date_american(record.time.midnight()));
-        tmp.push(time.into());
-        tmp.push(format!("{:.1}", record.data[0]));
-        str.push_str(&tmp.join("\t"));
-        str.push('\n');
-        tmp.clear();
+    for record in records {
+        let record = record?;
+        writeln!(
+            writer,
+            "{}\t{}\t{:.1}",
+            format_date_american(record.time.midnight()),
+            time,
+            record.data[0]
+        )?;
     }
-    str
+    Ok(())
+}
+
+/// CF NetCDF `time, y, x` cube on the grid of the files, which has to be the same for all of them,
+/// cells between the coordinates which weren't requested are empty
+///
+/// The cube needs every record, they are kept until the download is done.
+pub fn write_netcdf(
+    writer: &mut dyn Write,
+    records: &mut dyn Iterator<Item = Result<Record>>,
+) -> Result<()> {
+    let records = records.collect::<Result<Vec<_>>>()?;
+    let (grid, coordinates) = match records.first() {
+        Some(record) => (record.header, record.cells.as_slice()),
+        None => (GridHeader::GERMANY, [].as_slice()),
+    };
+    let (columns, rows) = axes(coordinates);
+    let invalid = |reason: &str| io::Error::new(io::ErrorKind::InvalidInput, reason);
+    if columns.is_empty() {
+        let reason = "the NetCDF cube needs at least one record with a coordinate";
+        return Err(invalid(reason).into());
+    }
+    if records
+        .iter()
+        .any(|record| record.header != grid || record.cells != coordinates)
+    {
+        return Err(invalid("the grid of the files changes, they don't fit into one cube").into());
+    }
+    let cells = columns.len() * rows.len();
+    let indices = coordinates
+        .iter()
+        .map(|p| {
+            let row = rows.binary_search(&p.y).unwrap();
+            row * columns.len() + columns.binary_search(&p.x).unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    // the grid values are in 0.1 mm
+    let nodata = grid.nodata.map(|n| n as f32 / 10.);
+    let fill_value = -9999.0;
+    let mut values = vec![fill_value; records.len() * cells];
+    let mut times = Vec::with_capacity(records.len());
+    for (t, record) in records.iter().enumerate() {
+        let days =
+            (record.time - Date::from_calendar_date(1970, Month::January, 1).unwrap()).whole_days();
+        times.push(days as f64);
+        for (index, value) in indices.iter().zip(&record.data) {
+            if Some(*value) != nodata {
+                values[t * cells + index] = *value;
+            }
+        }
+    }
+
+    let center = |cell: usize| (cell as f64 - 0.5) * grid.cellsize;
+    let x = columns
+        .iter()
+        .map(|&c| grid.x_lower_left + center(c))
+        .collect::<Vec<_>>();
+    let y = rows
+        .iter()
+        .map(|&r| grid.y_lower_left + center(grid.rows + 1 - r))
+        .collect::<Vec<_>>();
+    let positions = y
+        .iter()
+        .flat_map(|&y| x.iter().map(move |&x| from_gauss_krueger(x, y)))
+        .collect();
+    let cube = Cube {
+        name: "evapotranspiration",
+        long_name: "evapotranspiration",
+        units: "mm",
+        time_units: "days since 1970-01-01",
+        times,
+        x,
+        y,
+        positions,
+        grid_mapping: vec![
+            ("grid_mapping_name", "transverse_mercator".into()),
+            ("longitude_of_central_meridian", 9.0.into()),
+            ("latitude_of_projection_origin", 0.0.into()),
+            ("scale_factor_at_central_meridian", 1.0.into()),
+            ("false_easting", 3500000.0.into()),
+            ("false_northing", 0.0.into()),
+            ("semi_major_axis", 6377397.155.into()),
+            ("inverse_flattening", 299.1528128.into()),
+            // DHDN to WGS84
+            (
+                "towgs84",
+                Values::Double(vec![598.1, 73.7, 418.2, 0.202, 0.045, -2.455, 6.7]),
+            ),
+        ],
+        values,
+        fill_value,
+        // the grid values are in 0.1 mm
+        least_significant_digit: 1,
+    };
+    Ok(cube.to_netcdf().write(writer)?)
 }
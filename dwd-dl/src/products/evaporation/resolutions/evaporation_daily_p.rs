@@ -3,7 +3,8 @@ use time::{util::days_in_year_month, Date, PrimitiveDateTime};
 use crate::{
     base_url,
     dwd_source::{self, Common, UrlTimeIntervall},
-    products::evaporation::{decode::decode, EvaporationRequest, Record},
+    error::{DwdError, Result},
+    products::evaporation::{extract_records, EvaporationRequest, Record},
     util::{
         compression::universal::{Filter, MultiLayerFolder},
         download::download_text,
@@ -20,12 +21,12 @@ impl dwd_source::DwdSource for DailyP {
     type Record = Record;
     type RequestData = EvaporationRequest;
 
-    fn urls(&self, _: &Self::RequestData) -> Vec<UrlTimeIntervall> {
+    fn urls(&self, _: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>> {
         let url = format!(
             "{}/climate_environment/CDC/grids_germany/daily/evapo_p/",
             base_url()
         );
-        let html = download_text(&url, None);
+        let html = download_text(&url, None)?;
         let regex = r"grids_germany_daily_evapo_p_\d{6}.tgz";
         let links = links_in_text(&html, regex);
 
@@ -33,45 +34,38 @@ impl dwd_source::DwdSource for DailyP {
 
         let res = links
             .iter()
-            .map(|link| UrlTimeIntervall {
-                url: format!("{}{}", url, link),
-                interval: Some({
-                    let date = extract_d6(link).unwrap();
-                    let date = parse_yyyymm(date).unwrap();
-                    year_month_to_interval(date)
-                }),
+            .map(|link| {
+                let date = extract_d6(link)
+                    .and_then(parse_yyyymm)
+                    .map_err(|err| DwdError::listing_parse(&url, err))?;
+                Ok(UrlTimeIntervall {
+                    url: format!("{}{}", url, link),
+                    interval: Some(year_month_to_interval(date)),
+                })
             })
-            .collect();
+            .collect::<Result<Vec<_>>>()?;
 
         dbg!(&res);
-        res
+        Ok(res)
     }
 
-    fn extract_data(&self, request_data: &Self::RequestData, file: File) -> Vec<Self::Record> {
+    fn extract_data(
+        &self,
+        request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>> {
         let filter0 = |_: &str| true;
         let ts = request_data.common().timespan;
         let filter1 = move |s: &str| {
-            let date = extract_d8(s).unwrap();
-            let date = parse_yyyymmdd(date).unwrap();
-            ts.contains(&date.midnight())
+            extract_d8(s)
+                .and_then(parse_yyyymmdd)
+                .is_ok_and(|date| ts.contains(&date.midnight()))
         };
         let filter: Vec<Filter> = vec![Box::new(filter0), Box::new(filter1)];
-        let folder = MultiLayerFolder::new(file, filter);
-        let mut records = Vec::new();
-
-        for file in folder {
-            let date = extract_d8(&file.name).unwrap();
-            let date = parse_yyyymmdd(date).unwrap();
-            let ascii = std::str::from_utf8(&file.data).unwrap();
-            let parsed = decode(ascii, &request_data.coordinates);
-
-            records.push(Record {
-                data: parsed,
-                time: date,
-            });
-        }
-
-        records
+        let folder = MultiLayerFolder::new(file, filter, request_data.common().cancel.clone());
+        extract_records(request_data, folder, |s| {
+            extract_d8(s).and_then(parse_yyyymmdd)
+        })
     }
 }
 
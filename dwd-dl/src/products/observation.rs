@@ -0,0 +1,188 @@
+//! Station observations besides KL and precipitation: air temperature, wind, solar, sun,
+//! soil temperature, pressure and visibility
+
+use time::PrimitiveDateTime;
+
+use crate::{
+    dwd_source::{Common, CommonRequestData, DwdProduct, DwdSource, Sources, Timespan},
+    error::{DwdError, ParseError, Result},
+    util::{
+        compression::zip,
+        csv::{nth_column, parse_optional_column},
+        file::File,
+        interval::Interval,
+        time::{parse_yyyymmddhh, parse_yyyymmddhhmm},
+    },
+};
+
+mod formats;
+mod resolutions;
+mod sources;
+
+pub use formats::{ObservationFormat, ObservationWriter};
+pub use resolutions::ObservationResolution;
+
+#[derive(Debug, Clone)]
+pub struct ObservationCommonRequestData {
+    pub common: CommonRequestData,
+    pub station: String,
+    pub resolution: ObservationResolution,
+}
+
+impl Common for ObservationCommonRequestData {
+    fn common(&self) -> &CommonRequestData {
+        &self.common
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObservationRecord {
+    pub timespan: Interval<PrimitiveDateTime>,
+    /// quality level (`QN`) of the row
+    pub quality: Option<u8>,
+    /// in the order of [`ObservationResolution::columns`], missing values (`-999`) are `None`
+    pub values: Vec<Option<f32>>,
+}
+
+impl Timespan for ObservationRecord {
+    fn timespan(&self) -> Interval<PrimitiveDateTime> {
+        self.timespan
+    }
+}
+
+impl Sources for ObservationCommonRequestData {
+    type Record = ObservationRecord;
+
+    fn sources(&self) -> Vec<Box<dyn DwdSource<Record = Self::Record, RequestData = Self>>> {
+        match (self.resolution.is_row(), self.resolution.has_now()) {
+            (true, _) => vec![Box::new(sources::Recent)],
+            (false, true) => vec![
+                Box::new(sources::Historical),
+                Box::new(sources::Recent),
+                Box::new(sources::Now),
+            ],
+            (false, false) => vec![Box::new(sources::Historical), Box::new(sources::Recent)],
+        }
+    }
+}
+
+pub struct ObservationProduct;
+
+impl DwdProduct for ObservationProduct {
+    type Request = ObservationCommonRequestData;
+    type Response = Vec<ObservationRecord>;
+
+    fn downloadx(&self, request: Self::Request) -> Result<Self::Response> {
+        self.download(&request)
+    }
+}
+
+/// Parses every line of the `produkt` file inside the station zip,
+/// the columns are looked up by their name in the header
+fn extract_records(
+    file: File,
+    resolution: ObservationResolution,
+) -> Result<Vec<ObservationRecord>> {
+    let name = file.name.clone();
+    let data = zip::extract_text(file, "produkt")?;
+    parse_produkt(&data, resolution).map_err(|err| DwdError::record_parse(&name, err))
+}
+
+fn parse_produkt(
+    data: &str,
+    resolution: ObservationResolution,
+) -> Result<Vec<ObservationRecord>, ParseError> {
+    let mut lines = data.lines();
+    let header = lines
+        .next()
+        .ok_or_else(|| ParseError::new(data, "produkt file with a header"))?;
+    let names = header.split(';').map(str::trim).collect::<Vec<_>>();
+    let position = |name| {
+        names
+            .iter()
+            .position(|n| *n == name)
+            .ok_or_else(|| ParseError::new(header, "header with the columns of the resolution"))
+    };
+    let time = position("MESS_DATUM")?;
+    let quality = names.iter().position(|n| n.starts_with("QN"));
+    let columns = resolution
+        .columns()
+        .iter()
+        .map(|c| position(c.name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    lines
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let time = parse_mess_datum(nth_column(line, time)?)?;
+            Ok(ObservationRecord {
+                timespan: Interval::new(time, time).unwrap(),
+                quality: match quality {
+                    Some(n) => parse_optional_column(line, n)?,
+                    None => None,
+                },
+                values: columns
+                    .iter()
+                    .map(|&n| parse_optional_column(line, n))
+                    .collect::<Result<_, _>>()?,
+            })
+        })
+        .collect()
+}
+
+/// `YYYYMMDDhhmm` (10 minutes), `YYYYMMDDhh` (hourly) or `YYYYMMDDhh:mm` (hourly solar)
+fn parse_mess_datum(s: &str) -> Result<PrimitiveDateTime, ParseError> {
+    match s.split_once(':') {
+        Some((hour, minute)) => parse_yyyymmddhhmm(&format!("{hour}{minute}")),
+        None if s.len() == 10 => parse_yyyymmddhh(s),
+        None => parse_yyyymmddhhmm(s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_produkt() {
+        let data = "STATIONS_ID;MESS_DATUM;  QN;PP_10;TT_10;TM5_10;RF_10;TD_10;eor
+         44;202301010000;    3;   -999;  12.3;  11.0;  78.4;   8.6;eor
+         44;202301010010;    3; 1001.2;  12.1;  -999;  79.0;   8.5;eor
+";
+        let records = parse_produkt(data, ObservationResolution::AirTemperatureMin10).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].timespan.start, datetime!(2023-01-01 00:00));
+        assert_eq!(records[0].quality, Some(3));
+        assert_eq!(
+            records[0].values,
+            vec![None, Some(12.3), Some(11.0), Some(78.4), Some(8.6)]
+        );
+        assert_eq!(records[1].timespan.start, datetime!(2023-01-01 00:10));
+        assert_eq!(records[1].values[2], None);
+
+        // the columns of another parameter aren't in the header
+        assert!(parse_produkt(data, ObservationResolution::WindMin10).is_err());
+    }
+
+    #[test]
+    fn test_parse_produkt_hourly() {
+        let data = "STATIONS_ID;MESS_DATUM;QN_8;V_VV_I;V_VV;eor
+         44;2023010112;    1;     P; 25000;eor
+";
+        let records = parse_produkt(data, ObservationResolution::VisibilityHourly).unwrap();
+        assert_eq!(records[0].timespan.start, datetime!(2023-01-01 12:00));
+        assert_eq!(records[0].values, vec![Some(25000.0)]);
+
+        let data = "STATIONS_ID;MESS_DATUM;QN_592;ATMO_LBERG;FD_LBERG;FG_LBERG;SD_LBERG;ZENIT;MESS_DATUM_WOZ;eor
+        183;2023010100:00;    4;   -999;      0.0;      0.0;      0;  147.60;2023010100:53;eor
+";
+        let records = parse_produkt(data, ObservationResolution::SolarHourly).unwrap();
+        assert_eq!(records[0].timespan.start, datetime!(2023-01-01 00:00));
+        assert_eq!(
+            records[0].values,
+            vec![None, Some(0.0), Some(0.0), Some(0.0), Some(147.6)]
+        );
+    }
+}
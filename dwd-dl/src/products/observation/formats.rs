@@ -0,0 +1,91 @@
+use std::io::Write;
+
+use serde::Deserialize;
+
+use crate::{error::Result, util::time::format_yyyymmddhhmm};
+
+use super::{ObservationRecord, ObservationResolution};
+
+/// Writes the header and the records of `station` into `writer`
+pub type ObservationWriter = fn(
+    &mut dyn Write,
+    &str,
+    &mut dyn Iterator<Item = Result<ObservationRecord>>,
+    &ObservationResolution,
+) -> Result<()>;
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub enum ObservationFormat {
+    Standard,
+}
+
+impl ObservationFormat {
+    pub fn write_method(&self) -> ObservationWriter {
+        match self {
+            ObservationFormat::Standard => write_standard,
+        }
+    }
+}
+
+/// Tab separated `STATIONS_ID`, `MESS_DATUM`, `QN` and the value columns,
+/// missing values are `-999` like in the DWD files
+pub fn write_standard(
+    writer: &mut dyn Write,
+    station: &str,
+    records: &mut dyn Iterator<Item = Result<ObservationRecord>>,
+    resolution: &ObservationResolution,
+) -> Result<()> {
+    let mut header = vec!["STATIONS_ID", "MESS_DATUM", "QN"];
+    header.extend(resolution.columns().iter().map(|c| c.name));
+    writeln!(writer, "{}", header.join("\t"))?;
+
+    let missing = |value: Option<String>| value.unwrap_or_else(|| "-999".into());
+    for record in records {
+        let record = record?;
+        let mut row = vec![
+            station.to_string(),
+            format_yyyymmddhhmm(record.timespan.start),
+            missing(record.quality.map(|q| q.to_string())),
+        ];
+        row.extend(
+            record
+                .values
+                .iter()
+                .map(|v| missing(v.map(|v| v.to_string()))),
+        );
+        writeln!(writer, "{}", row.join("\t"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use crate::util::interval::Interval;
+
+    use super::*;
+
+    #[test]
+    fn test_write_standard() {
+        let time = datetime!(2023-01-01 12:00);
+        let records = vec![ObservationRecord {
+            timespan: Interval::new(time, time).unwrap(),
+            quality: Some(1),
+            values: vec![Some(1012.5), None],
+        }];
+        let mut buf = Vec::new();
+        write_standard(
+            &mut buf,
+            "00044",
+            &mut records.into_iter().map(Ok),
+            &ObservationResolution::PressureHourly,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "STATIONS_ID\tMESS_DATUM\tQN\tP\tP0\n00044\t202301011200\t1\t1012.5\t-999\n"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use serde::Deserialize;
+
+use crate::products::climate::Column;
+
+/// Parameter and interval of the station observations, e.g. `AirTemperatureMin10`
+/// for `10_minutes/air_temperature`
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, strum_macros::Display)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub enum ObservationResolution {
+    AirTemperatureMin10,
+    AirTemperatureHourly,
+    WindMin10,
+    WindHourly,
+    SolarMin10,
+    SolarHourly,
+    SunHourly,
+    SoilTemperatureHourly,
+    PressureHourly,
+    VisibilityHourly,
+}
+
+impl ObservationResolution {
+    /// directory below `observations_germany/climate/`
+    pub fn directory(&self) -> &'static str {
+        match self {
+            ObservationResolution::AirTemperatureMin10 => "10_minutes/air_temperature",
+            ObservationResolution::AirTemperatureHourly => "hourly/air_temperature",
+            ObservationResolution::WindMin10 => "10_minutes/wind",
+            ObservationResolution::WindHourly => "hourly/wind",
+            ObservationResolution::SolarMin10 => "10_minutes/solar",
+            ObservationResolution::SolarHourly => "hourly/solar",
+            ObservationResolution::SunHourly => "hourly/sun",
+            ObservationResolution::SoilTemperatureHourly => "hourly/soil_temperature",
+            ObservationResolution::PressureHourly => "hourly/pressure",
+            ObservationResolution::VisibilityHourly => "hourly/visibility",
+        }
+    }
+
+    /// start of the file names, e.g. `stundenwerte_TU` in `stundenwerte_TU_00044_akt.zip`
+    pub(super) fn file_prefix(&self) -> &'static str {
+        match self {
+            ObservationResolution::AirTemperatureMin10 => "10minutenwerte_TU",
+            ObservationResolution::AirTemperatureHourly => "stundenwerte_TU",
+            ObservationResolution::WindMin10 => "10minutenwerte_wind",
+            ObservationResolution::WindHourly => "stundenwerte_FF",
+            ObservationResolution::SolarMin10 => "10minutenwerte_SOLAR",
+            ObservationResolution::SolarHourly => "stundenwerte_ST",
+            ObservationResolution::SunHourly => "stundenwerte_SD",
+            ObservationResolution::SoilTemperatureHourly => "stundenwerte_EB",
+            ObservationResolution::PressureHourly => "stundenwerte_P0",
+            ObservationResolution::VisibilityHourly => "stundenwerte_VV",
+        }
+    }
+
+    /// only the 10 minute values have a `now` directory
+    pub(super) fn has_now(&self) -> bool {
+        matches!(
+            self,
+            ObservationResolution::AirTemperatureMin10
+                | ObservationResolution::WindMin10
+                | ObservationResolution::SolarMin10
+        )
+    }
+
+    /// hourly solar has no `historical` and `recent` directories, only one `_row.zip` per station
+    pub(crate) fn is_row(&self) -> bool {
+        matches!(self, ObservationResolution::SolarHourly)
+    }
+
+    /// value columns of the `produkt` file, without `STATIONS_ID`, `MESS_DATUM` and the quality level
+    pub fn columns(&self) -> &'static [Column] {
+        match self {
+            ObservationResolution::AirTemperatureMin10 => &AIR_TEMPERATURE_MIN10,
+            ObservationResolution::AirTemperatureHourly => &AIR_TEMPERATURE_HOURLY,
+            ObservationResolution::WindMin10 => &WIND_MIN10,
+            ObservationResolution::WindHourly => &WIND_HOURLY,
+            ObservationResolution::SolarMin10 => &SOLAR_MIN10,
+            ObservationResolution::SolarHourly => &SOLAR_HOURLY,
+            ObservationResolution::SunHourly => &SUN_HOURLY,
+            ObservationResolution::SoilTemperatureHourly => &SOIL_TEMPERATURE_HOURLY,
+            ObservationResolution::PressureHourly => &PRESSURE_HOURLY,
+            ObservationResolution::VisibilityHourly => &VISIBILITY_HOURLY,
+        }
+    }
+}
+
+const fn column(name: &'static str, unit: &'static str) -> Column {
+    Column {
+        name,
+        unit: Some(unit),
+    }
+}
+
+const AIR_TEMPERATURE_MIN10: [Column; 5] = [
+    column("PP_10", "hPa"),
+    column("TT_10", "°C"),
+    column("TM5_10", "°C"),
+    column("RF_10", "%"),
+    column("TD_10", "°C"),
+];
+
+const AIR_TEMPERATURE_HOURLY: [Column; 2] = [column("TT_TU", "°C"), column("RF_TU", "%")];
+
+const WIND_MIN10: [Column; 2] = [column("FF_10", "m/s"), column("DD_10", "°")];
+
+const WIND_HOURLY: [Column; 2] = [column("F", "m/s"), column("D", "°")];
+
+const SOLAR_MIN10: [Column; 4] = [
+    column("DS_10", "J/cm²"),
+    column("GS_10", "J/cm²"),
+    column("SD_10", "h"),
+    column("LS_10", "J/cm²"),
+];
+
+const SOLAR_HOURLY: [Column; 5] = [
+    column("ATMO_LBERG", "J/cm²"),
+    column("FD_LBERG", "J/cm²"),
+    column("FG_LBERG", "J/cm²"),
+    column("SD_LBERG", "min"),
+    column("ZENIT", "°"),
+];
+
+const SUN_HOURLY: [Column; 1] = [column("SD_SO", "min")];
+
+const SOIL_TEMPERATURE_HOURLY: [Column; 6] = [
+    column("V_TE002", "°C"),
+    column("V_TE005", "°C"),
+    column("V_TE010", "°C"),
+    column("V_TE020", "°C"),
+    column("V_TE050", "°C"),
+    column("V_TE100", "°C"),
+];
+
+const PRESSURE_HOURLY: [Column; 2] = [column("P", "hPa"), column("P0", "hPa")];
+
+/// without `V_VV_I`, a letter for the kind of observation
+const VISIBILITY_HOURLY: [Column; 1] = [column("V_VV", "m")];
@@ -0,0 +1,136 @@
+use crate::{
+    base_url,
+    dwd_source::{self, UrlTimeIntervall},
+    error::{DwdError, Result},
+    util::{
+        file::File,
+        regex::{extract_interval_d8_d8, links_in_text},
+    },
+};
+
+use super::{extract_records, ObservationCommonRequestData, ObservationRecord};
+
+fn directory_url(request_data: &ObservationCommonRequestData, period: &str) -> String {
+    let directory = request_data.resolution.directory();
+    match period.is_empty() {
+        true => format!(
+            "{}climate_environment/CDC/observations_germany/climate/{}/",
+            base_url(),
+            directory
+        ),
+        false => format!(
+            "{}climate_environment/CDC/observations_germany/climate/{}/{}/",
+            base_url(),
+            directory,
+            period
+        ),
+    }
+}
+
+/// the single file of a station in `url` ending with `_{suffix}.zip`
+fn station_file(
+    request_data: &ObservationCommonRequestData,
+    url: &str,
+    suffix: &str,
+) -> Result<Vec<UrlTimeIntervall>> {
+    let html = request_data.common.listings.get(url)?;
+    let regex = format!(
+        r"{}_{}_{}.zip",
+        request_data.resolution.file_prefix(),
+        request_data.station,
+        suffix
+    );
+    let links = links_in_text(&html, &regex);
+    Ok(links
+        .iter()
+        .map(|link| UrlTimeIntervall {
+            url: format!("{}{}", url, link),
+            interval: None,
+        })
+        .collect())
+}
+
+// https://opendata.dwd.de/climate_environment/CDC/observations_germany/climate/hourly/air_temperature/historical/
+// stundenwerte_TU_00044_20070401_20231231_hist.zip
+pub struct Historical;
+
+impl dwd_source::DwdSource for Historical {
+    type Record = ObservationRecord;
+    type RequestData = ObservationCommonRequestData;
+
+    fn urls(&self, request_data: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>> {
+        let url = directory_url(request_data, "historical");
+        let html = request_data.common.listings.get(&url)?;
+        let regex = format!(
+            r"{}_{}_\d{{8}}_\d{{8}}_hist.zip",
+            request_data.resolution.file_prefix(),
+            request_data.station
+        );
+        let links = links_in_text(&html, &regex);
+
+        links
+            .iter()
+            .map(|link| {
+                let interval = extract_interval_d8_d8(link)
+                    .map_err(|err| DwdError::listing_parse(&url, err))?;
+                Ok(UrlTimeIntervall {
+                    url: format!("{}{}", url, link),
+                    interval: Some(interval.into()),
+                })
+            })
+            .collect()
+    }
+
+    fn extract_data(
+        &self,
+        request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>> {
+        extract_records(file, request_data.resolution)
+    }
+}
+
+// https://opendata.dwd.de/climate_environment/CDC/observations_germany/climate/hourly/air_temperature/recent/
+// stundenwerte_TU_00044_akt.zip, for hourly solar hourly/solar/stundenwerte_ST_00183_row.zip
+pub struct Recent;
+
+impl dwd_source::DwdSource for Recent {
+    type Record = ObservationRecord;
+    type RequestData = ObservationCommonRequestData;
+
+    fn urls(&self, request_data: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>> {
+        match request_data.resolution.is_row() {
+            true => station_file(request_data, &directory_url(request_data, ""), "row"),
+            false => station_file(request_data, &directory_url(request_data, "recent"), "akt"),
+        }
+    }
+
+    fn extract_data(
+        &self,
+        request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>> {
+        extract_records(file, request_data.resolution)
+    }
+}
+
+// https://opendata.dwd.de/climate_environment/CDC/observations_germany/climate/10_minutes/air_temperature/now/
+// 10minutenwerte_TU_00044_now.zip
+pub struct Now;
+
+impl dwd_source::DwdSource for Now {
+    type Record = ObservationRecord;
+    type RequestData = ObservationCommonRequestData;
+
+    fn urls(&self, request_data: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>> {
+        station_file(request_data, &directory_url(request_data, "now"), "now")
+    }
+
+    fn extract_data(
+        &self,
+        request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>> {
+        extract_records(file, request_data.resolution)
+    }
+}
@@ -1,11 +1,17 @@
-use std::vec;
+use std::{io::Write, vec};
 
+use serde::Deserialize;
 use time::PrimitiveDateTime;
 
 use crate::{
     dwd_source::{self, Common, CommonRequestData, DwdSource, Timespan},
+    error::{DwdError, ParseError, Result},
     util::{
+        compression::zip,
+        csv::parse_optional_column,
+        file::File,
         interval::Interval,
+        output::write_to_string,
         time::{format_date_american, format_time_colon, format_yyyymmddhhmm},
     },
 };
@@ -15,14 +21,15 @@ use self::resolutions::*;
 mod formats;
 mod resolutions;
 
-pub use formats::PrecipitationFormat;
+pub use formats::{PrecipitationFormat, PrecipitationFormatConfig, PrecipitationWriter};
 pub use resolutions::PrecipitationResolution;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PrecipitationCommonRequestData {
     pub common: CommonRequestData,
     pub station: String,
     pub resolution: PrecipitationResolution,
+    pub quality: QualityFilter,
 }
 
 impl Common for PrecipitationCommonRequestData {
@@ -41,6 +48,45 @@ pub struct PrecipitationResponse {
 pub struct PrecipitationRecord {
     pub timespan: Interval<PrimitiveDateTime>,
     pub rs: f32,
+    /// quality level (`QN`) of the row
+    pub quality: Option<u8>,
+    /// precipitation indicator (`RS_IND`), 1 if it has rained
+    pub indicator: Option<u8>,
+    /// height of the tipping bucket (`RTH`), only in the historical 1 and 5 minute values
+    pub rth: Option<f32>,
+    /// height of the weighing gauge (`RWH`), only in the historical 1 and 5 minute values
+    pub rwh: Option<f32>,
+    /// below the minimum quality level of [`QualityFilter::Flag`]
+    pub flagged: bool,
+}
+
+/// Handling of records below a minimum quality level,
+/// records without a quality level are always kept
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub enum QualityFilter {
+    #[default]
+    All,
+    /// leaves out the records
+    Drop(u8),
+    /// keeps the records with [`PrecipitationRecord::flagged`] set
+    Flag(u8),
+}
+
+impl QualityFilter {
+    /// `None` if the record is left out
+    fn apply(&self, mut record: PrecipitationRecord) -> Option<PrecipitationRecord> {
+        let below = |min: u8| record.quality.is_some_and(|quality| quality < min);
+        match *self {
+            QualityFilter::All => Some(record),
+            QualityFilter::Drop(min) if below(min) => None,
+            QualityFilter::Drop(_) => Some(record),
+            QualityFilter::Flag(min) => {
+                record.flagged = below(min);
+                Some(record)
+            }
+        }
+    }
 }
 
 impl Timespan for PrecipitationRecord {
@@ -77,44 +123,84 @@ impl dwd_source::Sources for PrecipitationCommonRequestData {
     }
 }
 
-pub fn data_to_together(records: PrecipitationResponse) -> String {
-    let mut string = String::new();
+pub fn data_to_together(
+    records: PrecipitationResponse,
+    config: PrecipitationFormatConfig,
+) -> String {
+    write_to_string(|writer| {
+        write_together(
+            writer,
+            &records.station,
+            &mut records.records.into_iter().map(Ok),
+            config,
+        )
+    })
+}
+
+pub fn data_to_separated(
+    records: PrecipitationResponse,
+    config: PrecipitationFormatConfig,
+) -> String {
+    write_to_string(|writer| {
+        write_separated(
+            writer,
+            &records.station,
+            &mut records.records.into_iter().map(Ok),
+            config,
+        )
+    })
+}
+
+/// Streaming variant of [`data_to_together`], stops at the first failed record
+pub fn write_together(
+    writer: &mut dyn Write,
+    station: &str,
+    records: &mut dyn Iterator<Item = Result<PrecipitationRecord>>,
+    config: PrecipitationFormatConfig,
+) -> Result<()> {
     let header = ["STATIONS_ID", "MESS_DATUM", "RS"];
-    string.push_str(header.join("\t").as_str());
-    string.push('\n');
+    writeln!(writer, "{}{}", header.join("\t"), config.header())?;
 
-    for record in records.records {
-        let mut record_str = Vec::new();
-        record_str.push(records.station.clone());
+    for record in records {
+        let record = record?;
         let datetime_str = format_yyyymmddhhmm(record.timespan.start);
-        record_str.push(datetime_str);
-        record_str.push(format!("{:.2}", record.rs));
-
-        string.push_str(&record_str.join("\t"));
-        string.push('\n');
+        writeln!(
+            writer,
+            "{}\t{}\t{:.2}{}",
+            station,
+            datetime_str,
+            record.rs,
+            config.flags(&record)
+        )?;
     }
-    string
+    Ok(())
 }
 
-pub fn data_to_separated(records: PrecipitationResponse) -> String {
-    let mut string = String::new();
+/// Streaming variant of [`data_to_separated`], stops at the first failed record
+pub fn write_separated(
+    writer: &mut dyn Write,
+    station: &str,
+    records: &mut dyn Iterator<Item = Result<PrecipitationRecord>>,
+    config: PrecipitationFormatConfig,
+) -> Result<()> {
     let header = ["Station", "Date", "Time", "Value"];
-    string.push_str(header.join("\t").as_str());
-    string.push('\n');
+    writeln!(writer, "{}{}", header.join("\t"), config.header())?;
 
-    for record in records.records {
-        let mut record_str = Vec::new();
-        record_str.push(records.station.clone());
+    for record in records {
+        let record = record?;
         let date_str = format_date_american(record.timespan.start);
-        record_str.push(date_str);
         let time_str = format_time_colon(record.timespan.start);
-        record_str.push(time_str);
-        record_str.push(format!("{:.2}", record.rs));
-
-        string.push_str(&record_str.join("\t"));
-        string.push('\n');
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{:.2}{}",
+            station,
+            date_str,
+            time_str,
+            record.rs,
+            config.flags(&record)
+        )?;
     }
-    string
+    Ok(())
 }
 
 pub struct Product;
@@ -123,11 +209,69 @@ impl dwd_source::DwdProduct for Product {
     type Request = PrecipitationCommonRequestData;
     type Response = PrecipitationResponse;
 
-    fn downloadx(&self, request: Self::Request) -> Self::Response {
-        PrecipitationResponse {
+    fn downloadx(&self, request: Self::Request) -> Result<Self::Response> {
+        Ok(PrecipitationResponse {
             station: request.station.clone(),
-            records: self.download(&request),
-        }
+            records: self.download(&request)?,
+        })
+    }
+}
+
+/// Timespan and precipitation height of a line of the `produkt` file
+type LineParser = fn(&str) -> Result<(Interval<PrimitiveDateTime>, f32), ParseError>;
+
+/// Parses every line (except the header) of the `produkt` file inside the station zip
+fn extract_records(
+    file: File,
+    filter: QualityFilter,
+    parse_line: LineParser,
+) -> Result<Vec<PrecipitationRecord>> {
+    let name = file.name.clone();
+    let data = zip::extract_text(file, "produkt")?;
+    parse_produkt(&data, filter, parse_line).map_err(|err| DwdError::record_parse(&name, err))
+}
+
+/// The quality level, indicator and heights are looked up by their name in the header,
+/// their position differs between the resolutions and periods
+fn parse_produkt(
+    data: &str,
+    filter: QualityFilter,
+    parse_line: LineParser,
+) -> Result<Vec<PrecipitationRecord>, ParseError> {
+    let mut lines = data.lines();
+    let header = lines.next().unwrap_or_default();
+    let names = header.split(';').map(str::trim).collect::<Vec<_>>();
+    let position = |matches: fn(&str) -> bool| names.iter().position(|n| matches(n));
+    let quality = position(|n| n.starts_with("QN"));
+    let indicator = position(|n| n.contains("_IND"));
+    let rth = position(|n| n.starts_with("RTH"));
+    let rwh = position(|n| n.starts_with("RWH"));
+
+    let mut records = Vec::new();
+    for line in lines {
+        let (timespan, rs) = parse_line(line)?;
+        let record = PrecipitationRecord {
+            timespan,
+            rs,
+            quality: parse_optional(line, quality)?,
+            indicator: parse_optional(line, indicator)?,
+            rth: parse_optional(line, rth)?,
+            rwh: parse_optional(line, rwh)?,
+            flagged: false,
+        };
+        records.extend(filter.apply(record));
+    }
+    Ok(records)
+}
+
+/// `None` if the column isn't in the header or the value is missing
+fn parse_optional<T: std::str::FromStr>(
+    line: &str,
+    column: Option<usize>,
+) -> Result<Option<T>, ParseError> {
+    match column {
+        Some(n) => parse_optional_column(line, n),
+        None => Ok(None),
     }
 }
 
@@ -135,6 +279,11 @@ impl dwd_source::DwdProduct for Product {
 mod test {
     use time::macros::datetime;
 
+    use crate::util::{
+        csv::{nth_column, parse_column},
+        time::{parse_yyyymmddhh, parse_yyyymmddhhmm},
+    };
+
     use super::*;
 
     fn generate_common_data() -> PrecipitationResponse {
@@ -148,6 +297,11 @@ mod test {
                     )
                     .unwrap(),
                     rs: 10.0,
+                    quality: Some(3),
+                    indicator: Some(1),
+                    rth: None,
+                    rwh: None,
+                    flagged: false,
                 },
                 PrecipitationRecord {
                     timespan: Interval::new(
@@ -156,6 +310,11 @@ mod test {
                     )
                     .unwrap(),
                     rs: 13.0,
+                    quality: Some(3),
+                    indicator: Some(1),
+                    rth: None,
+                    rwh: None,
+                    flagged: false,
                 },
                 PrecipitationRecord {
                     timespan: Interval::new(
@@ -164,6 +323,11 @@ mod test {
                     )
                     .unwrap(),
                     rs: 9.0,
+                    quality: Some(3),
+                    indicator: Some(1),
+                    rth: None,
+                    rwh: None,
+                    flagged: false,
                 },
             ],
         }
@@ -172,7 +336,7 @@ mod test {
     #[test]
     fn test_format_date_together() {
         let test_data = generate_common_data();
-        let result = data_to_together(test_data);
+        let result = data_to_together(test_data, Default::default());
 
         let assert = r#"STATIONS_ID\tMESS_DATUM\tRS
 00001\t202201102000\t10.00
@@ -186,7 +350,7 @@ mod test {
     #[test]
     fn test_format_date_separated() {
         let test_data = generate_common_data();
-        let result = data_to_separated(test_data);
+        let result = data_to_separated(test_data, Default::default());
 
         let assert = r"Station\tDate\tTime\tValue
 00001\t01/10/2022\t20:00\t10.00
@@ -196,4 +360,84 @@ mod test {
         let assert = assert.replace(r"\t", "\t");
         assert_eq!(result, assert);
     }
+
+    #[test]
+    fn test_write_stops_at_error() {
+        let mut records = generate_common_data()
+            .records
+            .into_iter()
+            .map(Ok)
+            .take(1)
+            .chain([Err(DwdError::record_parse("produkt.txt", "invalid line"))]);
+        let mut buf = Vec::new();
+        let result = write_together(&mut buf, "00001", &mut records, Default::default());
+
+        assert!(matches!(result, Err(DwdError::RecordParse { .. })));
+        let assert = "STATIONS_ID\tMESS_DATUM\tRS\n00001\t202201102000\t10.00\n";
+        assert_eq!(String::from_utf8(buf).unwrap(), assert);
+    }
+
+    #[test]
+    fn test_format_quality() {
+        let mut test_data = generate_common_data();
+        test_data.records[1].quality = None;
+        test_data.records[2].flagged = true;
+        test_data.records[0].rth = Some(10.0);
+        test_data.records[0].rwh = Some(9.9);
+        let result = data_to_separated(test_data, PrecipitationFormatConfig { quality: true });
+
+        let assert = r"Station\tDate\tTime\tValue\tQN\tRS_IND\tRTH\tRWH\tFLAG
+00001\t01/10/2022\t20:00\t10.00\t3\t1\t10.00\t9.90\t0
+00001\t01/11/2022\t20:00\t13.00\t-999\t1\t-999\t-999\t0
+00001\t01/12/2022\t20:00\t9.00\t3\t1\t-999\t-999\t1
+";
+        let assert = assert.replace(r"\t", "\t");
+        assert_eq!(result, assert);
+    }
+
+    #[test]
+    fn test_parse_produkt() {
+        let data =
+            "STATIONS_ID;MESS_DATUM_BEGINN;MESS_DATUM_ENDE;QN;RS_01;RTH_01;RWH_01;RS_IND_01;eor
+   20;202201010000;202201010000;    1;   0.10;   0.10;   -999;   1;eor
+   20;202201010001;202201010001;    3;   0.00;   0.00;   0.00;   0;eor
+";
+        let parse_line = |line: &str| {
+            let time = parse_yyyymmddhhmm(nth_column(line, 1)?)?;
+            let rs = parse_column(line, 4)?;
+            Ok((Interval::new(time, time).unwrap(), rs))
+        };
+
+        let records = parse_produkt(data, QualityFilter::All, parse_line).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].rs, 0.1);
+        assert_eq!(records[0].quality, Some(1));
+        assert_eq!(records[0].indicator, Some(1));
+        assert_eq!(records[0].rth, Some(0.1));
+        assert_eq!(records[0].rwh, None);
+        assert!(!records[0].flagged);
+
+        let records = parse_produkt(data, QualityFilter::Flag(2), parse_line).unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records[0].flagged);
+        assert!(!records[1].flagged);
+
+        let records = parse_produkt(data, QualityFilter::Drop(2), parse_line).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].quality, Some(3));
+
+        // the hourly values have no heights of the gauges
+        let data = "STATIONS_ID;MESS_DATUM;QN_8;  R1;RS_IND;WRTR;eor
+        164;2022123120;    3;   0.4;   1;   6;eor
+";
+        let parse_line = |line: &str| {
+            let time = parse_yyyymmddhh(nth_column(line, 1)?)?;
+            let rs = parse_column(line, 3)?;
+            Ok((Interval::new(time, time).unwrap(), rs))
+        };
+        let records = parse_produkt(data, QualityFilter::All, parse_line).unwrap();
+        assert_eq!(records[0].quality, Some(3));
+        assert_eq!(records[0].indicator, Some(1));
+        assert_eq!(records[0].rth, None);
+    }
 }
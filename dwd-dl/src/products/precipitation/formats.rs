@@ -1,6 +1,21 @@
+use std::io::Write;
+
 use serde::Deserialize;
 
-use super::{data_to_separated, data_to_together, PrecipitationResponse};
+use crate::error::Result;
+
+use super::{
+    data_to_separated, data_to_together, write_separated, write_together, PrecipitationRecord,
+    PrecipitationResponse,
+};
+
+/// Writes the header and the records of a station into `writer`
+pub type PrecipitationWriter = fn(
+    &mut dyn Write,
+    &str,
+    &mut dyn Iterator<Item = Result<PrecipitationRecord>>,
+    PrecipitationFormatConfig,
+) -> Result<()>;
 
 #[derive(Debug, Deserialize, Clone, Copy)]
 #[cfg_attr(feature = "specta", derive(specta::Type))]
@@ -10,10 +25,53 @@ pub enum PrecipitationFormat {
 }
 
 impl PrecipitationFormat {
-    pub fn format_method(&self) -> fn(PrecipitationResponse) -> String {
+    pub fn format_method(&self) -> fn(PrecipitationResponse, PrecipitationFormatConfig) -> String {
         match self {
             PrecipitationFormat::DateSeparated => data_to_separated,
             PrecipitationFormat::DateTogether => data_to_together,
         }
     }
+
+    pub fn write_method(&self) -> PrecipitationWriter {
+        match self {
+            PrecipitationFormat::DateSeparated => write_separated,
+            PrecipitationFormat::DateTogether => write_together,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct PrecipitationFormatConfig {
+    /// adds the columns `QN`, `RS_IND`, `RTH`, `RWH` and `FLAG`, missing values are `-999`
+    #[serde(default)]
+    pub quality: bool,
+}
+
+impl PrecipitationFormatConfig {
+    /// the columns appended to the header, empty without [`Self::quality`]
+    pub(super) fn header(&self) -> &'static str {
+        match self.quality {
+            true => "\tQN\tRS_IND\tRTH\tRWH\tFLAG",
+            false => "",
+        }
+    }
+
+    /// the values appended to the row of `record`
+    pub(super) fn flags(&self, record: &PrecipitationRecord) -> String {
+        if !self.quality {
+            return String::new();
+        }
+        let missing = |value: Option<u8>| value.map_or_else(|| "-999".into(), |v| v.to_string());
+        let height =
+            |value: Option<f32>| value.map_or_else(|| "-999".into(), |v| format!("{v:.2}"));
+        format!(
+            "\t{}\t{}\t{}\t{}\t{}",
+            missing(record.quality),
+            missing(record.indicator),
+            height(record.rth),
+            height(record.rwh),
+            record.flagged as u8
+        )
+    }
 }
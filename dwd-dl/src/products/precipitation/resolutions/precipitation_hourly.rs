@@ -1,10 +1,12 @@
 use crate::{
     base_url,
     dwd_source::{self, UrlTimeIntervall},
-    products::precipitation::{PrecipitationCommonRequestData, PrecipitationRecord},
+    error::{DwdError, Result},
+    products::precipitation::{
+        extract_records, PrecipitationCommonRequestData, PrecipitationRecord,
+    },
     util::{
-        self,
-        download::download_text,
+        csv::{nth_column, parse_column},
         file::File,
         interval::Interval,
         regex::{extract_interval_d8_d8, links_in_text},
@@ -18,12 +20,12 @@ impl dwd_source::DwdSource for Historical {
     type Record = PrecipitationRecord;
     type RequestData = PrecipitationCommonRequestData;
 
-    fn urls(&self, request_data: &Self::RequestData) -> Vec<UrlTimeIntervall> {
+    fn urls(&self, request_data: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>> {
         let url = format!(
             "{}climate_environment/CDC/observations_germany/climate/hourly/precipitation/historical/",
             base_url()
         );
-        let html = download_text(&url, None);
+        let html = request_data.common.listings.get(&url)?;
         let regex = format!(
             r"stundenwerte_RR_{}_\d{{8}}_\d{{8}}_hist.zip",
             request_data.station
@@ -32,25 +34,28 @@ impl dwd_source::DwdSource for Historical {
 
         links
             .iter()
-            .map(|link| UrlTimeIntervall {
-                url: format!("{}{}", url, link),
-                interval: Some(extract_interval_d8_d8(link).unwrap().into()),
+            .map(|link| {
+                let interval = extract_interval_d8_d8(link)
+                    .map_err(|err| DwdError::listing_parse(&url, err))?;
+                Ok(UrlTimeIntervall {
+                    url: format!("{}{}", url, link),
+                    interval: Some(interval.into()),
+                })
             })
-            .collect::<Vec<_>>()
+            .collect()
     }
 
-    fn extract_data(&self, _request_data: &Self::RequestData, file: File) -> Vec<Self::Record> {
-        let bytes = util::compression::zip::extract_file(file.data, "produkt");
-        let data = String::from_utf8(bytes).unwrap();
-        data.lines()
-            .skip(1)
-            .map(|line| {
-                let time = parse_yyyymmddhh(line.split(';').nth(1).unwrap()).unwrap();
-                let timespan = Interval::new(time, time).unwrap();
-                let rs = line.split(';').nth(4).unwrap().trim().parse().unwrap();
-                Self::Record { rs, timespan }
-            })
-            .collect()
+    fn extract_data(
+        &self,
+        request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>> {
+        extract_records(file, request_data.quality, |line| {
+            let time = parse_yyyymmddhh(nth_column(line, 1)?)?;
+            let timespan = Interval::new(time, time).unwrap();
+            let rs = parse_column(line, 4)?;
+            Ok((timespan, rs))
+        })
     }
 }
 
@@ -60,36 +65,36 @@ impl dwd_source::DwdSource for Recent {
     type Record = PrecipitationRecord;
     type RequestData = PrecipitationCommonRequestData;
 
-    fn urls(&self, request_data: &Self::RequestData) -> Vec<UrlTimeIntervall> {
+    fn urls(&self, request_data: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>> {
         let url = format!(
             "{}climate_environment/CDC/observations_germany/climate/hourly/precipitation/recent/",
             base_url()
         );
-        let html = download_text(&url, None);
+        let html = request_data.common.listings.get(&url)?;
         let regex = format!(r"stundenwerte_RR_{}_akt.zip", request_data.station);
 
         let links = links_in_text(&html, &regex);
-        links
+        let links = links
             .iter()
             .map(|link| UrlTimeIntervall {
                 url: format!("{}{}", url, link),
                 interval: None,
             })
             .inspect(|x| println!("{:?}", x))
-            .collect::<Vec<_>>()
+            .collect::<Vec<_>>();
+        Ok(links)
     }
 
-    fn extract_data(&self, _request_data: &Self::RequestData, file: File) -> Vec<Self::Record> {
-        let bytes = util::compression::zip::extract_file(file.data, "produkt");
-        let data = String::from_utf8(bytes).unwrap();
-        data.lines()
-            .skip(1)
-            .map(|line| {
-                let time = parse_yyyymmddhh(line.split(';').nth(1).unwrap()).unwrap();
-                let timespan = Interval::new(time, time).unwrap();
-                let rs = line.split(';').nth(5).unwrap().trim().parse().unwrap();
-                Self::Record { rs, timespan }
-            })
-            .collect()
+    fn extract_data(
+        &self,
+        request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>> {
+        extract_records(file, request_data.quality, |line| {
+            let time = parse_yyyymmddhh(nth_column(line, 1)?)?;
+            let timespan = Interval::new(time, time).unwrap();
+            let rs = parse_column(line, 5)?;
+            Ok((timespan, rs))
+        })
     }
 }
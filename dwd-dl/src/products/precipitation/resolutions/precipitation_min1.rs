@@ -3,10 +3,12 @@ use time::PrimitiveDateTime;
 use crate::{
     base_url,
     dwd_source::{self, UrlTimeIntervall},
-    products::precipitation::{PrecipitationCommonRequestData, PrecipitationRecord},
+    error::{DwdError, ParseError, Result},
+    products::precipitation::{
+        extract_records, PrecipitationCommonRequestData, PrecipitationRecord,
+    },
     util::{
-        self,
-        download::download_text,
+        csv::{nth_column, parse_column},
         file::File,
         interval::Interval,
         regex::{extract_interval_d8_d8, links_in_text, year_links_in_text},
@@ -20,12 +22,12 @@ impl dwd_source::DwdSource for PrecipitationMin1Historical {
     type Record = PrecipitationRecord;
     type RequestData = PrecipitationCommonRequestData;
 
-    fn urls(&self, request_data: &Self::RequestData) -> Vec<UrlTimeIntervall> {
+    fn urls(&self, request_data: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>> {
         let url = format!(
             "{}climate_environment/CDC/observations_germany/climate/1_minute/precipitation/historical/",
             base_url()
         );
-        let html = download_text(&url, None);
+        let html = request_data.common.listings.get(&url)?;
         let mut years = year_links_in_text(&html);
         years.sort_unstable();
         let ts = request_data.common.timespan;
@@ -39,33 +41,36 @@ impl dwd_source::DwdSource for PrecipitationMin1Historical {
         //FIXME: dont recompile regex
         let mut links = Vec::new();
         for url in urls {
-            let html = download_text(&url, None);
+            let html = request_data.common.listings.get(&url)?;
             let regex = format!(
                 r"1minutenwerte_nieder_{}_\d{{8}}_\d{{8}}_hist.zip",
                 request_data.station
             );
             let current_links = links_in_text(&html, &regex);
 
-            links.extend(current_links.iter().map(|link| UrlTimeIntervall {
-                url: format!("{}{}", url, link),
-                interval: Some(extract_interval_d8_d8(link).unwrap().into()),
-            }));
+            for link in current_links {
+                let interval = extract_interval_d8_d8(&link)
+                    .map_err(|err| DwdError::listing_parse(&url, err))?;
+                links.push(UrlTimeIntervall {
+                    url: format!("{}{}", url, link),
+                    interval: Some(interval.into()),
+                });
+            }
         }
         dbg!(&links);
-        links
+        Ok(links)
     }
 
-    fn extract_data(&self, _request_data: &Self::RequestData, file: File) -> Vec<Self::Record> {
-        let bytes = util::compression::zip::extract_file(file.data, "produkt");
-        let data = String::from_utf8(bytes).unwrap();
-        data.lines()
-            .skip(1)
-            .map(|line| {
-                let timespan = extract_timespan(line);
-                let rs = line.split(';').nth(4).unwrap().trim().parse().unwrap();
-                Self::Record { rs, timespan }
-            })
-            .collect()
+    fn extract_data(
+        &self,
+        request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>> {
+        extract_records(file, request_data.quality, |line| {
+            let timespan = extract_timespan(line)?;
+            let rs = parse_column(line, 4)?;
+            Ok((timespan, rs))
+        })
     }
 }
 
@@ -75,37 +80,37 @@ impl dwd_source::DwdSource for PrecipitationMin1Recent {
     type Record = PrecipitationRecord;
     type RequestData = PrecipitationCommonRequestData;
 
-    fn urls(&self, request_data: &Self::RequestData) -> Vec<UrlTimeIntervall> {
+    fn urls(&self, request_data: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>> {
         let url = format!(
             "{}climate_environment/CDC/observations_germany/climate/1_minute/precipitation/recent/",
             base_url()
         );
-        let html = download_text(&url, None);
+        let html = request_data.common.listings.get(&url)?;
         let regex = format!(r"1minutenwerte_nieder_{}_akt.zip", request_data.station);
 
         let links = links_in_text(&html, &regex);
-        links
+        let links = links
             .iter()
             .map(|link| UrlTimeIntervall {
                 url: format!("{}{}", url, link),
                 interval: None,
             })
             .inspect(|x| println!("{:?}", x))
-            .collect::<Vec<_>>()
+            .collect::<Vec<_>>();
+        Ok(links)
     }
 
-    fn extract_data(&self, _request_data: &Self::RequestData, file: File) -> Vec<Self::Record> {
-        let bytes = util::compression::zip::extract_file(file.data, "produkt");
-        let data = String::from_utf8(bytes).unwrap();
-        data.lines()
-            .skip(1)
-            .map(|line| {
-                let time = parse_yyyymmddhhmm(line.split(';').nth(1).unwrap()).unwrap();
-                let timespan = Interval::new(time, time).unwrap();
-                let rs = line.split(';').nth(3).unwrap().trim().parse().unwrap();
-                Self::Record { rs, timespan }
-            })
-            .collect()
+    fn extract_data(
+        &self,
+        request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>> {
+        extract_records(file, request_data.quality, |line| {
+            let time = parse_yyyymmddhhmm(nth_column(line, 1)?)?;
+            let timespan = Interval::new(time, time).unwrap();
+            let rs = parse_column(line, 3)?;
+            Ok((timespan, rs))
+        })
     }
 }
 
@@ -115,44 +120,43 @@ impl dwd_source::DwdSource for PrecipitationMin1Now {
     type Record = PrecipitationRecord;
     type RequestData = PrecipitationCommonRequestData;
 
-    fn urls(&self, request_data: &Self::RequestData) -> Vec<UrlTimeIntervall> {
+    fn urls(&self, request_data: &Self::RequestData) -> Result<Vec<UrlTimeIntervall>> {
         let url = format!(
             "{}climate_environment/CDC/observations_germany/climate/1_minute/precipitation/now/",
             base_url()
         );
-        let html = download_text(&url, None);
+        let html = request_data.common.listings.get(&url)?;
         let regex = format!(r"1minutenwerte_nieder_{}_now.zip", request_data.station);
 
         let links = links_in_text(&html, &regex);
-        links
+        let links = links
             .iter()
             .map(|link| UrlTimeIntervall {
                 url: format!("{}{}", url, link),
                 interval: None,
             })
             .inspect(|x| println!("{:?}", x))
-            .collect::<Vec<_>>()
+            .collect::<Vec<_>>();
+        Ok(links)
     }
 
-    fn extract_data(&self, _request_data: &Self::RequestData, file: File) -> Vec<Self::Record> {
-        let bytes = util::compression::zip::extract_file(file.data, "produkt");
-        let data = String::from_utf8(bytes).unwrap();
-        data.lines()
-            .skip(1)
-            .map(|line| {
-                let time = parse_yyyymmddhhmm(line.split(';').nth(1).unwrap()).unwrap();
-                let timespan = Interval::new(time, time).unwrap();
-                let rs = line.split(';').nth(3).unwrap().trim().parse().unwrap();
-                Self::Record { rs, timespan }
-            })
-            .collect()
+    fn extract_data(
+        &self,
+        request_data: &Self::RequestData,
+        file: File,
+    ) -> Result<Vec<Self::Record>> {
+        extract_records(file, request_data.quality, |line| {
+            let time = parse_yyyymmddhhmm(nth_column(line, 1)?)?;
+            let timespan = Interval::new(time, time).unwrap();
+            let rs = parse_column(line, 3)?;
+            Ok((timespan, rs))
+        })
     }
 }
 
 /// for STATIONS_ID;MESS_DATUM_BEGINN;MESS_DATUM_ENDE; ...
-fn extract_timespan(s: &str) -> Interval<PrimitiveDateTime> {
-    let times = s.split(';').skip(1).take(2).collect::<Vec<_>>();
-    let start = parse_yyyymmddhhmm(times[0]).unwrap();
-    let end = parse_yyyymmddhhmm(times[1]).unwrap();
-    Interval::new(start, end).unwrap()
+fn extract_timespan(s: &str) -> Result<Interval<PrimitiveDateTime>, ParseError> {
+    let start = parse_yyyymmddhhmm(nth_column(s, 1)?)?;
+    let end = parse_yyyymmddhhmm(nth_column(s, 2)?)?;
+    Interval::new(start, end).map_err(|_| ParseError::new(s, "line with a valid interval"))
 }
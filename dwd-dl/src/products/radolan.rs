@@ -1,24 +1,65 @@
-use radolan::Radolan;
+use radolan::{header::Dimension, projection::Grid, Radolan};
 use time::PrimitiveDateTime;
 
 use crate::{
     dwd_source::{Common, CommonRequestData, DwdProduct, DwdSource, Sources, Timespan},
-    util::{interval::Interval, point::Point},
+    error::{DwdError, ParseError, Result},
+    progress::ProgressUpdate,
+    util::{
+        compression::universal::MultiLayerFolder, geo::LatLon, interval::Interval, point::Point,
+        polygon::MultiPolygon, regex::extract_d10, time::parse_yyyymmddhhmm,
+    },
 };
 
+pub mod area;
 pub mod formats;
 mod resolutions;
 
+pub use area::{Area, AreaCoordinates, AreaStatistics};
 pub use formats::RadolanFormat;
+pub use radolan::{header::Unit, record::Flag};
 pub use resolutions::RadolanResolution;
 
 #[derive(Debug, Clone)]
 pub struct RadolanRequest {
     pub common: CommonRequestData,
     pub coordinates: Vec<Point<u16>>,
+    /// aggregates an area in addition to the single coordinates
+    pub area: Option<Area>,
     pub resolution: RadolanResolution,
 }
 
+impl RadolanRequest {
+    /// Grid cells (x = column, y = row from the top) of WGS84 positions
+    pub fn cells_of(
+        resolution: RadolanResolution,
+        positions: &[LatLon],
+    ) -> Result<Vec<Point<u16>>, ParseError> {
+        let grid = resolution.grid();
+        positions
+            .iter()
+            .map(|p| {
+                let (row, column) = grid.cell(p.lat, p.lon).ok_or_else(|| {
+                    ParseError::new(
+                        &format!("{},{}", p.lat, p.lon),
+                        "position in the RADOLAN grid",
+                    )
+                })?;
+                Ok(Point::new(column, row))
+            })
+            .collect()
+    }
+
+    /// The cells covered by a polygon in the grid of `resolution`
+    pub fn area_of(
+        resolution: RadolanResolution,
+        polygon: &MultiPolygon,
+        coordinates: AreaCoordinates,
+    ) -> Result<Area, ParseError> {
+        Area::new(polygon, coordinates, resolution.grid())
+    }
+}
+
 impl Common for RadolanRequest {
     fn common(&self) -> &CommonRequestData {
         &self.common
@@ -28,7 +69,16 @@ impl Common for RadolanRequest {
 #[derive(Debug, Clone)]
 pub struct Record {
     pub time: PrimitiveDateTime,
+    /// errors and clutter are `-9`
     pub data: Vec<f32>,
+    /// flag of each value in `data`
+    pub flags: Vec<Flag>,
+    /// unit of the file, `None` for products which aren't tested
+    pub unit: Option<Unit>,
+    /// the values are multiples of `10^precision`
+    pub precision: i8,
+    /// `Some` if the request has an area
+    pub area: Option<AreaStatistics>,
 }
 
 impl Timespan for Record {
@@ -65,6 +115,7 @@ impl Sources for RadolanRequest {
 pub struct RadolanResponse {
     pub coordinates: Vec<Point<u16>>,
     pub records: Vec<Record>,
+    pub resolution: RadolanResolution,
 }
 
 pub struct Product;
@@ -73,21 +124,108 @@ impl DwdProduct for Product {
     type Request = RadolanRequest;
     type Response = RadolanResponse;
 
-    fn downloadx(&self, request: Self::Request) -> Self::Response {
-        let records = self.download(&request);
-        RadolanResponse {
+    fn downloadx(&self, request: Self::Request) -> Result<Self::Response> {
+        let records = self.download(&request)?;
+        Ok(RadolanResponse {
             coordinates: request.coordinates,
             records,
-        }
+            resolution: request.resolution,
+        })
     }
 }
 
 pub fn extract_points(
     points: &[Point<u16>],
     file: &Radolan,
-) -> Vec<(Point<u16>, radolan::record::Record)> {
+) -> Result<Vec<(Point<u16>, radolan::record::Record)>, ParseError> {
     points
         .iter()
-        .map(|p| (*p, file.get_point(p.y, p.x).unwrap()))
+        .map(|p| {
+            let record = file.get_point(p.y, p.x).map_err(|_| {
+                ParseError::new(&format!("{},{}", p.x, p.y), "point in the RADOLAN grid")
+            })?;
+            Ok((*p, record))
+        })
         .collect()
 }
+
+/// Decodes every composite inside `folder` and extracts the requested coordinates
+fn extract_records(request: &RadolanRequest, folder: MultiLayerFolder) -> Result<Vec<Record>> {
+    let archive = folder.name().to_string();
+    let mut records = Vec::new();
+    for file in folder {
+        let file = file?;
+        request.common.progress.report(ProgressUpdate::Member {
+            archive: archive.clone(),
+            member: file.name.clone(),
+        });
+        let parse_error = |err: ParseError| DwdError::record_parse(&file.name, err);
+        let time = datetime_from_name(&file.name).map_err(parse_error)?;
+
+        // a damaged member is reported as corrupt archive, which is downloaded again
+        let radolan = Radolan::new(&file.data)
+            .and_then(|radolan| radolan.validate().map(|_| radolan))
+            .map_err(|err| DwdError::corrupt_archive(&file.name, err))?;
+        check_grid(request.resolution, &radolan.header().dimension, &file.name)?;
+        let (unit, precision) = (radolan.header().unit(), radolan.header().precision);
+        let parsed = extract_points(&request.coordinates, &radolan).map_err(parse_error)?;
+        let flags = parsed.iter().map(|(_, v)| v.flag()).collect();
+        let parsed = parsed
+            .into_iter()
+            .map(|(_, v)| v.default_f32(precision))
+            .collect::<Vec<_>>();
+        let area = match &request.area {
+            Some(area) => Some(area.statistics(&radolan).map_err(parse_error)?),
+            None => None,
+        };
+
+        records.push(Record {
+            data: parsed,
+            flags,
+            unit,
+            precision,
+            time,
+            area,
+        });
+    }
+    Ok(records)
+}
+
+/// The cells of the request are in the grid of the resolution, which has to be the one of the file
+fn check_grid(resolution: RadolanResolution, dimension: &Dimension, file: &str) -> Result<()> {
+    let expected = resolution.grid();
+    match Grid::from_dimension(dimension) == Some(expected) {
+        true => Ok(()),
+        false => Err(DwdError::GridMismatch {
+            file: file.to_string(),
+            expected: format!("{}x{}", expected.rows, expected.columns),
+            found: format!("{}x{}", dimension.rows, dimension.columns),
+        }),
+    }
+}
+
+/// for names like `raa01-rw_10000-2301010050-dwd---bin` (YYMMDDhhmm)
+fn datetime_from_name(s: &str) -> Result<PrimitiveDateTime, ParseError> {
+    let date = extract_d10(s)?;
+    parse_yyyymmddhhmm(&format!("20{}", date))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_grid() {
+        let national = Dimension {
+            rows: 900,
+            columns: 900,
+        };
+        assert!(check_grid(RadolanResolution::RadolanHourly, &national, "raa01-rw").is_ok());
+
+        let err = check_grid(RadolanResolution::RadolanMin5, &national, "raa01-yw").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`raa01-yw` has a 900x900 grid, the resolution expects 1100x900"
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use radolan::{projection::Grid, Radolan};
+use serde::Deserialize;
+
+use crate::{
+    error::ParseError,
+    util::{point::Point, polygon::MultiPolygon},
+};
+
+/// Coordinate system of the polygon vertices
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub enum AreaCoordinates {
+    /// `(lon, lat)` in degrees
+    Wgs84,
+    /// `(column, row)` in cells, rows are counted from the top, the cell `(c, r)` spans `c..c + 1`
+    Grid,
+}
+
+/// The grid cells covered by a polygon, weighted by the covered fraction of each cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct Area {
+    cells: Vec<(Point<u16>, f64)>,
+}
+
+impl Area {
+    pub fn new(
+        polygon: &MultiPolygon,
+        coordinates: AreaCoordinates,
+        grid: Grid,
+    ) -> Result<Self, ParseError> {
+        let polygon = match coordinates {
+            AreaCoordinates::Wgs84 => polygon.map(|(lon, lat)| grid.position(lat, lon)),
+            AreaCoordinates::Grid => polygon.clone(),
+        };
+        let ((x_min, y_min), (x_max, y_max)) = polygon.bounding_box();
+        let range = |min: f64, max: f64, size: u16| {
+            min.floor().max(0.0) as u16..max.ceil().clamp(0.0, size as f64) as u16
+        };
+
+        let mut cells = Vec::new();
+        for row in range(y_min, y_max, grid.rows) {
+            for column in range(x_min, x_max, grid.columns) {
+                let (x, y) = (column as f64, row as f64);
+                let weight = polygon.clipped_area((x, y), (x + 1.0, y + 1.0));
+                // rounding errors of cells next to the polygon
+                if weight > 1e-9 {
+                    cells.push((Point::new(column, row), weight.min(1.0)));
+                }
+            }
+        }
+        match cells.is_empty() {
+            true => Err(ParseError::new(
+                &format!("{:?}", polygon.bounding_box()),
+                "polygon inside of the RADOLAN grid",
+            )),
+            false => Ok(Self { cells }),
+        }
+    }
+
+    /// Covered cells (x = column, y = row from the top) and the covered fraction of each
+    pub fn cells(&self) -> &[(Point<u16>, f64)] {
+        &self.cells
+    }
+
+    /// Aggregates the covered cells of a composite, cells with errors or clutter are left out
+    pub fn statistics(&self, file: &Radolan) -> Result<AreaStatistics, ParseError> {
+        let precision = file.header().precision;
+        let (mut weights, mut sum, mut max) = (0.0, 0.0, f32::NEG_INFINITY);
+        for (p, weight) in &self.cells {
+            let record = file.get_point(p.y, p.x).map_err(|_| {
+                ParseError::new(&format!("{},{}", p.x, p.y), "point in the RADOLAN grid")
+            })?;
+            let Some(value) = record.value(precision) else {
+                continue;
+            };
+            weights += weight;
+            sum += weight * value as f64;
+            max = max.max(value);
+        }
+
+        if weights == 0.0 {
+            return Ok(AreaStatistics::ERROR);
+        }
+        Ok(AreaStatistics {
+            mean: (sum / weights) as f32,
+            max,
+            sum: sum as f32,
+        })
+    }
+}
+
+/// Aggregated values of an [`Area`] for one timestep
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AreaStatistics {
+    /// mean weighted by the covered fraction of each cell
+    pub mean: f32,
+    pub max: f32,
+    /// sum of the values weighted by the covered fraction of each cell
+    pub sum: f32,
+}
+
+impl AreaStatistics {
+    pub fn is_error(&self) -> bool {
+        *self == Self::ERROR
+    }
+
+    /// every covered cell is an error, same value as [`radolan::record::Record::default_f32`]
+    const ERROR: AreaStatistics = AreaStatistics {
+        mean: -9.0,
+        max: -9.0,
+        sum: -9.0,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use radolan::{
+        encode::write_composite,
+        record::{Flags, Record},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_grid_area() {
+        let polygon = "POLYGON ((10 20, 12 20, 12 21.5, 10 21.5, 10 20))"
+            .parse::<MultiPolygon>()
+            .unwrap();
+        let area = Area::new(&polygon, AreaCoordinates::Grid, Grid::NATIONAL).unwrap();
+        assert_eq!(
+            area.cells(),
+            &[
+                (Point::new(10, 20), 1.0),
+                (Point::new(11, 20), 1.0),
+                (Point::new(10, 21), 0.5),
+                (Point::new(11, 21), 0.5),
+            ]
+        );
+
+        let outside = "POLYGON ((-5 -5, -1 -5, -1 -1, -5 -5))"
+            .parse::<MultiPolygon>()
+            .unwrap();
+        assert!(Area::new(&outside, AreaCoordinates::Grid, Grid::NATIONAL).is_err());
+    }
+
+    /// national composite with 0.1 mm precision
+    fn composite(cells: &[(Point<u16>, Record)]) -> Vec<u8> {
+        let header = radolan::header::Header::new(
+            "RW010050100000322BY      0VS 3SW   2.18.3PR E-01INT  60GP 900x 900MS 13<boo,ros,emd>",
+        )
+        .unwrap();
+        let mut records = vec![Record::normal(0); 900 * 900];
+        for (p, record) in cells {
+            records[p.y as usize * 900 + p.x as usize] = *record;
+        }
+        let mut file = Vec::new();
+        write_composite(&header, &records, &mut file).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_statistics() {
+        let polygon = "POLYGON ((10 20, 12 20, 12 21.5, 10 21.5, 10 20))"
+            .parse::<MultiPolygon>()
+            .unwrap();
+        let area = Area::new(&polygon, AreaCoordinates::Grid, Grid::NATIONAL).unwrap();
+
+        let file = composite(&[
+            (Point::new(10, 20), Record::normal(20)),
+            (Point::new(11, 20), Record::normal(40)),
+            (Point::new(10, 21), Record::normal(60)),
+            (Point::new(11, 21), Record::new(0, Flags::ERROR)),
+        ]);
+        let statistics = area.statistics(&Radolan::new(&file).unwrap()).unwrap();
+        assert_eq!(
+            statistics,
+            AreaStatistics {
+                mean: 3.6,
+                max: 6.0,
+                sum: 9.0,
+            }
+        );
+
+        let file = composite(&[
+            (Point::new(10, 20), Record::new(0, Flags::ERROR)),
+            (Point::new(11, 20), Record::new(0, Flags::ERROR)),
+            (Point::new(10, 21), Record::new(0, Flags::ERROR)),
+            (Point::new(11, 21), Record::new(0, Flags::ERROR)),
+        ]);
+        let statistics = area.statistics(&Radolan::new(&file).unwrap()).unwrap();
+        assert_eq!(statistics, AreaStatistics::ERROR);
+    }
+
+    #[test]
+    fn test_wgs84_area() {
+        // roughly 10 x 10 km around Berlin
+        let polygon =
+            "POLYGON ((13.33 52.475, 13.48 52.475, 13.48 52.565, 13.33 52.565, 13.33 52.475))"
+                .parse::<MultiPolygon>()
+                .unwrap();
+        let area = Area::new(&polygon, AreaCoordinates::Wgs84, Grid::NATIONAL).unwrap();
+        let covered = area.cells().iter().map(|(_, w)| w).sum::<f64>();
+        // the grid has 1 km cells, but is distorted by the projection
+        assert!((90.0..110.0).contains(&covered), "{covered}");
+
+        let (row, column) = Grid::NATIONAL.cell(52.52, 13.405).unwrap();
+        assert!(area.cells().contains(&(Point::new(column, row), 1.0)));
+    }
+}
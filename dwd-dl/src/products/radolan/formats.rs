@@ -1,27 +1,54 @@
-use std::{collections::HashMap, ops::Add};
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    ops::Add,
+};
 
+use radolan::record::Flag;
 use serde::Deserialize;
-use time::ext::NumericalDuration;
+use time::{ext::NumericalDuration, PrimitiveDateTime};
 
 use crate::{
+    error::Result,
     products::evaporation::decode::{sort_coordinates_x, sort_coordinates_y},
-    util::time::{format_date_iso, format_time_iso, timezone},
+    util::{
+        geo::LatLon,
+        netcdf::{axes, Cube},
+        output::write_to_string,
+        point::Point,
+        time::{format_date_iso, format_time_iso, timezone},
+    },
 };
 
-use super::RadolanResponse;
+use super::{AreaStatistics, RadolanResolution, RadolanResponse, Record};
 
 #[derive(Debug, Deserialize, Clone, Copy)]
 #[cfg_attr(feature = "specta", derive(specta::Type))]
 pub enum RadolanFormat {
     Default,
     SwmmRainfallData,
+    /// mean, max and sum of the requested area per timestep
+    AreaStatistics,
+    /// CF NetCDF cube over the rows and columns of the coordinates
+    NetCdf,
 }
 
+/// Writes the records of the coordinates into `writer` while they are downloaded
+pub type RadolanWriter = fn(
+    &mut dyn Write,
+    &[Point<u16>],
+    RadolanResolution,
+    &mut dyn Iterator<Item = Result<Record>>,
+    RadolanFormatConfig,
+) -> Result<()>;
+
 impl RadolanFormat {
-    pub fn format_method(&self) -> fn(RadolanResponse, RadolanFormatConfig) -> String {
+    pub fn write_method(&self) -> RadolanWriter {
         match self {
-            RadolanFormat::Default => format_default,
-            RadolanFormat::SwmmRainfallData => format_swmm_rainfall_data,
+            RadolanFormat::Default => write_default,
+            RadolanFormat::SwmmRainfallData => write_swmm_rainfall_data,
+            RadolanFormat::AreaStatistics => write_area_statistics,
+            RadolanFormat::NetCdf => write_netcdf,
         }
     }
 }
@@ -31,94 +58,342 @@ impl RadolanFormat {
 pub struct RadolanFormatConfig {
     pub utc_to_berlin: bool,
     pub offset: i8,
+    /// how values with errors or clutter are written
+    #[serde(default)]
+    pub nodata: NodataPolicy,
+    /// adds a column with the flag of each value
+    #[serde(default)]
+    pub quality: bool,
 }
 
-pub fn format_default(evaporation: RadolanResponse, config: RadolanFormatConfig) -> String {
-    let header = ["Name", "Date", "Time", "Value"];
-    let mut str = header.join("\t");
-    str.push('\n');
-
-    let coords = sort_coordinates_y(&evaporation.coordinates);
-    let coords_idx = coords
-        .iter()
-        .enumerate()
-        .map(|(i, p)| (p, i))
-        .collect::<HashMap<_, _>>();
-    let coords_x = sort_coordinates_x(&evaporation.coordinates);
-    let idx_table = coords_x
-        .iter()
-        .map(|p| *coords_idx.get(p).unwrap())
-        .collect::<Vec<_>>();
+/// Output of values without a measurement (errors and clutter)
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub enum NodataPolicy {
+    /// `-9`, the fill value in NetCDF
+    #[default]
+    Sentinel,
+    NaN,
+    /// leaves out the row, the fill value in NetCDF
+    Skip,
+    Zero,
+}
 
-    let mut tmp = Vec::new();
-    for (idx, coord) in coords_x.iter().enumerate() {
-        for record in &evaporation.records {
-            let mut time = record.time;
+impl RadolanFormatConfig {
+    /// `None` if the row is skipped
+    fn format_value(&self, value: f32, flag: Flag) -> Option<String> {
+        if !flag.is_nodata() {
+            return Some(format!("{:.2}", value));
+        }
+        match self.nodata {
+            NodataPolicy::Sentinel => Some(format!("{:.2}", value)),
+            NodataPolicy::NaN => Some("NaN".to_string()),
+            NodataPolicy::Skip => None,
+            NodataPolicy::Zero => Some(format!("{:.2}", 0.0)),
+        }
+    }
 
-            if config.utc_to_berlin {
-                time = timezone::convert_utc_to_berlin(time);
-            }
-            if config.offset != 0 {
-                time = time.add((config.offset as i64).minutes());
-            }
+    fn header(&self, header: &[&str]) -> String {
+        let mut header = header.join("\t");
+        if self.quality {
+            header.push_str("\tQuality");
+        }
+        header.push('\n');
+        header
+    }
 
-            tmp.push(format!("{:0>4}_{:0>4}", coord.x, coord.y));
-            tmp.push(format_date_iso(time.date()));
-            tmp.push(format_time_iso(time.time()));
-            tmp.push(format!("{:.2}", record.data[idx_table[idx]]));
-            str.push_str(&tmp.join("\t"));
-            str.push('\n');
-            tmp.clear();
+    fn local_time(&self, mut time: PrimitiveDateTime) -> PrimitiveDateTime {
+        if self.utc_to_berlin {
+            time = timezone::convert_utc_to_berlin(time);
+        }
+        if self.offset != 0 {
+            time = time.add((self.offset as i64).minutes());
         }
+        time
+    }
+}
+
+/// name of the area in the formats with one row per coordinate, the value is the mean
+const AREA_NAME: &str = "area";
+
+fn area_flag(area: &AreaStatistics) -> Flag {
+    match area.is_error() {
+        true => Flag::Error,
+        false => Flag::Normal,
     }
-    str
 }
 
-pub fn format_swmm_rainfall_data(
-    evaporation: RadolanResponse,
+pub fn format_default(radolan: RadolanResponse, config: RadolanFormatConfig) -> String {
+    write_to_string(|writer| {
+        write_default(
+            writer,
+            &radolan.coordinates,
+            radolan.resolution,
+            &mut radolan.records.into_iter().map(Ok),
+            config,
+        )
+    })
+}
+
+pub fn format_swmm_rainfall_data(radolan: RadolanResponse, config: RadolanFormatConfig) -> String {
+    write_to_string(|writer| {
+        write_swmm_rainfall_data(
+            writer,
+            &radolan.coordinates,
+            radolan.resolution,
+            &mut radolan.records.into_iter().map(Ok),
+            config,
+        )
+    })
+}
+
+pub fn format_area_statistics(radolan: RadolanResponse, config: RadolanFormatConfig) -> String {
+    write_to_string(|writer| {
+        write_area_statistics(
+            writer,
+            &radolan.coordinates,
+            radolan.resolution,
+            &mut radolan.records.into_iter().map(Ok),
+            config,
+        )
+    })
+}
+
+/// Streaming variant of [`format_default`]
+pub fn write_default(
+    writer: &mut dyn Write,
+    coordinates: &[Point<u16>],
+    resolution: RadolanResolution,
+    records: &mut dyn Iterator<Item = Result<Record>>,
     config: RadolanFormatConfig,
-) -> String {
-    let header = ["Name", "Jahr", "Monat", "Tag", "Stunde", "Minute", "Wert"];
-    let mut str = header.join("\t");
-    str.push('\n');
+) -> Result<()> {
+    let value = format!("Value [{}]", resolution.unit().symbol());
+    let header = config.header(&["Name", "Date", "Time", &value]);
+    write_rows(writer, coordinates, records, config, &header, |time| {
+        vec![format_date_iso(time.date()), format_time_iso(time.time())]
+    })
+}
 
-    let coords = sort_coordinates_y(&evaporation.coordinates);
+/// Streaming variant of [`format_swmm_rainfall_data`]
+pub fn write_swmm_rainfall_data(
+    writer: &mut dyn Write,
+    coordinates: &[Point<u16>],
+    resolution: RadolanResolution,
+    records: &mut dyn Iterator<Item = Result<Record>>,
+    config: RadolanFormatConfig,
+) -> Result<()> {
+    let value = format!("Wert [{}]", resolution.unit().symbol());
+    let header = config.header(&["Name", "Jahr", "Monat", "Tag", "Stunde", "Minute", &value]);
+    write_rows(writer, coordinates, records, config, &header, |time| {
+        vec![
+            time.date().year().to_string(),
+            format!("{:0>2}", time.date().month() as u8),
+            format!("{:0>2}", time.date().day()),
+            format!("{:0>2}", time.time().hour()),
+            format!("{:0>2}", time.time().minute()),
+        ]
+    })
+}
+
+/// Rows of every coordinate ordered by x and then of the area, `time_columns` formats the time of a row
+///
+/// The rows are grouped by coordinate, so the records of several coordinates are kept until the
+/// download is done, a single coordinate is written while downloading.
+fn write_rows(
+    writer: &mut dyn Write,
+    coordinates: &[Point<u16>],
+    records: &mut dyn Iterator<Item = Result<Record>>,
+    config: RadolanFormatConfig,
+    header: &str,
+    time_columns: fn(PrimitiveDateTime) -> Vec<String>,
+) -> Result<()> {
+    writer.write_all(header.as_bytes())?;
+    let mut write_row = |name: String, time, value: f32, flag: Flag| -> Result<()> {
+        let Some(value) = config.format_value(value, flag) else {
+            return Ok(());
+        };
+        let mut row = vec![name];
+        row.extend(time_columns(config.local_time(time)));
+        row.push(value);
+        if config.quality {
+            row.push(flag.name().to_string());
+        }
+        Ok(writeln!(writer, "{}", row.join("\t"))?)
+    };
+
+    let coords = sort_coordinates_y(coordinates);
     let coords_idx = coords
         .iter()
         .enumerate()
         .map(|(i, p)| (p, i))
         .collect::<HashMap<_, _>>();
-    let coords_x = sort_coordinates_x(&evaporation.coordinates);
-    let idx_table = coords_x
-        .iter()
-        .map(|p| *coords_idx.get(p).unwrap())
-        .collect::<Vec<_>>();
-
-    let mut tmp = Vec::new();
-    for (idx, coord) in coords_x.iter().enumerate() {
-        for record in &evaporation.records {
-            let mut time = record.time;
+    let coords_x = sort_coordinates_x(coordinates);
+    let name = |coord: &Point<u16>| format!("{:0>4}_{:0>4}", coord.x, coord.y);
 
-            if config.utc_to_berlin {
-                time = timezone::convert_utc_to_berlin(time);
-            }
-            if config.offset != 0 {
-                time = time.add((config.offset as i64).minutes());
+    let mut kept = Vec::new();
+    let mut areas = Vec::new();
+    for record in records {
+        let record = record?;
+        if let Some(area) = record.area {
+            areas.push((record.time, area));
+        }
+        match coords_x.as_slice() {
+            [coord] => write_row(name(coord), record.time, record.data[0], record.flags[0])?,
+            _ => kept.push(record),
+        }
+    }
+    if coords_x.len() > 1 {
+        for coord in &coords_x {
+            let i = coords_idx[coord];
+            for record in &kept {
+                write_row(name(coord), record.time, record.data[i], record.flags[i])?;
             }
+        }
+    }
+    for (time, area) in areas {
+        write_row(AREA_NAME.to_string(), time, area.mean, area_flag(&area))?;
+    }
+    Ok(())
+}
+
+/// Streaming variant of [`format_area_statistics`]
+pub fn write_area_statistics(
+    writer: &mut dyn Write,
+    _coordinates: &[Point<u16>],
+    resolution: RadolanResolution,
+    records: &mut dyn Iterator<Item = Result<Record>>,
+    config: RadolanFormatConfig,
+) -> Result<()> {
+    let unit = resolution.unit().symbol();
+    let [mean, max, sum] = ["Mean", "Max", "Sum"].map(|name| format!("{} [{}]", name, unit));
+    writer.write_all(
+        config
+            .header(&["Date", "Time", &mean, &max, &sum])
+            .as_bytes(),
+    )?;
+
+    for record in records {
+        let record = record?;
+        let Some(area) = record.area else { continue };
+        let flag = area_flag(&area);
+        let values = [area.mean, area.max, area.sum].map(|v| config.format_value(v, flag));
+        let [Some(mean), Some(max), Some(sum)] = values else {
+            continue;
+        };
+        let time = config.local_time(record.time);
+        let mut row = vec![
+            format_date_iso(time.date()),
+            format_time_iso(time.time()),
+            mean,
+            max,
+            sum,
+        ];
+        if config.quality {
+            row.push(flag.name().to_string());
+        }
+        writeln!(writer, "{}", row.join("\t"))?;
+    }
+    Ok(())
+}
 
-            tmp.push(format!("{:0>4}_{:0>4}", coord.x, coord.y));
+/// km of the grid to m, rounded to mm to get rid of floating point noise
+fn meters(km: f64) -> f64 {
+    (km * 1e6).round() / 1e3
+}
 
-            tmp.push(time.date().year().to_string());
-            tmp.push(format!("{:0>2}", time.date().month() as u8));
-            tmp.push(format!("{:0>2}", time.date().day()));
-            tmp.push(format!("{:0>2}", time.time().hour()));
-            tmp.push(format!("{:0>2}", time.time().minute()));
+/// value of cells without data
+const NETCDF_FILL_VALUE: f32 = -9999.0;
 
-            tmp.push(format!("{:.2}", record.data[idx_table[idx]]));
-            str.push_str(&tmp.join("\t"));
-            str.push('\n');
-            tmp.clear();
+/// CF NetCDF `time, y, x` cube, cells between the coordinates which weren't requested are empty.
+/// The units are the ones of the decoded files, there is no quality variable.
+///
+/// The times are in UTC shifted by the offset of the config, `utc_to_berlin` is rejected since the
+/// time axis has a fixed offset. The cube needs every record, they are kept until the download is
+/// done.
+pub fn write_netcdf(
+    writer: &mut dyn Write,
+    coordinates: &[Point<u16>],
+    resolution: RadolanResolution,
+    records: &mut dyn Iterator<Item = Result<Record>>,
+    config: RadolanFormatConfig,
+) -> Result<()> {
+    let invalid = |reason: &str| io::Error::new(io::ErrorKind::InvalidInput, reason);
+    if config.utc_to_berlin {
+        return Err(
+            invalid("the NetCDF time axis is in UTC, it can't be converted to Berlin").into(),
+        );
+    }
+    let records = records.collect::<Result<Vec<_>>>()?;
+    let grid = resolution.grid();
+    let (columns, rows) = axes(coordinates);
+    if columns.is_empty() {
+        return Err(invalid("the NetCDF cube needs at least one coordinate").into());
+    }
+    let unit = match records.first() {
+        Some(first) if records.iter().all(|record| record.unit == first.unit) => first.unit,
+        Some(_) => return Err(invalid("the files of the NetCDF cube have different units").into()),
+        None => return Err(invalid("the NetCDF cube needs at least one record").into()),
+    };
+    let unit = unit.ok_or_else(|| invalid("the unit of the files is unknown"))?;
+    let precision = records.iter().map(|record| record.precision).max();
+    let cells = columns.len() * rows.len();
+    let index = |p: &Point<u16>| {
+        let row = rows.binary_search(&p.y).unwrap();
+        row * columns.len() + columns.binary_search(&p.x).unwrap()
+    };
+
+    let mut values = vec![NETCDF_FILL_VALUE; records.len() * cells];
+    let mut times = Vec::with_capacity(records.len());
+    for (t, record) in records.iter().enumerate() {
+        let time = record.time.add((config.offset as i64).minutes());
+        times.push((time.assume_utc().unix_timestamp() / 60) as f64);
+        let data = record.data.iter().zip(&record.flags);
+        for (p, (value, flag)) in coordinates.iter().zip(data) {
+            values[t * cells + index(p)] = match (flag.is_nodata(), config.nodata) {
+                (false, _) => *value,
+                (true, NodataPolicy::NaN) => f32::NAN,
+                (true, NodataPolicy::Zero) => 0.0,
+                (true, NodataPolicy::Sentinel | NodataPolicy::Skip) => NETCDF_FILL_VALUE,
+            };
         }
     }
-    str
+
+    let (x, y) = grid.lower_left();
+    let positions = rows
+        .iter()
+        .flat_map(|&row| columns.iter().map(move |&column| (row, column)))
+        .map(|(row, column)| {
+            let (lat, lon) = grid.center(row, column);
+            LatLon { lat, lon }
+        })
+        .collect();
+    let cube = Cube {
+        name: "precipitation",
+        long_name: "RADOLAN precipitation",
+        units: unit.symbol(),
+        time_units: "minutes since 1970-01-01 00:00:00",
+        times,
+        x: columns
+            .iter()
+            .map(|&c| meters(x + c as f64 + 0.5))
+            .collect(),
+        y: rows
+            .iter()
+            .map(|&r| meters(y + (grid.rows - 1 - r) as f64 + 0.5))
+            .collect(),
+        positions,
+        grid_mapping: vec![
+            ("grid_mapping_name", "polar_stereographic".into()),
+            ("straight_vertical_longitude_from_pole", 10.0.into()),
+            ("latitude_of_projection_origin", 90.0.into()),
+            ("standard_parallel", 60.0.into()),
+            ("false_easting", 0.0.into()),
+            ("false_northing", 0.0.into()),
+            ("earth_radius", 6370040.0.into()),
+        ],
+        values,
+        fill_value: NETCDF_FILL_VALUE,
+        least_significant_digit: -(precision.unwrap_or_default() as i32),
+    };
+    Ok(cube.to_netcdf().write(writer)?)
 }
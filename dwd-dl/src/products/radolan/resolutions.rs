@@ -1,3 +1,7 @@
+use radolan::{
+    header::{Produktkennung, Unit},
+    projection::Grid,
+};
 use serde::Deserialize;
 
 pub mod radolan_daily;
@@ -15,3 +19,33 @@ pub enum RadolanResolution {
     RadolanMin5,
     RadolanMin5Reproc2017,
 }
+
+impl RadolanResolution {
+    /// Grid of the composites, the 2017 reprocessing and YW use the extended grid
+    pub fn grid(&self) -> Grid {
+        match self {
+            RadolanResolution::RadolanDaily | RadolanResolution::RadolanHourly => Grid::NATIONAL,
+            RadolanResolution::RadolanHourlyReproc2017
+            | RadolanResolution::RadolanMin5
+            | RadolanResolution::RadolanMin5Reproc2017 => Grid::EXTENDED,
+        }
+    }
+
+    /// Product code in the header of the composites
+    pub fn produktkennung(&self) -> Produktkennung {
+        match self {
+            RadolanResolution::RadolanDaily => Produktkennung::SF,
+            RadolanResolution::RadolanHourly | RadolanResolution::RadolanHourlyReproc2017 => {
+                Produktkennung::RW
+            }
+            RadolanResolution::RadolanMin5 | RadolanResolution::RadolanMin5Reproc2017 => {
+                Produktkennung::YW
+            }
+        }
+    }
+
+    /// Unit of the values, the precipitation height of each interval
+    pub fn unit(&self) -> Unit {
+        self.produktkennung().unit().unwrap_or(Unit::Mm)
+    }
+}
@@ -0,0 +1,85 @@
+use std::{fmt::Debug, sync::Arc};
+
+/// A step of a running download
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressUpdate {
+    /// the stations of a batch are downloaded one after another, `station` is the `index`th of `count`
+    Station {
+        station: String,
+        index: usize,
+        count: usize,
+    },
+    /// the sources of a request are queried one after another, the `index`th of `count` is next
+    Source { index: usize, count: usize },
+    /// the listing of a source was read, `files` of it are downloaded
+    Listing { files: usize },
+    /// `bytes` of a file are downloaded (or read from the cache), `total` if the server sent a size
+    Download {
+        url: String,
+        bytes: u64,
+        total: Option<u64>,
+    },
+    /// a file inside an archive was decoded
+    Member { archive: String, member: String },
+    /// a downloaded file is completely decoded into `count` records
+    Records { url: String, count: usize },
+}
+
+/// Receiver of [`ProgressUpdate`]s, called from the download threads
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, update: ProgressUpdate);
+}
+
+impl ProgressSink for crossbeam_channel::Sender<ProgressUpdate> {
+    fn report(&self, update: ProgressUpdate) {
+        // nobody listening anymore isn't a reason to stop the download
+        let _ = self.send(update);
+    }
+}
+
+/// Optional [`ProgressSink`] of a request, reports nothing by default
+#[derive(Clone, Default)]
+pub struct Progress(Option<Arc<dyn ProgressSink>>);
+
+impl Progress {
+    pub fn new(sink: impl ProgressSink + 'static) -> Self {
+        Self(Some(Arc::new(sink)))
+    }
+
+    pub fn report(&self, update: ProgressUpdate) {
+        if let Some(sink) = &self.0 {
+            sink.report(update);
+        }
+    }
+}
+
+impl Debug for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => f.write_str("Progress(Some(..))"),
+            None => f.write_str("Progress(None)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_sink() {
+        let (s, r) = crossbeam_channel::unbounded();
+        let progress = Progress::new(s);
+        progress.report(ProgressUpdate::Listing { files: 3 });
+        drop(progress);
+
+        assert_eq!(r.recv(), Ok(ProgressUpdate::Listing { files: 3 }));
+        assert!(r.recv().is_err());
+
+        // reporting without a sink or receiver is a no-op
+        Progress::default().report(ProgressUpdate::Listing { files: 1 });
+        let (s, r) = crossbeam_channel::unbounded();
+        drop(r);
+        Progress::new(s).report(ProgressUpdate::Listing { files: 1 });
+    }
+}
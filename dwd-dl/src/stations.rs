@@ -0,0 +1,431 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use time::{Date, PrimitiveDateTime};
+
+use crate::{
+    base_url,
+    cancel::CancellationToken,
+    error::{DwdError, ParseError, Result},
+    products::{
+        climate::ClimateResolution, observation::ObservationResolution,
+        precipitation::PrecipitationResolution,
+    },
+    progress::Progress,
+    util::{
+        cache::Freshness,
+        download::{download_body, download_text},
+        geo::LatLon,
+        interval::Interval,
+        regex::links_in_text,
+    },
+};
+
+/// Product whose stations are listed, every product has its own station list
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub enum StationProduct {
+    Climate(ClimateResolution),
+    Precipitation(PrecipitationResolution),
+    Observation(ObservationResolution),
+}
+
+impl StationProduct {
+    /// directories containing a `*_Beschreibung_Stationen.txt`, older data first
+    fn directories(&self) -> Vec<String> {
+        let url = |directory: String| {
+            format!(
+                "{}climate_environment/CDC/observations_germany/climate/{}/",
+                base_url(),
+                directory
+            )
+        };
+        let product = match self {
+            StationProduct::Climate(resolution) => match resolution {
+                ClimateResolution::ClimateDaily => "daily/kl",
+                ClimateResolution::ClimateMonthly => "monthly/kl",
+                ClimateResolution::ClimateAnnual => "annual/kl",
+            },
+            StationProduct::Precipitation(resolution) => match resolution {
+                PrecipitationResolution::PrecipitationMin1 => "1_minute/precipitation",
+                PrecipitationResolution::PrecipitationMin5 => "5_minutes/precipitation",
+                PrecipitationResolution::PrecipitationMin10 => "10_minutes/precipitation",
+                PrecipitationResolution::PrecipitationHourly => "hourly/precipitation",
+            },
+            StationProduct::Observation(resolution) if resolution.is_row() => {
+                return vec![url(resolution.directory().to_string())];
+            }
+            StationProduct::Observation(resolution) => resolution.directory(),
+        };
+        ["historical", "recent"]
+            .iter()
+            .map(|period| url(format!("{}/{}", product, period)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Station {
+    /// 5 digits with leading zeros, like in the file names
+    pub id: String,
+    pub availability: Interval<Date>,
+    /// in meters above sea level
+    pub height: f32,
+    pub position: LatLon,
+    pub name: String,
+    pub state: String,
+}
+
+/// A station found by [`StationCatalog::nearest`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NearbyStation {
+    pub station: Station,
+    /// great circle distance in km
+    pub distance: f64,
+    /// part of the requested timespan with data of this station
+    pub overlap: Option<Interval<PrimitiveDateTime>>,
+}
+
+/// Downloads the station list of `product` and returns the `n` stations closest to `position`
+pub fn nearest_stations(
+    product: StationProduct,
+    position: LatLon,
+    timespan: Interval<PrimitiveDateTime>,
+    n: usize,
+) -> Result<Vec<NearbyStation>> {
+    Ok(StationCatalog::download(product)?.nearest(position, timespan, n))
+}
+
+/// Stations of one product, parsed from the `*_Beschreibung_Stationen.txt` files
+#[derive(Debug, Clone, Default)]
+pub struct StationCatalog {
+    stations: Vec<Station>,
+}
+
+impl StationCatalog {
+    /// Downloads the station lists of `product`, of its `historical` and `recent` data if it has both
+    ///
+    /// A station listed in both is merged, its availability covers both lists.
+    pub fn download(product: StationProduct) -> Result<Self> {
+        let mut lists = Vec::new();
+        for directory in product.directories() {
+            let html = download_text(&directory, None)?;
+            for link in links_in_text(&html, r"\w+_Beschreibung_Stationen\.txt") {
+                let url = format!("{}{}", directory, link);
+                let body = download_body(
+                    &url,
+                    None,
+                    Freshness::Revalidate,
+                    &Progress::default(),
+                    &CancellationToken::default(),
+                )?;
+                let list = Self::parse(&decode_text(&body))
+                    .map_err(|err| DwdError::record_parse(&url, err))?;
+                lists.push(list);
+            }
+        }
+        if lists.is_empty() {
+            return Err(DwdError::listing_parse(
+                &product.directories().join(", "),
+                "no station list found",
+            ));
+        }
+        Ok(Self::merge(lists))
+    }
+
+    /// Parses a `*_Beschreibung_Stationen.txt`, the header and the separator line are skipped
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or_default();
+        let has_abgabe = header.contains("Abgabe");
+
+        let stations = lines
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('-'))
+            .map(|line| parse_station(line, has_abgabe))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { stations })
+    }
+
+    fn merge(lists: Vec<Self>) -> Self {
+        let mut merged: BTreeMap<String, Station> = BTreeMap::new();
+        for station in lists.into_iter().flat_map(|list| list.stations) {
+            match merged.get_mut(&station.id) {
+                Some(known) => {
+                    let start = known.availability.start.min(station.availability.start);
+                    let end = known.availability.end.max(station.availability.end);
+                    *known = Station {
+                        availability: Interval { start, end },
+                        ..station
+                    };
+                }
+                None => {
+                    merged.insert(station.id.clone(), station);
+                }
+            }
+        }
+        Self {
+            stations: merged.into_values().collect(),
+        }
+    }
+
+    pub fn stations(&self) -> &[Station] {
+        &self.stations
+    }
+
+    /// `id` may be given without leading zeros
+    pub fn by_id(&self, id: &str) -> Option<&Station> {
+        let id = format!("{:0>5}", id.trim());
+        self.stations.iter().find(|station| station.id == id)
+    }
+
+    /// Case insensitive search in the station names
+    pub fn search_name<'a>(&'a self, query: &str) -> impl Iterator<Item = &'a Station> {
+        let query = query.to_lowercase();
+        self.stations
+            .iter()
+            .filter(move |station| station.name.to_lowercase().contains(&query))
+    }
+
+    /// Stations inside the box spanned by the south west and the north east corner
+    pub fn in_bounding_box(
+        &self,
+        south_west: LatLon,
+        north_east: LatLon,
+    ) -> impl Iterator<Item = &Station> {
+        self.stations.iter().filter(move |station| {
+            let p = station.position;
+            (south_west.lat..=north_east.lat).contains(&p.lat)
+                && (south_west.lon..=north_east.lon).contains(&p.lon)
+        })
+    }
+}
+
+impl StationCatalog {
+    /// The `n` stations closest to `position`, nearest first
+    ///
+    /// Stations without data in `timespan` are included, their `overlap` is `None`.
+    pub fn nearest(
+        &self,
+        position: LatLon,
+        timespan: Interval<PrimitiveDateTime>,
+        n: usize,
+    ) -> Vec<NearbyStation> {
+        let mut nearby = self
+            .stations
+            .iter()
+            .map(|station| (station, station.position.distance(&position)))
+            .collect::<Vec<_>>();
+        nearby.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        nearby
+            .into_iter()
+            .take(n)
+            .map(|(station, distance)| {
+                let available = Interval::<PrimitiveDateTime>::from(station.availability);
+                NearbyStation {
+                    station: station.clone(),
+                    distance,
+                    overlap: available.intersection(&timespan),
+                }
+            })
+            .collect()
+    }
+}
+
+/// for `00001 19370101 19860630 478 47.8413 8.8493 Aach Baden-Württemberg [Frei]`,
+/// names can contain spaces, the state can't
+fn parse_station(line: &str, has_abgabe: bool) -> Result<Station, ParseError> {
+    let invalid = || ParseError::new(line, "station line");
+    let mut columns = line.split_whitespace().collect::<Vec<_>>();
+    if has_abgabe {
+        columns.pop();
+    }
+    if columns.len() < 8 {
+        return Err(invalid());
+    }
+    let state = columns.pop().ok_or_else(invalid)?.to_string();
+    let name = columns[6..].join(" ");
+
+    let number = |s: &str| s.parse::<f64>().map_err(|_| invalid());
+    Ok(Station {
+        id: format!("{:0>5}", columns[0]),
+        availability: Interval::<Date>::parse_str(columns[1], columns[2])?,
+        height: number(columns[3])? as f32,
+        position: LatLon {
+            lat: number(columns[4])?,
+            lon: number(columns[5])?,
+        },
+        name,
+        state,
+    })
+}
+
+/// The station lists are Latin-1 encoded, newer ones may be UTF-8
+fn decode_text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    #[test]
+    fn test_directories() {
+        let relative = |product: StationProduct| {
+            product
+                .directories()
+                .into_iter()
+                .map(|url| url[base_url().len()..].to_string())
+                .collect::<Vec<_>>()
+        };
+        let climate = "climate_environment/CDC/observations_germany/climate";
+        assert_eq!(
+            relative(StationProduct::Climate(ClimateResolution::ClimateDaily)),
+            vec![
+                format!("{climate}/daily/kl/historical/"),
+                format!("{climate}/daily/kl/recent/")
+            ]
+        );
+        assert_eq!(
+            relative(StationProduct::Observation(
+                ObservationResolution::WindHourly
+            )),
+            vec![
+                format!("{climate}/hourly/wind/historical/"),
+                format!("{climate}/hourly/wind/recent/")
+            ]
+        );
+        // the station list of hourly solar is next to its files
+        assert_eq!(
+            relative(StationProduct::Observation(
+                ObservationResolution::SolarHourly
+            )),
+            vec![format!("{climate}/hourly/solar/")]
+        );
+    }
+
+    const LIST: &str = "Stations_id von_datum bis_datum Stationshoehe geoBreite geoLaenge Stationsname Bundesland Abgabe
+----------- --------- --------- ------------- --------- --------- ----------------------------------------- ---------- ------
+00001 19370101 19860630            478     47.8413    8.8493 Aach                                     Baden-Württemberg                        Frei
+00044 19690101 20231231             44     52.9336    8.2370 Großenkneten                             Niedersachsen                            Frei
+04271 19470101 20231231              4     54.1803   12.0808 Rostock-Warnemünde                       Mecklenburg-Vorpommern                   Frei
+15555 20200101 20231231            800     47.9000    8.1000 Bad Dürrheim (Hochschw.)                 Baden-Württemberg                        Frei
+";
+
+    #[test]
+    fn test_parse() {
+        let catalog = StationCatalog::parse(LIST).unwrap();
+        assert_eq!(catalog.stations().len(), 4);
+        assert_eq!(
+            catalog.stations()[0],
+            Station {
+                id: "00001".to_string(),
+                availability: Interval::new(date!(1937 - 01 - 01), date!(1986 - 06 - 30)).unwrap(),
+                height: 478.0,
+                position: LatLon {
+                    lat: 47.8413,
+                    lon: 8.8493
+                },
+                name: "Aach".to_string(),
+                state: "Baden-Württemberg".to_string(),
+            }
+        );
+        assert_eq!(catalog.stations()[3].name, "Bad Dürrheim (Hochschw.)");
+
+        let without_abgabe = LIST.replace(" Abgabe", "").replace("Frei", "");
+        let catalog = StationCatalog::parse(&without_abgabe).unwrap();
+        assert_eq!(catalog.stations()[2].state, "Mecklenburg-Vorpommern");
+    }
+
+    #[test]
+    fn test_lookup() {
+        let catalog = StationCatalog::parse(LIST).unwrap();
+        assert_eq!(catalog.by_id("44").unwrap().name, "Großenkneten");
+        assert!(catalog.by_id("00002").is_none());
+
+        let found = catalog.search_name("rostock").collect::<Vec<_>>();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, "04271");
+
+        let south_west = LatLon {
+            lat: 47.0,
+            lon: 7.0,
+        };
+        let north_east = LatLon {
+            lat: 49.0,
+            lon: 9.0,
+        };
+        let ids = catalog
+            .in_bounding_box(south_west, north_east)
+            .map(|s| s.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["00001", "15555"]);
+    }
+
+    #[test]
+    fn test_nearest() {
+        let catalog = StationCatalog::parse(LIST).unwrap();
+        // Oldenburg
+        let position = LatLon {
+            lat: 53.1435,
+            lon: 8.2146,
+        };
+        let timespan =
+            Interval::new(datetime!(1980-01-01 00:00), datetime!(2000-01-01 00:00)).unwrap();
+        let nearby = catalog.nearest(position, timespan, 2);
+
+        assert_eq!(nearby.len(), 2);
+        assert_eq!(nearby[0].station.id, "00044");
+        assert!((nearby[0].distance - 23.4).abs() < 1.0);
+        assert_eq!(nearby[0].overlap, Some(timespan));
+        assert_eq!(nearby[1].station.id, "04271");
+
+        let ids = catalog
+            .nearest(position, timespan, 10)
+            .into_iter()
+            .map(|s| s.station.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["00044", "04271", "15555", "00001"]);
+        // Aach only has data until 1986
+        let far = catalog.nearest(position, timespan, 10).pop().unwrap();
+        assert_eq!(
+            far.overlap,
+            Some(
+                Interval::new(
+                    datetime!(1980-01-01 00:00),
+                    datetime!(1986-06-30 23:59:59.999_999_999)
+                )
+                .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let historical = StationCatalog::parse(LIST).unwrap();
+        let recent = StationCatalog::parse(
+            "Stations_id von_datum bis_datum Stationshoehe geoBreite geoLaenge Stationsname Bundesland Abgabe
+----------- --------- --------- ------------- --------- --------- ----------------------------------------- ---------- ------
+00044 20220101 20240601             44     52.9336    8.2370 Großenkneten                             Niedersachsen                            Frei
+",
+        )
+        .unwrap();
+        let merged = StationCatalog::merge(vec![historical, recent]);
+        assert_eq!(merged.stations().len(), 4);
+        assert_eq!(
+            merged.by_id("00044").unwrap().availability,
+            Interval::new(date!(1969 - 01 - 01), date!(2024 - 06 - 01)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decode_latin1() {
+        assert_eq!(decode_text(b"Gro\xdfenkneten"), "Großenkneten");
+        assert_eq!(decode_text("Großenkneten".as_bytes()), "Großenkneten");
+    }
+}
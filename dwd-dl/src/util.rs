@@ -1,7 +1,15 @@
+pub mod cache;
 pub mod compression;
+pub mod csv;
 pub mod download;
 pub mod file;
+pub mod gauss_krueger;
+pub mod geo;
 pub mod interval;
+pub mod listing;
+pub mod netcdf;
+pub mod output;
 pub mod point;
+pub mod polygon;
 pub mod regex;
 pub mod time;
@@ -0,0 +1,205 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bytes::Bytes;
+
+/// How long a cached response can be reused without asking the server
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Freshness {
+    /// never changes once published, e.g. `historical` and `reproc` archives
+    Immutable,
+    /// may be updated in place, e.g. `recent`/`now` archives and directory listings
+    Revalidate,
+    /// ignores the cached response, e.g. because it turned out to be corrupted
+    Refetch,
+}
+
+impl Freshness {
+    pub fn of_url(url: &str) -> Self {
+        match url.contains("/historical/") || url.contains("/reproc/") {
+            true => Freshness::Immutable,
+            false => Freshness::Revalidate,
+        }
+    }
+}
+
+/// `ETag` and `Last-Modified` of a cached response, used for conditional requests
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    fn parse(s: &str) -> Self {
+        let mut validators = Validators::default();
+        for line in s.lines() {
+            match line.split_once(": ") {
+                Some(("etag", v)) => validators.etag = Some(v.to_string()),
+                Some(("last-modified", v)) => validators.last_modified = Some(v.to_string()),
+                _ => (),
+            }
+        }
+        validators
+    }
+
+    fn serialize(&self) -> String {
+        let mut s = String::new();
+        if let Some(etag) = &self.etag {
+            s.push_str(&format!("etag: {}\n", etag));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            s.push_str(&format!("last-modified: {}\n", last_modified));
+        }
+        s
+    }
+}
+
+/// A single cached response, the directory layout mirrors the url
+///
+/// `https://opendata.dwd.de/a/b` is stored as `<cache>/opendata.dwd.de/a/b`, directory listings
+/// get their own tree, `https://opendata.dwd.de/a/b/` is stored as
+/// `<cache>/.listings/opendata.dwd.de/a/b/.index`, so a file can't be in the way of a directory
+pub struct CacheEntry {
+    data: PathBuf,
+    meta: PathBuf,
+}
+
+impl CacheEntry {
+    pub fn new(cache_dir: &Path, url: &str) -> Self {
+        let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let mut data = cache_dir.to_path_buf();
+        if url.ends_with('/') {
+            data.push(".listings");
+        }
+        for segment in without_scheme.split('/') {
+            if segment.is_empty() || segment == "." || segment == ".." {
+                continue;
+            }
+            data.push(segment.replace([':', '?', '*', '"', '<', '>', '|', '\\'], "_"));
+        }
+        if url.ends_with('/') {
+            data.push(".index");
+        }
+        let mut meta = data.clone().into_os_string();
+        meta.push(".meta");
+
+        Self {
+            data,
+            meta: meta.into(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.data
+    }
+
+    /// `None` if nothing (or only a partially written entry) is cached
+    pub fn read(&self) -> Option<(Bytes, Validators)> {
+        let meta = fs::read_to_string(&self.meta).ok()?;
+        let data = fs::read(&self.data).ok()?;
+        Some((Bytes::from(data), Validators::parse(&meta)))
+    }
+
+    /// Replaces the cached response, the metadata is written last
+    pub fn write(&self, data: &[u8], validators: &Validators) -> io::Result<()> {
+        if let Some(parent) = self.data.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // remove the metadata first, so an interrupted write is never taken as valid
+        match fs::remove_file(&self.meta) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => (),
+        }
+        write_atomic(&self.data, data)?;
+        write_atomic(&self.meta, validators.serialize().as_bytes())
+    }
+}
+
+/// Writes a temporary file next to `path` and renames it, so `path` is never partially written
+fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    // unique per write, the threads of a download may write the same entry
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = path.with_file_name(name);
+
+    let result = fs::write(&tmp, data).and_then(|_| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_path() {
+        let dir = Path::new("cache");
+        let entry = CacheEntry::new(dir, "http://localhost:8080/climate_environment/CDC/");
+        assert_eq!(
+            entry.path(),
+            Path::new("cache/.listings/localhost_8080/climate_environment/CDC/.index")
+        );
+
+        let entry = CacheEntry::new(dir, "https://opendata.dwd.de//a/../b/file.zip");
+        assert_eq!(
+            entry.path(),
+            Path::new("cache/opendata.dwd.de/a/b/file.zip")
+        );
+    }
+
+    #[test]
+    fn test_freshness() {
+        let url = "https://opendata.dwd.de/climate_environment/CDC/observations_germany/climate/daily/kl/historical/tageswerte_KL_00001_19370101_19860630_hist.zip";
+        assert_eq!(Freshness::of_url(url), Freshness::Immutable);
+        let url = "https://opendata.dwd.de/climate_environment/CDC/observations_germany/climate/daily/kl/recent/tageswerte_KL_00011_akt.zip";
+        assert_eq!(Freshness::of_url(url), Freshness::Revalidate);
+    }
+
+    #[test]
+    fn test_write_read() {
+        let dir = std::env::temp_dir().join(format!("dwd-dl-cache-{}", std::process::id()));
+        let entry = CacheEntry::new(&dir, "http://localhost/a/b.zip");
+        assert!(entry.read().is_none());
+
+        let validators = Validators {
+            etag: Some("\"5f-1a\"".to_string()),
+            last_modified: Some("Tue, 01 Aug 2023 08:00:00 GMT".to_string()),
+        };
+        entry.write(b"content", &validators).unwrap();
+        let (data, read_validators) = entry.read().unwrap();
+        assert_eq!(data.as_ref(), b"content");
+        assert_eq!(read_validators, validators);
+
+        // replaced without leaving temporary files
+        entry.write(b"new content", &Validators::default()).unwrap();
+        let (data, read_validators) = entry.read().unwrap();
+        assert_eq!(data.as_ref(), b"new content");
+        assert_eq!(read_validators, Validators::default());
+        let mut names: Vec<_> = fs::read_dir(dir.join("localhost/a"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["b.zip", "b.zip.meta"]);
+
+        // a listing of the same path as a file
+        let listing = CacheEntry::new(&dir, "http://localhost/a/b.zip/");
+        listing.write(b"listing", &validators).unwrap();
+        assert_eq!(listing.read().unwrap().0.as_ref(), b"listing");
+        assert_eq!(entry.read().unwrap().0.as_ref(), b"new content");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use std::io::Read;
+use std::io::{self, Read};
 
 use bytes::Bytes;
 use tar::{Archive, Entry};
@@ -8,15 +8,14 @@ pub struct Tarball {
 }
 
 impl Tarball {
-    pub fn new(data: Bytes) -> Self {
+    pub fn new(data: Bytes) -> io::Result<Self> {
         let mut archive = Archive::new(data.as_ref());
         let mut entries = Vec::new();
-        for entry in archive.entries().unwrap() {
-            let entry = entry.unwrap();
-            entries.push(TarEntry::from_tar_entry(entry, data.clone()));
+        for entry in archive.entries()? {
+            entries.push(TarEntry::from_tar_entry(entry?, data.clone())?);
         }
         entries.sort_unstable_by(|a, b| a.filename.cmp(&b.filename));
-        Self { entries }
+        Ok(Self { entries })
     }
 
     pub fn entries(&self) -> &[TarEntry] {
@@ -34,14 +33,20 @@ impl TarEntry {
         Self { filename, data }
     }
 
-    pub fn from_tar_entry<'a, R>(entry: Entry<'a, R>, data: Bytes) -> Self
+    pub fn from_tar_entry<'a, R>(entry: Entry<'a, R>, data: Bytes) -> io::Result<Self>
     where
         R: 'a + Read,
     {
-        let name = entry.path().unwrap().display().to_string();
+        let name = entry.path()?.display().to_string();
         let offset = entry.raw_file_position() as usize;
-        let length = entry.header().size().unwrap() as usize;
+        let length = entry.header().size()? as usize;
+        if offset + length > data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("member `{name}` is truncated"),
+            ));
+        }
         let data = data.slice(offset..offset + length);
-        Self::new(name, data)
+        Ok(Self::new(name, data))
     }
 }
@@ -1,22 +1,35 @@
 use bytes::Bytes;
 
-use crate::util::file::File;
+use crate::{
+    cancel::CancellationToken,
+    error::{DwdError, Result},
+    util::file::File,
+};
 
 use super::{gz::decode_gz, tar::Tarball};
 
 pub struct MultiLayerFolder {
     file: File,
     filters: Vec<Filter>,
+    cancel: CancellationToken,
 }
 
 impl MultiLayerFolder {
-    pub fn new(file: File, filters: Vec<Filter>) -> Self {
-        Self { file, filters }
+    pub fn new(file: File, filters: Vec<Filter>, cancel: CancellationToken) -> Self {
+        Self {
+            file,
+            filters,
+            cancel,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.file.name
     }
 }
 
 impl IntoIterator for MultiLayerFolder {
-    type Item = File;
+    type Item = Result<File>;
 
     type IntoIter = MultiLayerFolderIter;
 
@@ -28,6 +41,7 @@ impl IntoIterator for MultiLayerFolder {
             current_layer: 0,
             layer_start_index: vec![0],
             filters: self.filters,
+            cancel: self.cancel,
         }
     }
 }
@@ -39,10 +53,11 @@ pub struct MultiLayerFolderIter {
     current_layer: usize,
     layer_start_index: Vec<usize>,
     filters: Vec<Filter>,
+    cancel: CancellationToken,
 }
 
 impl Iterator for MultiLayerFolderIter {
-    type Item = File;
+    type Item = Result<File>;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
@@ -52,6 +67,10 @@ impl Iterator for MultiLayerFolderIter {
             if self.folder.is_empty() {
                 return None;
             }
+            if let Err(err) = self.cancel.check() {
+                self.folder.clear();
+                return Some(Err(err));
+            }
             if self.folder.len() < *self.layer_start_index.last().unwrap() {
                 self.layer_start_index.pop();
                 self.current_layer -= 1;
@@ -66,12 +85,15 @@ impl Iterator for MultiLayerFolderIter {
                 continue;
             }
 
-            let extension = file.extension().unwrap().into();
+            let extension = file.extension().unwrap_or_default().into();
             println!("Processing: {}", file.name);
             match extension {
                 Extension::Gz => {
                     let file = self.folder.pop().unwrap();
-                    let decoded = decode_gz(&file.data);
+                    let decoded = match decode_gz(&file.data) {
+                        Ok(decoded) => decoded,
+                        Err(err) => return Some(Err(DwdError::corrupt_archive(&file.name, err))),
+                    };
                     let file = File::new(
                         file.name.strip_suffix(".gz").unwrap().into(),
                         Bytes::from(decoded),
@@ -79,10 +101,13 @@ impl Iterator for MultiLayerFolderIter {
                     self.folder.push(file);
                 }
                 Extension::Tar => {
-                    self.current_layer += 1;
-                    self.layer_start_index.push(self.folder.len());
                     let file = self.folder.pop().unwrap();
-                    let file = Tarball::new(file.data);
+                    let file = match Tarball::new(file.data) {
+                        Ok(tarball) => tarball,
+                        Err(err) => return Some(Err(DwdError::corrupt_archive(&file.name, err))),
+                    };
+                    self.current_layer += 1;
+                    self.layer_start_index.push(self.folder.len() + 1);
                     for entry in file.entries().iter().rev() {
                         let file = entry.data.clone();
                         let file = File::new(entry.filename.clone(), file);
@@ -97,7 +122,7 @@ impl Iterator for MultiLayerFolderIter {
                 }
                 Extension::Other => {
                     let file = self.folder.pop().unwrap();
-                    return Some(file);
+                    return Some(Ok(file));
                 }
             }
         }
@@ -141,8 +166,8 @@ mod test {
     #[test]
     fn test() {
         let file = fs::read("../example_setup/ftp_data/climate_environment/CDC/grids_germany/daily/evapo_p/grids_germany_daily_evapo_p_202210.tgz").unwrap();
-        let file = decode_gz(&file);
-        let file = Tarball::new(file.into());
+        let file = decode_gz(&file).unwrap();
+        let file = Tarball::new(file.into()).unwrap();
         for entry in file.entries() {
             println!("{}", entry.filename);
         }
@@ -190,11 +215,23 @@ mod test {
         ))
         .unwrap();
         let file = File::new(name.into(), file.into());
-        let folder = MultiLayerFolder::new(file, filters);
-        let folder: Vec<_> = folder.into_iter().collect();
+        let folder = MultiLayerFolder::new(file, filters, CancellationToken::default());
+        let folder: Vec<_> = folder.into_iter().collect::<Result<_>>().unwrap();
         for file in &folder {
             println!("{}", file.name);
         }
         assert_eq!(folder.len(), 12);
     }
+
+    #[test]
+    fn test_cancel() {
+        let filters: Vec<Filter> = vec![Box::new(|_: &str| true)];
+        let file = File::new("evapo_p_20221126.asc".into(), Bytes::from_static(b"data"));
+        let cancel = CancellationToken::default();
+        cancel.cancel();
+
+        let mut folder = MultiLayerFolder::new(file, filters, cancel).into_iter();
+        assert!(matches!(folder.next(), Some(Err(DwdError::Cancelled))));
+        assert!(folder.next().is_none());
+    }
 }
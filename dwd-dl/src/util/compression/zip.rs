@@ -1,29 +1,48 @@
 use std::io::Read;
 
-use bytes::Bytes;
+use crate::{
+    error::{DwdError, Result},
+    util::file::File,
+};
 
-struct File {
+struct Member {
     name: String,
     id: usize,
 }
 
-pub fn extract_file(b: Bytes, search: &str) -> Vec<u8> {
-    let cursor = std::io::Cursor::new(b);
-    let mut archive = zip::ZipArchive::new(cursor).unwrap();
+/// Extracts the first member of the zip `file` whose name contains `search`
+pub fn extract_file(file: File, search: &str) -> Result<Vec<u8>> {
+    let corrupt = |err| DwdError::corrupt_archive(&file.name, err);
+    let cursor = std::io::Cursor::new(file.data.clone());
+    let mut archive = zip::ZipArchive::new(cursor).map_err(corrupt)?;
     let mut archive_indexed = Vec::new();
     for i in 0..archive.len() {
-        let file = archive.by_index_raw(i).unwrap();
-        archive_indexed.push(File {
-            name: file.name().to_string(),
+        let member = archive.by_index_raw(i).map_err(corrupt)?;
+        archive_indexed.push(Member {
+            name: member.name().to_string(),
             id: i,
         });
     }
     let id = archive_indexed
         .iter()
         .find(|x| x.name.contains(search))
-        .unwrap()
+        .ok_or_else(|| DwdError::MissingMember {
+            file: file.name.clone(),
+            member: search.to_string(),
+        })?
         .id;
     let mut buf = Vec::new();
-    let _ = archive.by_index(id).unwrap().read_to_end(&mut buf);
-    buf
+    archive
+        .by_index(id)
+        .map_err(corrupt)?
+        .read_to_end(&mut buf)
+        .map_err(|err| DwdError::corrupt_archive(&file.name, err))?;
+    Ok(buf)
+}
+
+/// Like [`extract_file`], but the member has to be valid UTF-8 text
+pub fn extract_text(file: File, search: &str) -> Result<String> {
+    let name = file.name.clone();
+    let bytes = extract_file(file, search)?;
+    String::from_utf8(bytes).map_err(|err| DwdError::record_parse(&name, err))
 }
@@ -0,0 +1,50 @@
+use std::str::FromStr;
+
+use crate::error::ParseError;
+
+/// Returns the trimmed `n`-th column of a semicolon separated DWD `produkt` line
+pub fn nth_column(line: &str, n: usize) -> Result<&str, ParseError> {
+    line.split(';')
+        .nth(n)
+        .map(str::trim)
+        .ok_or_else(|| ParseError::new(line, "line with enough columns"))
+}
+
+/// Parses the `n`-th column of a semicolon separated DWD `produkt` line
+pub fn parse_column<T: FromStr>(line: &str, n: usize) -> Result<T, ParseError> {
+    let column = nth_column(line, n)?;
+    column
+        .parse()
+        .map_err(|_| ParseError::new(column, "number"))
+}
+
+/// Like [`parse_column`], but the missing value `-999` is `None`
+pub fn parse_optional_column<T: FromStr>(line: &str, n: usize) -> Result<Option<T>, ParseError> {
+    let column = nth_column(line, n)?;
+    if column.parse::<f64>() == Ok(-999.0) {
+        return Ok(None);
+    }
+    column
+        .parse()
+        .map(Some)
+        .map_err(|_| ParseError::new(column, "number"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_column() {
+        let line = "     164;2022123119;    3;   0.3;   1;   6;eor";
+        assert_eq!(nth_column(line, 1), Ok("2022123119"));
+        assert_eq!(parse_column::<f32>(line, 3), Ok(0.3));
+        assert!(parse_column::<f32>(line, 6).is_err());
+        assert!(nth_column(line, 7).is_err());
+
+        let line = "44;-999;-999.0;  3";
+        assert_eq!(parse_optional_column::<u8>(line, 1), Ok(None));
+        assert_eq!(parse_optional_column::<f32>(line, 2), Ok(None));
+        assert_eq!(parse_optional_column::<u8>(line, 3), Ok(Some(3)));
+    }
+}
@@ -1,30 +1,230 @@
 use std::borrow::Cow;
 
-use super::file::File;
+use bytes::Bytes;
 
-pub fn download_text(url: &str, client: Option<&ureq::Agent>) -> String {
-    let client = client
-        .map(Cow::Borrowed)
-        .unwrap_or_else(|| Cow::Owned(create_client()));
+use crate::{
+    cache_dir,
+    cancel::CancellationToken,
+    error::{DwdError, Result},
+    progress::{Progress, ProgressUpdate},
+};
+
+use super::{
+    cache::{CacheEntry, Freshness, Validators},
+    file::File,
+};
 
-    client.get(url).call().unwrap().into_string().unwrap()
+/// Downloads a text (e.g. a directory listing), cached responses are always revalidated
+pub fn download_text(url: &str, client: Option<&ureq::Agent>) -> Result<String> {
+    let body = download_body(
+        url,
+        client,
+        Freshness::Revalidate,
+        &Progress::default(),
+        &CancellationToken::default(),
+    )?;
+    String::from_utf8(body.into()).map_err(|err| DwdError::transport(url, err))
 }
 
-pub fn download_body(url: &str, client: Option<&ureq::Agent>) -> bytes::Bytes {
+/// The body is read in chunks, `cancel` is checked after each of them
+pub fn download_body(
+    url: &str,
+    client: Option<&ureq::Agent>,
+    freshness: Freshness,
+    progress: &Progress,
+    cancel: &CancellationToken,
+) -> Result<Bytes> {
     let client = client
         .map(Cow::Borrowed)
         .unwrap_or_else(|| Cow::Owned(create_client()));
-    let mut response_reader = client.get(url).call().unwrap().into_reader();
-    let mut body = Vec::new();
-    let _ = response_reader.read_to_end(&mut body);
-    bytes::Bytes::from(body)
+
+    let Some(cache_dir) = cache_dir() else {
+        return Ok(fetch(&client, url, None, progress, cancel)?.0);
+    };
+
+    let entry = CacheEntry::new(cache_dir, url);
+    let cached = match freshness {
+        Freshness::Refetch => None,
+        _ => entry.read(),
+    };
+    if let Some((body, validators)) = cached {
+        if freshness == Freshness::Immutable {
+            report_cached(url, &body, progress);
+            return Ok(body);
+        }
+        match fetch(&client, url, Some(&validators), progress, cancel)? {
+            (_, None) => {
+                report_cached(url, &body, progress);
+                return Ok(body);
+            }
+            (body, Some(validators)) => {
+                store(&entry, &body, &validators)?;
+                return Ok(body);
+            }
+        }
+    }
+
+    let (body, validators) = fetch(&client, url, None, progress, cancel)?;
+    store(&entry, &body, &validators.unwrap_or_default())?;
+    Ok(body)
 }
 
-pub fn download_file(url: &str, client: Option<&ureq::Agent>) -> File {
-    let body = download_body(url, client);
-    File::new(url.rsplit('/').next().unwrap().to_string(), body)
+pub fn download_file(
+    url: &str,
+    client: Option<&ureq::Agent>,
+    progress: &Progress,
+    cancel: &CancellationToken,
+) -> Result<File> {
+    file_of(url, client, Freshness::of_url(url), progress, cancel)
+}
+
+/// Downloads the file again and replaces the cached one
+pub fn refetch_file(
+    url: &str,
+    client: Option<&ureq::Agent>,
+    progress: &Progress,
+    cancel: &CancellationToken,
+) -> Result<File> {
+    file_of(url, client, Freshness::Refetch, progress, cancel)
+}
+
+fn file_of(
+    url: &str,
+    client: Option<&ureq::Agent>,
+    freshness: Freshness,
+    progress: &Progress,
+    cancel: &CancellationToken,
+) -> Result<File> {
+    let body = download_body(url, client, freshness, progress, cancel)?;
+    let name = url.rsplit('/').next().unwrap_or(url).to_string();
+    Ok(File::new(name, body))
 }
 
 pub fn create_client() -> ureq::Agent {
     ureq::AgentBuilder::new().build()
 }
+
+/// Returns `None` as validators if the server answered `304 Not Modified`
+fn fetch(
+    client: &ureq::Agent,
+    url: &str,
+    validators: Option<&Validators>,
+    progress: &Progress,
+    cancel: &CancellationToken,
+) -> Result<(Bytes, Option<Validators>)> {
+    let mut request = client.get(url);
+    if let Some(validators) = validators {
+        if let Some(etag) = &validators.etag {
+            request = request.set("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.set("If-Modified-Since", last_modified);
+        }
+    }
+
+    let response = request.call().map_err(|err| match err {
+        ureq::Error::Status(status, _) => DwdError::HttpStatus {
+            url: url.to_string(),
+            status,
+        },
+        ureq::Error::Transport(transport) => DwdError::transport(url, transport),
+    })?;
+    if response.status() == 304 {
+        return Ok((Bytes::new(), None));
+    }
+
+    let validators = Validators {
+        etag: response.header("ETag").map(str::to_string),
+        last_modified: response.header("Last-Modified").map(str::to_string),
+    };
+    let total = response
+        .header("Content-Length")
+        .and_then(|length| length.parse().ok());
+
+    let mut reader = response.into_reader();
+    let mut body = Vec::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        // a cancelled body is dropped before it reaches the cache
+        cancel.check()?;
+        let n = reader
+            .read(&mut buf)
+            .map_err(|err| DwdError::transport(url, err))?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&buf[..n]);
+        progress.report(ProgressUpdate::Download {
+            url: url.to_string(),
+            bytes: body.len() as u64,
+            total,
+        });
+    }
+    Ok((Bytes::from(body), Some(validators)))
+}
+
+fn report_cached(url: &str, body: &Bytes, progress: &Progress) {
+    let bytes = body.len() as u64;
+    progress.report(ProgressUpdate::Download {
+        url: url.to_string(),
+        bytes,
+        total: Some(bytes),
+    });
+}
+
+fn store(entry: &CacheEntry, body: &[u8], validators: &Validators) -> Result<()> {
+    entry
+        .write(body, validators)
+        .map_err(|err| DwdError::Cache {
+            path: entry.path().display().to_string(),
+            reason: err.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    use crate::progress::ProgressSink;
+
+    use super::*;
+
+    /// cancels the download once the first chunk arrived
+    struct CancelOnDownload(CancellationToken);
+
+    impl ProgressSink for CancelOnDownload {
+        fn report(&self, update: ProgressUpdate) {
+            if matches!(update, ProgressUpdate::Download { .. }) {
+                self.0.cancel();
+            }
+        }
+    }
+
+    #[test]
+    fn test_cancel_while_reading() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/file.zip", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            let total = 1024 * 1024;
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {total}\r\n\r\n").unwrap();
+            // the rest of the body never arrives, only the cancellation ends the download
+            stream.write_all(&vec![0; 64 * 1024]).unwrap();
+            stream.flush().unwrap();
+            let _ = stream.read(&mut request);
+        });
+
+        let cancel = CancellationToken::default();
+        let progress = Progress::new(CancelOnDownload(cancel.clone()));
+        let client = create_client();
+        let result = fetch(&client, &url, None, &progress, &cancel);
+        assert!(matches!(result, Err(DwdError::Cancelled)));
+        drop(client);
+        server.join().unwrap();
+    }
+}
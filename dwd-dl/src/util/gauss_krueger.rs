@@ -0,0 +1,212 @@
+//! Gauss-Krüger coordinates (DHDN, Bessel ellipsoid) as used by the `grids_germany` rasters
+//!
+//! WGS84 positions are shifted to DHDN with the 7 parameter transformation of the BKG
+//! (accurate to a few meters), the projection uses the Krüger series (accurate to mm).
+
+use super::geo::LatLon;
+
+struct Ellipsoid {
+    a: f64,
+    f: f64,
+}
+
+impl Ellipsoid {
+    fn e2(&self) -> f64 {
+        self.f * (2.0 - self.f)
+    }
+
+    /// geodetic degrees on the surface to earth centered cartesian coordinates
+    fn to_cartesian(&self, position: LatLon) -> [f64; 3] {
+        let (phi, lambda) = (position.lat.to_radians(), position.lon.to_radians());
+        let n = self.a / (1.0 - self.e2() * phi.sin().powi(2)).sqrt();
+        [
+            n * phi.cos() * lambda.cos(),
+            n * phi.cos() * lambda.sin(),
+            n * (1.0 - self.e2()) * phi.sin(),
+        ]
+    }
+
+    fn to_geodetic(&self, [x, y, z]: [f64; 3]) -> LatLon {
+        let p = (x * x + y * y).sqrt();
+        let mut phi = z.atan2(p * (1.0 - self.e2()));
+        for _ in 0..10 {
+            let n = self.a / (1.0 - self.e2() * phi.sin().powi(2)).sqrt();
+            let h = p / phi.cos() - n;
+            phi = z.atan2(p * (1.0 - self.e2() * n / (n + h)));
+        }
+        LatLon {
+            lat: phi.to_degrees(),
+            lon: y.atan2(x).to_degrees(),
+        }
+    }
+}
+
+const WGS84: Ellipsoid = Ellipsoid {
+    a: 6378137.0,
+    f: 1.0 / 298.257223563,
+};
+
+const BESSEL: Ellipsoid = Ellipsoid {
+    a: 6377397.155,
+    f: 1.0 / 299.1528128,
+};
+
+/// DHDN to WGS84, position vector convention: translation (m), rotation (arc seconds), scale (ppm)
+const DHDN_TO_WGS84: ([f64; 3], [f64; 3], f64) =
+    ([598.1, 73.7, 418.2], [0.202, 0.045, -2.455], 6.7);
+
+/// `inverse` applies the transformation from WGS84 to DHDN
+fn helmert([x, y, z]: [f64; 3], inverse: bool) -> [f64; 3] {
+    let (t, r, s) = DHDN_TO_WGS84;
+    let sign = if inverse { -1.0 } else { 1.0 };
+    let [rx, ry, rz] = r.map(|r| sign * (r / 3600.0).to_radians());
+    let scale = 1.0 + sign * s * 1e-6;
+    [
+        sign * t[0] + scale * (x - rz * y + ry * z),
+        sign * t[1] + scale * (rz * x + y - rx * z),
+        sign * t[2] + scale * (-ry * x + rx * y + z),
+    ]
+}
+
+/// Series coefficients of the Bessel ellipsoid: rectifying radius, forward, backward, latitude
+struct Series {
+    radius: f64,
+    alpha: [f64; 3],
+    beta: [f64; 3],
+    delta: [f64; 3],
+}
+
+fn series() -> Series {
+    let n = BESSEL.f / (2.0 - BESSEL.f);
+    let (n2, n3) = (n * n, n * n * n);
+    Series {
+        radius: BESSEL.a / (1.0 + n) * (1.0 + n2 / 4.0 + n2 * n2 / 64.0),
+        alpha: [
+            n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0,
+            13.0 * n2 / 48.0 - 3.0 * n3 / 5.0,
+            61.0 * n3 / 240.0,
+        ],
+        beta: [
+            n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0,
+            n2 / 48.0 + n3 / 15.0,
+            17.0 * n3 / 480.0,
+        ],
+        delta: [
+            2.0 * n - 2.0 * n2 / 3.0 - 2.0 * n3,
+            7.0 * n2 / 3.0 - 8.0 * n3 / 5.0,
+            56.0 * n3 / 15.0,
+        ],
+    }
+}
+
+/// WGS84 position to `(easting, northing)` in meters of the Gauss-Krüger `zone` (3° wide)
+pub fn to_gauss_krueger(position: LatLon, zone: u8) -> (f64, f64) {
+    let dhdn = BESSEL.to_geodetic(helmert(WGS84.to_cartesian(position), true));
+    let Series { radius, alpha, .. } = series();
+    let n = BESSEL.f / (2.0 - BESSEL.f);
+    let c = 2.0 * n.sqrt() / (1.0 + n);
+
+    let phi = dhdn.lat.to_radians();
+    let lambda = (dhdn.lon - 3.0 * zone as f64).to_radians();
+    let t = (phi.sin().atanh() - c * (c * phi.sin()).atanh()).sinh();
+    let xi = t.atan2(lambda.cos());
+    let eta = (lambda.sin() / (1.0 + t * t).sqrt()).atanh();
+
+    let (mut easting, mut northing) = (eta, xi);
+    for (j, alpha) in (1..=3).zip(alpha) {
+        let j = 2.0 * j as f64;
+        easting += alpha * (j * xi).cos() * (j * eta).sinh();
+        northing += alpha * (j * xi).sin() * (j * eta).cosh();
+    }
+    (
+        zone as f64 * 1e6 + 500_000.0 + radius * easting,
+        radius * northing,
+    )
+}
+
+/// Inverse of [`to_gauss_krueger`], the zone is the first digit of the easting
+pub fn from_gauss_krueger(easting: f64, northing: f64) -> LatLon {
+    let zone = (easting / 1e6).floor();
+    let Series {
+        radius,
+        beta,
+        delta,
+        ..
+    } = series();
+
+    let xi = northing / radius;
+    let eta = (easting - zone * 1e6 - 500_000.0) / radius;
+    let (mut xi_, mut eta_) = (xi, eta);
+    for (j, beta) in (1..=3).zip(beta) {
+        let j = 2.0 * j as f64;
+        xi_ -= beta * (j * xi).sin() * (j * eta).cosh();
+        eta_ -= beta * (j * xi).cos() * (j * eta).sinh();
+    }
+    let chi = (xi_.sin() / eta_.cosh()).asin();
+    let mut phi = chi;
+    for (j, delta) in (1..=3).zip(delta) {
+        phi += delta * (2.0 * j as f64 * chi).sin();
+    }
+    let lambda = eta_.sinh().atan2(xi_.cos());
+
+    let dhdn = LatLon {
+        lat: phi.to_degrees(),
+        lon: 3.0 * zone + lambda.to_degrees(),
+    };
+    WGS84.to_geodetic(helmert(BESSEL.to_cartesian(dhdn), false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_gauss_krueger() {
+        // reference values computed with Snyder's series for the transverse Mercator projection
+        let frankfurt = LatLon {
+            lat: 50.1109,
+            lon: 8.6821,
+        };
+        let (easting, northing) = to_gauss_krueger(frankfurt, 3);
+        assert!((easting - 3477334.819).abs() < 0.05, "{easting}");
+        assert!((northing - 5552790.755).abs() < 0.05, "{northing}");
+
+        let warnemuende = LatLon {
+            lat: 54.1803,
+            lon: 12.0808,
+        };
+        let (easting, northing) = to_gauss_krueger(warnemuende, 3);
+        assert!((easting - 3701191.632).abs() < 0.05, "{easting}");
+        assert!((northing - 6009932.942).abs() < 0.05, "{northing}");
+    }
+
+    // the inverse helmert transformation only negates the parameters, good to a few cm
+    #[test]
+    fn test_round_trip() {
+        for position in [
+            LatLon {
+                lat: 50.1109,
+                lon: 8.6821,
+            },
+            LatLon {
+                lat: 54.1803,
+                lon: 12.0808,
+            },
+            LatLon {
+                lat: 47.4212,
+                lon: 10.9863,
+            },
+        ] {
+            let (easting, northing) = to_gauss_krueger(position, 3);
+            let back = from_gauss_krueger(easting, northing);
+            assert!(
+                (back.lat - position.lat).abs() < 1e-6,
+                "{back:?} != {position:?}"
+            );
+            assert!(
+                (back.lon - position.lon).abs() < 1e-6,
+                "{back:?} != {position:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::error::ParseError;
+
+/// WGS84 coordinates in degrees
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "specta", derive(specta::Type))]
+pub struct LatLon {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// mean earth radius in km
+const EARTH_RADIUS: f64 = 6371.0088;
+
+impl LatLon {
+    /// Great circle distance in km (haversine)
+    pub fn distance(&self, other: &LatLon) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.lon - self.lon).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().asin()
+    }
+}
+
+impl FromStr for LatLon {
+    type Err = ParseError;
+
+    /// `lat,lon`, e.g. `52.52,13.405`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::new(s, "lat,lon position");
+        let (lat, lon) = s.split_once(',').ok_or_else(invalid)?;
+        let lat = lat.trim().parse::<f64>().map_err(|_| invalid())?;
+        let lon = lon.trim().parse::<f64>().map_err(|_| invalid())?;
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err(invalid());
+        }
+        Ok(Self { lat, lon })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        let berlin = LatLon {
+            lat: 52.5200,
+            lon: 13.4050,
+        };
+        let munich = LatLon {
+            lat: 48.1351,
+            lon: 11.5820,
+        };
+        assert!((berlin.distance(&munich) - 504.4).abs() < 1.0);
+        assert_eq!(berlin.distance(&berlin), 0.0);
+    }
+
+    #[test]
+    fn test_parse() {
+        let position = "52.52, 13.405".parse::<LatLon>().unwrap();
+        assert_eq!(
+            position,
+            LatLon {
+                lat: 52.52,
+                lon: 13.405
+            }
+        );
+        assert!("52.52".parse::<LatLon>().is_err());
+        assert!("152.52,13.4".parse::<LatLon>().is_err());
+    }
+}
@@ -1,5 +1,8 @@
+use thiserror::Error;
 use time::{macros::time, Date, PrimitiveDateTime, Time};
 
+use crate::error::ParseError;
+
 use super::time::{parse_yyyymmdd, parse_yyyymmddhhmm};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -15,9 +18,9 @@ impl<T> Interval<T>
 where
     T: std::cmp::PartialOrd + std::fmt::Debug,
 {
-    pub fn new(start: T, end: T) -> Result<Self, ()> {
+    pub fn new(start: T, end: T) -> Result<Self, InvalidInterval> {
         if start > end {
-            return Err(());
+            return Err(InvalidInterval);
         }
         Ok(Self { start, end })
     }
@@ -35,21 +38,41 @@ where
     }
 }
 
+impl<T> Interval<T>
+where
+    T: std::cmp::PartialOrd + Copy,
+{
+    /// The part covered by both intervals, `None` if they don't overlap
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let start = match self.start > other.start {
+            true => self.start,
+            false => other.start,
+        };
+        let end = match self.end < other.end {
+            true => self.end,
+            false => other.end,
+        };
+        (start < end).then_some(Self { start, end })
+    }
+}
+
+#[derive(Error, Debug, Clone, Copy, PartialEq)]
+#[error("start of the interval is after its end")]
+pub struct InvalidInterval;
+
 impl Interval<PrimitiveDateTime> {
-    pub fn parse_str(start: &str, end: &str) -> Self {
-        let parser = |s: &str| parse_yyyymmddhhmm(s).unwrap();
-        let start = parser(start);
-        let end = parser(end);
-        Self::new(start, end).unwrap()
+    pub fn parse_str(start: &str, end: &str) -> Result<Self, ParseError> {
+        let start = parse_yyyymmddhhmm(start)?;
+        let end = parse_yyyymmddhhmm(end)?;
+        Self::new(start, end).map_err(|_| ParseError::new(&format!("{start}_{end}"), "interval"))
     }
 }
 
 impl Interval<Date> {
-    pub fn parse_str(start: &str, end: &str) -> Self {
-        let parser = |s: &str| parse_yyyymmdd(s).unwrap();
-        let start = parser(start);
-        let end = parser(end);
-        Self::new(start, end).unwrap()
+    pub fn parse_str(start: &str, end: &str) -> Result<Self, ParseError> {
+        let start = parse_yyyymmdd(start)?;
+        let end = parse_yyyymmdd(end)?;
+        Self::new(start, end).map_err(|_| ParseError::new(&format!("{start}_{end}"), "interval"))
     }
 }
 
@@ -120,4 +143,16 @@ mod test {
         let res = interval.contains(&date);
         assert!(res);
     }
+
+    #[test]
+    fn test_intersection() {
+        let a = Interval::new(date!(2022 - 01 - 01), date!(2022 - 06 - 01)).unwrap();
+        let b = Interval::new(date!(2022 - 03 - 01), date!(2022 - 12 - 01)).unwrap();
+        let expected = Interval::new(date!(2022 - 03 - 01), date!(2022 - 06 - 01)).unwrap();
+        assert_eq!(a.intersection(&b), Some(expected));
+        assert_eq!(b.intersection(&a), Some(expected));
+
+        let c = Interval::new(date!(2022 - 06 - 01), date!(2022 - 07 - 01)).unwrap();
+        assert_eq!(a.intersection(&c), None);
+    }
 }
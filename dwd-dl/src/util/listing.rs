@@ -0,0 +1,25 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::error::Result;
+
+use super::download::download_text;
+
+/// Directory listings downloaded during a request, clones share them,
+/// e.g. the requests of the stations of a batch
+#[derive(Debug, Clone, Default)]
+pub struct Listings(Arc<Mutex<HashMap<String, String>>>);
+
+impl Listings {
+    /// Downloads the listing on the first call, later calls return the same text
+    pub fn get(&self, url: &str) -> Result<String> {
+        if let Some(html) = self.0.lock().unwrap().get(url) {
+            return Ok(html.clone());
+        }
+        let html = download_text(url, None)?;
+        self.0.lock().unwrap().insert(url.to_string(), html.clone());
+        Ok(html)
+    }
+}
@@ -0,0 +1,377 @@
+//! Minimal writer of the NetCDF classic format (64-bit offset variant), without record dimensions
+
+use std::io::{self, Write};
+
+use super::{geo::LatLon, point::Point};
+
+/// Values of a variable or an attribute
+#[derive(Debug, Clone, PartialEq)]
+pub enum Values {
+    Text(String),
+    Int(Vec<i32>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+}
+
+impl Values {
+    fn nc_type(&self) -> u32 {
+        match self {
+            Values::Text(_) => 2,
+            Values::Int(_) => 4,
+            Values::Float(_) => 5,
+            Values::Double(_) => 6,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Values::Text(s) => s.len(),
+            Values::Int(v) => v.len(),
+            Values::Float(v) => v.len(),
+            Values::Double(v) => v.len(),
+        }
+    }
+
+    /// bytes of the encoded values
+    fn size(&self) -> usize {
+        let element = match self {
+            Values::Text(_) => 1,
+            Values::Int(_) | Values::Float(_) => 4,
+            Values::Double(_) => 8,
+        };
+        padded(self.len() * element)
+    }
+
+    /// like [`Values::encode`] without copying large variables at once
+    fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        const CHUNK: usize = 64 * 1024;
+        match self {
+            Values::Float(v) => {
+                for chunk in v.chunks(CHUNK) {
+                    let bytes = chunk.iter().flat_map(|v| v.to_be_bytes());
+                    writer.write_all(&bytes.collect::<Vec<_>>())?;
+                }
+                Ok(())
+            }
+            values => writer.write_all(&values.encode()),
+        }
+    }
+
+    /// big endian, padded to 4 bytes
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = match self {
+            Values::Text(s) => s.as_bytes().to_vec(),
+            Values::Int(v) => v.iter().flat_map(|v| v.to_be_bytes()).collect(),
+            Values::Float(v) => v.iter().flat_map(|v| v.to_be_bytes()).collect(),
+            Values::Double(v) => v.iter().flat_map(|v| v.to_be_bytes()).collect(),
+        };
+        bytes.resize(padded(bytes.len()), 0);
+        bytes
+    }
+}
+
+impl From<&str> for Values {
+    fn from(value: &str) -> Self {
+        Values::Text(value.to_string())
+    }
+}
+
+impl From<f64> for Values {
+    fn from(value: f64) -> Self {
+        Values::Double(vec![value])
+    }
+}
+
+impl From<f32> for Values {
+    fn from(value: f32) -> Self {
+        Values::Float(vec![value])
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub name: String,
+    /// indices into [`NetCdf::dimensions`], the last one varies fastest
+    pub dimensions: Vec<usize>,
+    pub attributes: Vec<(String, Values)>,
+    pub values: Values,
+}
+
+impl Variable {
+    pub fn new(name: &str, dimensions: &[usize], values: Values) -> Self {
+        Self {
+            name: name.to_string(),
+            dimensions: dimensions.to_vec(),
+            attributes: Vec::new(),
+            values,
+        }
+    }
+
+    pub fn attribute(mut self, name: &str, value: impl Into<Values>) -> Self {
+        self.attributes.push((name.to_string(), value.into()));
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NetCdf {
+    /// name and length
+    pub dimensions: Vec<(String, usize)>,
+    pub attributes: Vec<(String, Values)>,
+    pub variables: Vec<Variable>,
+}
+
+const NC_DIMENSION: u32 = 0x0A;
+const NC_VARIABLE: u32 = 0x0B;
+const NC_ATTRIBUTE: u32 = 0x0C;
+
+fn padded(len: usize) -> usize {
+    len.div_ceil(4) * 4
+}
+
+fn name(bytes: &mut Vec<u8>, name: &str) {
+    bytes.extend((name.len() as u32).to_be_bytes());
+    bytes.extend(Values::from(name).encode());
+}
+
+fn attributes(bytes: &mut Vec<u8>, attributes: &[(String, Values)]) {
+    if attributes.is_empty() {
+        // ABSENT
+        bytes.extend([0; 8]);
+        return;
+    }
+    bytes.extend(NC_ATTRIBUTE.to_be_bytes());
+    bytes.extend((attributes.len() as u32).to_be_bytes());
+    for (attribute, values) in attributes {
+        name(bytes, attribute);
+        bytes.extend(values.nc_type().to_be_bytes());
+        bytes.extend((values.len() as u32).to_be_bytes());
+        bytes.extend(values.encode());
+    }
+}
+
+impl NetCdf {
+    pub fn dimension(&mut self, name: &str, len: usize) -> usize {
+        self.dimensions.push((name.to_string(), len));
+        self.dimensions.len() - 1
+    }
+
+    /// Header with the offsets of the variables, followed by the values of each variable
+    pub fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        for variable in &self.variables {
+            let len = variable
+                .dimensions
+                .iter()
+                .map(|&d| self.dimensions[d].1)
+                .product::<usize>();
+            if len != variable.values.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} values for {} cells of {}",
+                        variable.values.len(),
+                        len,
+                        variable.name
+                    ),
+                ));
+            }
+        }
+
+        let header = |begins: &[u64]| {
+            let mut bytes = b"CDF\x02".to_vec();
+            // numrecs
+            bytes.extend(0u32.to_be_bytes());
+            match self.dimensions.is_empty() {
+                true => bytes.extend([0; 8]),
+                false => {
+                    bytes.extend(NC_DIMENSION.to_be_bytes());
+                    bytes.extend((self.dimensions.len() as u32).to_be_bytes());
+                    for (dimension, len) in &self.dimensions {
+                        name(&mut bytes, dimension);
+                        bytes.extend((*len as u32).to_be_bytes());
+                    }
+                }
+            }
+            attributes(&mut bytes, &self.attributes);
+            match self.variables.is_empty() {
+                true => bytes.extend([0; 8]),
+                false => {
+                    bytes.extend(NC_VARIABLE.to_be_bytes());
+                    bytes.extend((self.variables.len() as u32).to_be_bytes());
+                }
+            }
+            for (variable, begin) in self.variables.iter().zip(begins) {
+                name(&mut bytes, &variable.name);
+                bytes.extend((variable.dimensions.len() as u32).to_be_bytes());
+                for dimension in &variable.dimensions {
+                    bytes.extend((*dimension as u32).to_be_bytes());
+                }
+                attributes(&mut bytes, &variable.attributes);
+                bytes.extend(variable.values.nc_type().to_be_bytes());
+                // only the last variable may be larger than the field allows
+                let size = variable.values.size().min(u32::MAX as usize);
+                bytes.extend((size as u32).to_be_bytes());
+                bytes.extend(begin.to_be_bytes());
+            }
+            bytes
+        };
+
+        // the size of the header doesn't depend on the offsets
+        let mut begins = vec![0; self.variables.len()];
+        let mut offset = header(&begins).len() as u64;
+        for (begin, variable) in begins.iter_mut().zip(&self.variables) {
+            *begin = offset;
+            offset += variable.values.size() as u64;
+        }
+
+        writer.write_all(&header(&begins))?;
+        for variable in &self.variables {
+            variable.values.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// A CF-1.8 `time, y, x` cube on a projected grid
+#[derive(Debug, Clone)]
+pub struct Cube<'a> {
+    pub name: &'a str,
+    pub long_name: &'a str,
+    pub units: &'a str,
+    /// e.g. `minutes since 1970-01-01 00:00:00`
+    pub time_units: &'a str,
+    pub times: Vec<f64>,
+    /// projection coordinates of the cell centers in m
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    /// position of every cell, `y` major
+    pub positions: Vec<LatLon>,
+    /// attributes of the `crs` variable, starting with `grid_mapping_name`
+    pub grid_mapping: Vec<(&'a str, Values)>,
+    /// `time` major, then `y`, then `x`
+    pub values: Vec<f32>,
+    pub fill_value: f32,
+    /// reliable decimal places of the values, e.g. `2` for multiples of 0.01
+    pub least_significant_digit: i32,
+}
+
+impl Cube<'_> {
+    pub fn to_netcdf(self) -> NetCdf {
+        let mut nc = NetCdf::default();
+        let time = nc.dimension("time", self.times.len());
+        let y = nc.dimension("y", self.y.len());
+        let x = nc.dimension("x", self.x.len());
+        nc.attributes
+            .push(("Conventions".to_string(), "CF-1.8".into()));
+        nc.attributes
+            .push(("source".to_string(), "Deutscher Wetterdienst".into()));
+
+        let (lat, lon) = self
+            .positions
+            .iter()
+            .map(|p| (p.lat as f32, p.lon as f32))
+            .unzip();
+        let mut crs = Variable::new("crs", &[], Values::Int(vec![0]));
+        for (name, value) in self.grid_mapping {
+            crs = crs.attribute(name, value);
+        }
+        nc.variables = vec![
+            Variable::new("time", &[time], Values::Double(self.times))
+                .attribute("standard_name", "time")
+                .attribute("units", self.time_units)
+                .attribute("calendar", "standard"),
+            Variable::new("y", &[y], Values::Double(self.y))
+                .attribute("standard_name", "projection_y_coordinate")
+                .attribute("units", "m"),
+            Variable::new("x", &[x], Values::Double(self.x))
+                .attribute("standard_name", "projection_x_coordinate")
+                .attribute("units", "m"),
+            Variable::new("lat", &[y, x], Values::Float(lat))
+                .attribute("standard_name", "latitude")
+                .attribute("units", "degrees_north"),
+            Variable::new("lon", &[y, x], Values::Float(lon))
+                .attribute("standard_name", "longitude")
+                .attribute("units", "degrees_east"),
+            crs,
+            Variable::new(self.name, &[time, y, x], Values::Float(self.values))
+                .attribute("long_name", self.long_name)
+                .attribute("units", self.units)
+                .attribute("_FillValue", self.fill_value)
+                .attribute(
+                    "least_significant_digit",
+                    Values::Int(vec![self.least_significant_digit]),
+                )
+                .attribute("grid_mapping", "crs")
+                .attribute("coordinates", "lat lon"),
+        ];
+        nc
+    }
+}
+
+/// Sorted distinct columns and rows of the points, the axes of a [`Cube`]
+pub fn axes<T: Ord + Copy>(points: &[Point<T>]) -> (Vec<T>, Vec<T>) {
+    let mut columns = points.iter().map(|p| p.x).collect::<Vec<_>>();
+    let mut rows = points.iter().map(|p| p.y).collect::<Vec<_>>();
+    for axis in [&mut columns, &mut rows] {
+        axis.sort();
+        axis.dedup();
+    }
+    (columns, rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let mut nc = NetCdf::default();
+        let x = nc.dimension("x", 3);
+        nc.attributes.push(("title".to_string(), "test".into()));
+        nc.variables.push(
+            Variable::new("x", &[x], Values::Double(vec![1.0, 2.0, 3.0])).attribute("units", "m"),
+        );
+        nc.variables
+            .push(Variable::new("crs", &[], Values::Int(vec![0])));
+
+        let mut out = Vec::new();
+        nc.write(&mut out).unwrap();
+
+        let expected_header = [
+            b"CDF\x02".as_slice(),
+            &[0, 0, 0, 0],
+            // dimensions
+            &[0, 0, 0, 0x0A, 0, 0, 0, 1],
+            &[0, 0, 0, 1, b'x', 0, 0, 0, 0, 0, 0, 3],
+            // global attributes
+            &[0, 0, 0, 0x0C, 0, 0, 0, 1],
+            &[0, 0, 0, 5, b't', b'i', b't', b'l', b'e', 0, 0, 0],
+            &[0, 0, 0, 2, 0, 0, 0, 4, b't', b'e', b's', b't'],
+            // variables
+            &[0, 0, 0, 0x0B, 0, 0, 0, 2],
+            &[0, 0, 0, 1, b'x', 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0],
+            &[0, 0, 0, 0x0C, 0, 0, 0, 1],
+            &[0, 0, 0, 5, b'u', b'n', b'i', b't', b's', 0, 0, 0],
+            &[0, 0, 0, 2, 0, 0, 0, 1, b'm', 0, 0, 0],
+            &[0, 0, 0, 6, 0, 0, 0, 24],
+        ]
+        .concat();
+        assert_eq!(&out[..expected_header.len()], expected_header);
+
+        let header_len = out.len() - 24 - 4;
+        let begin = u64::from_be_bytes(out[expected_header.len()..][..8].try_into().unwrap());
+        assert_eq!(begin, header_len as u64);
+        assert_eq!(&out[header_len..header_len + 8], 1.0f64.to_be_bytes());
+        assert_eq!(&out[out.len() - 4..], [0, 0, 0, 0]);
+
+        nc.variables
+            .push(Variable::new("y", &[x], Values::Float(vec![1.0])));
+        assert!(nc.write(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_axes() {
+        let points = [Point::new(3, 1), Point::new(1, 1), Point::new(3, 5)];
+        assert_eq!(axes(&points), (vec![1, 3], vec![1, 5]));
+    }
+}
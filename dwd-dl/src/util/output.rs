@@ -0,0 +1,16 @@
+use std::io::Write;
+
+use crate::error::Result;
+
+/// Runs a writing formatter on a buffer, for formatters that only get already downloaded records
+/// and can't reject their options
+pub fn write_to_string(write: impl FnOnce(&mut dyn Write) -> Result<()>) -> String {
+    try_write_to_string(write).expect("formatting into memory can't fail")
+}
+
+/// [`write_to_string`] for formatters which can reject their options
+pub fn try_write_to_string(write: impl FnOnce(&mut dyn Write) -> Result<()>) -> Result<String> {
+    let mut buf = Vec::new();
+    write(&mut buf)?;
+    Ok(String::from_utf8(buf).expect("formatters only write UTF-8"))
+}
@@ -0,0 +1,309 @@
+//! Polygons parsed from WKT or GeoJSON, vertices are `(x, y)`, for WGS84 that's `(lon, lat)`
+
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::error::ParseError;
+
+pub type Vertex = (f64, f64);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    pub exterior: Vec<Vertex>,
+    pub holes: Vec<Vec<Vertex>>,
+}
+
+/// One or more polygons, the union of them is the area
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiPolygon(pub Vec<Polygon>);
+
+impl MultiPolygon {
+    /// Transforms every vertex, e.g. to project WGS84 onto a grid
+    pub fn map(&self, f: impl Fn(Vertex) -> Vertex) -> Self {
+        let ring = |ring: &Vec<Vertex>| ring.iter().copied().map(&f).collect::<Vec<_>>();
+        Self(
+            self.0
+                .iter()
+                .map(|p| Polygon {
+                    exterior: ring(&p.exterior),
+                    holes: p.holes.iter().map(ring).collect(),
+                })
+                .collect(),
+        )
+    }
+
+    /// `(min, max)` corner of the exterior rings
+    pub fn bounding_box(&self) -> (Vertex, Vertex) {
+        let vertices = self.0.iter().flat_map(|p| p.exterior.iter());
+        vertices.fold(
+            (
+                (f64::INFINITY, f64::INFINITY),
+                (f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(min, max), v| {
+                (
+                    (min.0.min(v.0), min.1.min(v.1)),
+                    (max.0.max(v.0), max.1.max(v.1)),
+                )
+            },
+        )
+    }
+
+    /// Area of the intersection with the rectangle `min..max`
+    pub fn clipped_area(&self, min: Vertex, max: Vertex) -> f64 {
+        let area = |ring: &Vec<Vertex>| ring_area(&clip(ring, min, max)).abs();
+        self.0
+            .iter()
+            .map(|p| area(&p.exterior) - p.holes.iter().map(area).sum::<f64>())
+            .sum()
+    }
+}
+
+/// shoelace formula, positive for counterclockwise rings
+fn ring_area(ring: &[Vertex]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Sutherland-Hodgman clipping against the rectangle `min..max`,
+/// concave rings may get degenerate edges which don't change the area
+fn clip(ring: &[Vertex], min: Vertex, max: Vertex) -> Vec<Vertex> {
+    type Axis = fn(Vertex) -> f64;
+    let edges: [(Axis, f64, bool); 4] = [
+        (|v| v.0, min.0, true),
+        (|v| v.0, max.0, false),
+        (|v| v.1, min.1, true),
+        (|v| v.1, max.1, false),
+    ];
+    let mut ring = ring.to_vec();
+    for (axis, bound, lower) in edges {
+        let inside = |v: Vertex| match lower {
+            true => axis(v) >= bound,
+            false => axis(v) <= bound,
+        };
+        let crossing = |a: Vertex, b: Vertex| {
+            let t = (bound - axis(a)) / (axis(b) - axis(a));
+            (a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1))
+        };
+        let input = std::mem::take(&mut ring);
+        for (i, &current) in input.iter().enumerate() {
+            let previous = input[(i + input.len() - 1) % input.len()];
+            match (inside(previous), inside(current)) {
+                (true, true) => ring.push(current),
+                (true, false) => ring.push(crossing(previous, current)),
+                (false, true) => {
+                    ring.push(crossing(previous, current));
+                    ring.push(current);
+                }
+                (false, false) => (),
+            }
+        }
+    }
+    ring
+}
+
+impl FromStr for MultiPolygon {
+    type Err = ParseError;
+
+    /// GeoJSON (Polygon, MultiPolygon, Feature or FeatureCollection) or WKT (POLYGON, MULTIPOLYGON)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let polygons = match s.starts_with('{') {
+            true => {
+                let json =
+                    serde_json::from_str::<Value>(s).map_err(|_| ParseError::new(s, "GeoJSON"))?;
+                geojson_polygons(&json)
+            }
+            false => wkt_polygons(s),
+        }
+        .ok_or_else(|| ParseError::new(s, "polygon"))?;
+        if polygons.is_empty() {
+            return Err(ParseError::new(s, "polygon"));
+        }
+        Ok(Self(polygons))
+    }
+}
+
+fn geojson_polygons(json: &Value) -> Option<Vec<Polygon>> {
+    let ring = |ring: &Value| -> Option<Vec<Vertex>> {
+        ring.as_array()?
+            .iter()
+            .map(|v| Some((v.get(0)?.as_f64()?, v.get(1)?.as_f64()?)))
+            .collect()
+    };
+    let polygon = |rings: &Value| -> Option<Polygon> {
+        let rings = rings
+            .as_array()?
+            .iter()
+            .map(ring)
+            .collect::<Option<Vec<_>>>()?;
+        polygon_of(rings)
+    };
+    match json.get("type")?.as_str()? {
+        "Polygon" => Some(vec![polygon(json.get("coordinates")?)?]),
+        "MultiPolygon" => json
+            .get("coordinates")?
+            .as_array()?
+            .iter()
+            .map(polygon)
+            .collect(),
+        "Feature" => geojson_polygons(json.get("geometry")?),
+        "FeatureCollection" => {
+            let features = json.get("features")?.as_array()?.iter();
+            let polygons = features.map(geojson_polygons).collect::<Option<Vec<_>>>()?;
+            Some(polygons.into_iter().flatten().collect())
+        }
+        _ => None,
+    }
+}
+
+/// A parenthesized WKT list, either of coordinates or of nested lists
+enum Nested {
+    Coordinates(Vec<Vertex>),
+    List(Vec<Nested>),
+}
+
+fn wkt_polygons(s: &str) -> Option<Vec<Polygon>> {
+    let (keyword, mut rest) = s.split_at(s.find('(')?);
+    let nested = parse_nested(&mut rest)?;
+    if !rest.trim().is_empty() {
+        return None;
+    }
+    let polygon = |nested: Nested| match nested {
+        Nested::List(rings) => {
+            let rings = rings.into_iter().map(|r| match r {
+                Nested::Coordinates(c) => Some(c),
+                Nested::List(_) => None,
+            });
+            polygon_of(rings.collect::<Option<Vec<_>>>()?)
+        }
+        Nested::Coordinates(_) => None,
+    };
+    match keyword.trim().to_ascii_uppercase().as_str() {
+        "POLYGON" => Some(vec![polygon(nested)?]),
+        "MULTIPOLYGON" => match nested {
+            Nested::List(polygons) => polygons.into_iter().map(polygon).collect(),
+            Nested::Coordinates(_) => None,
+        },
+        _ => None,
+    }
+}
+
+fn parse_nested(s: &mut &str) -> Option<Nested> {
+    *s = s.trim_start().strip_prefix('(')?;
+    if s.trim_start().starts_with('(') {
+        let mut list = Vec::new();
+        loop {
+            list.push(parse_nested(s)?);
+            *s = s.trim_start();
+            match s.chars().next()? {
+                ',' => *s = &s[1..],
+                ')' => {
+                    *s = &s[1..];
+                    return Some(Nested::List(list));
+                }
+                _ => return None,
+            }
+        }
+    }
+    let (coordinates, rest) = s.split_once(')')?;
+    *s = rest;
+    coordinates
+        .split(',')
+        .map(|c| {
+            let mut numbers = c.split_whitespace().map(|n| n.parse::<f64>().ok());
+            Some((numbers.next()??, numbers.next()??))
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(Nested::Coordinates)
+}
+
+/// first ring is the exterior, every ring needs at least 3 distinct vertices
+fn polygon_of(mut rings: Vec<Vec<Vertex>>) -> Option<Polygon> {
+    for ring in &mut rings {
+        if ring.len() > 1 && ring.first() == ring.last() {
+            ring.pop();
+        }
+        if ring.len() < 3 {
+            return None;
+        }
+    }
+    let mut rings = rings.into_iter();
+    Some(Polygon {
+        exterior: rings.next()?,
+        holes: rings.collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Vec<Vertex> {
+        vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]
+    }
+
+    #[test]
+    fn test_wkt() {
+        let polygon = "POLYGON ((0 0, 4 0, 4 4, 0 4, 0 0), (1 1, 2 1, 2 2, 1 1))"
+            .parse::<MultiPolygon>()
+            .unwrap();
+        assert_eq!(polygon.0.len(), 1);
+        assert_eq!(polygon.0[0].exterior, square());
+        assert_eq!(polygon.0[0].holes.len(), 1);
+
+        let multi = "MultiPolygon (((0 0, 1 0, 1 1)), ((5 5, 6 5, 6 6, 5 5)))"
+            .parse::<MultiPolygon>()
+            .unwrap();
+        assert_eq!(multi.0.len(), 2);
+
+        assert!("POLYGON ((0 0, 1 1))".parse::<MultiPolygon>().is_err());
+        assert!("POINT (1 1)".parse::<MultiPolygon>().is_err());
+        assert!("POLYGON ((0 0, 1 0, 1 1)".parse::<MultiPolygon>().is_err());
+    }
+
+    #[test]
+    fn test_geojson() {
+        let polygon =
+            r#"{"type": "Polygon", "coordinates": [[[0, 0], [4, 0], [4, 4], [0, 4], [0, 0]]]}"#
+                .parse::<MultiPolygon>()
+                .unwrap();
+        assert_eq!(polygon.0[0].exterior, square());
+
+        let feature = format!(
+            r#"{{"type": "FeatureCollection", "features": [{{"type": "Feature", "properties": {{}}, "geometry": {}}}]}}"#,
+            r#"{"type": "MultiPolygon", "coordinates": [[[[0, 0], [1, 0], [1, 1]]], [[[5, 5], [6, 5], [6, 6]]]]}"#
+        );
+        assert_eq!(feature.parse::<MultiPolygon>().unwrap().0.len(), 2);
+
+        assert!(r#"{"type": "Point", "coordinates": [1, 1]}"#.parse::<MultiPolygon>().is_err());
+    }
+
+    #[test]
+    fn test_clipped_area() {
+        let polygon = MultiPolygon(vec![Polygon {
+            exterior: square(),
+            holes: vec![vec![(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 2.0)]],
+        }]);
+        assert_eq!(polygon.clipped_area((0.0, 0.0), (4.0, 4.0)), 15.0);
+        assert_eq!(polygon.clipped_area((3.0, 3.0), (5.0, 5.0)), 1.0);
+        assert_eq!(polygon.clipped_area((1.0, 1.0), (2.0, 2.0)), 0.0);
+        assert_eq!(polygon.clipped_area((5.0, 5.0), (6.0, 6.0)), 0.0);
+
+        // a triangle covers half of the cells on its diagonal, clockwise rings work too
+        let triangle = MultiPolygon(vec![Polygon {
+            exterior: vec![(0.0, 0.0), (0.0, 2.0), (2.0, 0.0)],
+            holes: vec![],
+        }]);
+        assert_eq!(triangle.clipped_area((0.0, 0.0), (1.0, 1.0)), 1.0);
+        assert_eq!(triangle.clipped_area((1.0, 0.0), (2.0, 1.0)), 0.5);
+        assert_eq!(triangle.bounding_box(), ((0.0, 0.0), (2.0, 2.0)));
+    }
+}
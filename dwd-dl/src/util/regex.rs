@@ -2,6 +2,8 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use time::Date;
 
+use crate::error::ParseError;
+
 use super::interval::Interval;
 
 /// Extracts links from a text using a specified regex pattern.
@@ -54,25 +56,33 @@ pub fn year_links_in_text(text: &str) -> Vec<i32> {
         .collect::<Vec<_>>()
 }
 
-pub fn extract_interval_d8_d8(s: &str) -> Result<Interval<Date>, ()> {
+pub fn extract_interval_d8_d8(s: &str) -> Result<Interval<Date>, ParseError> {
     static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?P<start>\d{8})_(?P<end>\d{8})").unwrap());
-    let cap = RE.captures(s).ok_or(())?;
-    Ok(Interval::<Date>::parse_str(&cap["start"], &cap["end"]))
+    let cap = RE
+        .captures(s)
+        .ok_or_else(|| ParseError::new(s, "interval (YYYYMMDD_YYYYMMDD)"))?;
+    Interval::<Date>::parse_str(&cap["start"], &cap["end"])
 }
 
-pub fn extract_d6(s: &str) -> Result<&str, ()> {
+pub fn extract_d6(s: &str) -> Result<&str, ParseError> {
     static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d{6}").unwrap());
-    Ok(RE.find(s).unwrap().as_str())
+    RE.find(s)
+        .map(|m| m.as_str())
+        .ok_or_else(|| ParseError::new(s, "name containing 6 digits"))
 }
 
-pub fn extract_d8(s: &str) -> Result<&str, ()> {
+pub fn extract_d8(s: &str) -> Result<&str, ParseError> {
     static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d{8}").unwrap());
-    Ok(RE.find(s).unwrap().as_str())
+    RE.find(s)
+        .map(|m| m.as_str())
+        .ok_or_else(|| ParseError::new(s, "name containing 8 digits"))
 }
 
-pub fn extract_d10(s: &str) -> Result<&str, ()> {
+pub fn extract_d10(s: &str) -> Result<&str, ParseError> {
     static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d{10}").unwrap());
-    Ok(RE.find(s).unwrap().as_str())
+    RE.find(s)
+        .map(|m| m.as_str())
+        .ok_or_else(|| ParseError::new(s, "name containing 10 digits"))
 }
 
 #[cfg(test)]
@@ -4,36 +4,36 @@ use time::{
     Date, PrimitiveDateTime, Time,
 };
 
+use crate::error::ParseError;
+
 pub mod timezone;
 
-pub fn parse_yyyymm(s: &str) -> Result<Date, ()> {
-    let year = s[..4].parse().unwrap();
-    let month: u8 = s[4..].parse().unwrap();
-    Date::from_calendar_date(year, month.try_into().unwrap(), 1).map_err(|_| ())
+pub fn parse_yyyymm(s: &str) -> Result<Date, ParseError> {
+    let error = || ParseError::new(s, "date (YYYYMM)");
+    let year = s.get(..4).and_then(|y| y.parse().ok()).ok_or_else(error)?;
+    let month: u8 = s.get(4..).and_then(|m| m.parse().ok()).ok_or_else(error)?;
+    let month = month.try_into().map_err(|_| error())?;
+    Date::from_calendar_date(year, month, 1).map_err(|_| error())
 }
 
-// TODO Error Handling
-pub fn parse_yyyymmdd(s: &str) -> Result<Date, ()> {
+pub fn parse_yyyymmdd(s: &str) -> Result<Date, ParseError> {
     static FORMAT: &[FormatItem<'_>] = format_description!("[year][month][day]");
-    Date::parse(s, &FORMAT).map_err(|_| ())
+    Date::parse(s, &FORMAT).map_err(|_| ParseError::new(s, "date (YYYYMMDD)"))
 }
 
-// TODO Error Handling
-pub fn parse_yyyymmdd_into_date_time(s: &str) -> Result<PrimitiveDateTime, ()> {
+pub fn parse_yyyymmdd_into_date_time(s: &str) -> Result<PrimitiveDateTime, ParseError> {
     let date = parse_yyyymmdd(s)?;
     Ok(PrimitiveDateTime::new(date, Time::MIDNIGHT))
 }
 
-// TODO Error Handling
-pub fn parse_yyyymmddhhmm(s: &str) -> Result<PrimitiveDateTime, ()> {
+pub fn parse_yyyymmddhhmm(s: &str) -> Result<PrimitiveDateTime, ParseError> {
     let format = format_description!("[year][month][day][hour][minute]");
-    PrimitiveDateTime::parse(s, &format).map_err(|_| ())
+    PrimitiveDateTime::parse(s, &format).map_err(|_| ParseError::new(s, "datetime (YYYYMMDDhhmm)"))
 }
 
-// TODO Error Handling
-pub fn parse_yyyymmddhh(s: &str) -> Result<PrimitiveDateTime, ()> {
+pub fn parse_yyyymmddhh(s: &str) -> Result<PrimitiveDateTime, ParseError> {
     let format = format_description!("[year][month][day][hour]");
-    PrimitiveDateTime::parse(s, &format).map_err(|_| ())
+    PrimitiveDateTime::parse(s, &format).map_err(|_| ParseError::new(s, "datetime (YYYYMMDDhh)"))
 }
 
 pub fn format_yyyymmddhhmm(date: PrimitiveDateTime) -> String {
@@ -20,9 +20,12 @@ fn test_daily() {
                 start: datetime!(2022 - 12 - 25 00:00:00),
                 end: datetime!(2023 - 01 - 06 00:00:00),
             },
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let data = ClimateProduct.download(&request);
+    let data = ClimateProduct.download(&request).unwrap();
     let response = climate_data_to_string(data, &resolution);
 
     let assert = r"STATIONS_ID;MESS_DATUM;QN_3;  FX;  FM;QN_4; RSK;RSKF; SDK;SHK_TAG;  NM; VPM;  PM; TMK; UPM; TXK; TNK; TGK;eor
@@ -46,16 +49,21 @@ fn test_daily() {
 fn test_monthly() {
     common::setup();
     let resolution = ClimateResolution::ClimateMonthly;
-    let data = ClimateProduct.download(&ClimateCommonRequestData {
-        station: "00044".to_string(),
-        resolution,
-        common: CommonRequestData {
-            timespan: dwd_dl::util::interval::Interval {
-                start: datetime!(2022 - 03 - 01 00:00:00),
-                end: datetime!(2023 - 05 - 02 00:00:00),
+    let data = ClimateProduct
+        .download(&ClimateCommonRequestData {
+            station: "00044".to_string(),
+            resolution,
+            common: CommonRequestData {
+                timespan: dwd_dl::util::interval::Interval {
+                    start: datetime!(2022 - 03 - 01 00:00:00),
+                    end: datetime!(2023 - 05 - 02 00:00:00),
+                },
+                progress: Default::default(),
+                cancel: Default::default(),
+                listings: Default::default(),
             },
-        },
-    });
+        })
+        .unwrap();
     let response = climate_data_to_string(data, &resolution);
 
     let assert = r"STATIONS_ID;MESS_DATUM_BEGINN;MESS_DATUM_ENDE;QN_4;MO_N;MO_TT;MO_TX;MO_TN;MO_FK;MX_TX;MX_FX;MX_TN;MO_SD_S;QN_6;MO_RR;MX_RS;eor
@@ -82,16 +90,21 @@ fn test_monthly() {
 fn test_annual() {
     common::setup();
     let resolution = ClimateResolution::ClimateAnnual;
-    let data = ClimateProduct.download(&ClimateCommonRequestData {
-        station: "00044".to_string(),
-        resolution,
-        common: CommonRequestData {
-            timespan: dwd_dl::util::interval::Interval {
-                start: datetime!(2020 - 01 - 01 00:00:00),
-                end: datetime!(2023 - 10 - 01 00:00:00),
+    let data = ClimateProduct
+        .download(&ClimateCommonRequestData {
+            station: "00044".to_string(),
+            resolution,
+            common: CommonRequestData {
+                timespan: dwd_dl::util::interval::Interval {
+                    start: datetime!(2020 - 01 - 01 00:00:00),
+                    end: datetime!(2023 - 10 - 01 00:00:00),
+                },
+                progress: Default::default(),
+                cancel: Default::default(),
+                listings: Default::default(),
             },
-        },
-    });
+        })
+        .unwrap();
     let response = climate_data_to_string(data, &resolution);
 
     let assert = r"STATIONS_ID;MESS_DATUM_BEGINN;MESS_DATUM_ENDE;QN_4;JA_N;JA_TT;JA_TX;JA_TN;JA_FK;JA_SD_S;JA_MX_FX;JA_MX_TX;JA_MX_TN;QN_6;JA_RR;JA_MX_RS;eor
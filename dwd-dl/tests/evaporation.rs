@@ -2,30 +2,49 @@ use dwd_dl::{
     dwd_source::{CommonRequestData, DwdProduct},
     products::evaporation::{
         self,
-        formats::{format_default, format_swmm_rainfall_data},
+        decode::{GridHeader, GridPosition},
+        formats::{format_default, format_swmm_rainfall_data, write_netcdf},
         EvaporationRequest, EvaporationResolution,
     },
     util::point::Point,
 };
-use time::macros::{date, datetime};
+use time::{
+    macros::{date, datetime},
+    Date,
+};
 
 mod common;
 
+fn record(time: Date, cells: &[Point<usize>], data: Vec<f32>) -> evaporation::Record {
+    evaporation::Record {
+        time,
+        header: GridHeader::GERMANY,
+        cells: cells.to_vec(),
+        data,
+    }
+}
+
 #[test]
 fn test_daily_p() {
     common::setup();
     let resolution = EvaporationResolution::EvaporationDailyP;
     let request = EvaporationRequest {
-        coordinates: vec![Point::new(20, 369), Point::new(21, 369)],
+        coordinates: vec![
+            GridPosition::Cell(Point::new(20, 369)),
+            GridPosition::Cell(Point::new(21, 369)),
+        ],
         resolution,
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval {
                 start: datetime!(2022 - 12 - 31 00:00:00),
                 end: datetime!(2023 - 01 - 05 00:00:00),
             },
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = evaporation::Product.downloadx(request);
+    let response = evaporation::Product.downloadx(request).unwrap();
     dbg!(&response);
     assert_eq!(response.records.len(), 5);
 }
@@ -35,16 +54,22 @@ fn test_daily_r() {
     common::setup();
     let resolution = EvaporationResolution::EvaporationDailyR;
     let request = EvaporationRequest {
-        coordinates: vec![Point::new(20, 369), Point::new(21, 369)],
+        coordinates: vec![
+            GridPosition::Cell(Point::new(20, 369)),
+            GridPosition::Cell(Point::new(21, 369)),
+        ],
         resolution,
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval {
                 start: datetime!(2022 - 12 - 15 00:00:00),
                 end: datetime!(2022 - 12 - 21 00:00:00),
             },
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = evaporation::Product.downloadx(request);
+    let response = evaporation::Product.downloadx(request).unwrap();
     dbg!(&response);
     assert_eq!(response.records.len(), 6);
 }
@@ -54,16 +79,22 @@ fn test_monthly_p() {
     common::setup();
     let resolution = EvaporationResolution::EvaporationMonthlyP;
     let request = EvaporationRequest {
-        coordinates: vec![Point::new(20, 369), Point::new(21, 369)],
+        coordinates: vec![
+            GridPosition::Cell(Point::new(20, 369)),
+            GridPosition::Cell(Point::new(21, 369)),
+        ],
         resolution,
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval {
                 start: datetime!(2022 - 12 - 1 00:00:00),
                 end: datetime!(2022 - 12 - 2 00:00:00),
             },
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = evaporation::Product.downloadx(request);
+    let response = evaporation::Product.downloadx(request).unwrap();
     dbg!(&response);
     assert_eq!(response.records.len(), 1);
 }
@@ -73,16 +104,22 @@ fn test_monthly_r() {
     common::setup();
     let resolution = EvaporationResolution::EvaporationMonthlyR;
     let request = EvaporationRequest {
-        coordinates: vec![Point::new(20, 369), Point::new(21, 369)],
+        coordinates: vec![
+            GridPosition::Cell(Point::new(20, 369)),
+            GridPosition::Cell(Point::new(21, 369)),
+        ],
         resolution,
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval {
                 start: datetime!(2022 - 12 - 1 00:00:00),
                 end: datetime!(2023 - 12 - 2 00:00:00),
             },
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = evaporation::Product.downloadx(request);
+    let response = evaporation::Product.downloadx(request).unwrap();
     dbg!(&response);
     assert_eq!(response.records.len(), 1);
 }
@@ -91,30 +128,18 @@ fn test_monthly_r() {
 fn test_format_default() {
     common::setup();
 
+    let cells = [
+        Point::new(4, 1),
+        Point::new(4, 2),
+        Point::new(5, 2),
+        Point::new(7, 1),
+    ];
     let res = format_default(evaporation::EvaporationResponse {
-        coordinates: vec![
-            Point::new(4, 1), // 1
-            Point::new(4, 2), // 3
-            Point::new(5, 2), // 4
-            Point::new(7, 1), // 2
-        ],
         records: vec![
-            evaporation::Record {
-                time: date!(2022 - 12 - 31),
-                data: vec![1.0, 2.0, 3.0, 4.0],
-            },
-            evaporation::Record {
-                time: date!(2023 - 01 - 01),
-                data: vec![1.1, 2.1, 3.1, 4.1],
-            },
-            evaporation::Record {
-                time: date!(2023 - 01 - 02),
-                data: vec![1.2, 2.2, 3.2, 4.2],
-            },
-            evaporation::Record {
-                time: date!(2023 - 01 - 03),
-                data: vec![1.3, 2.3, 3.3, 4.3],
-            },
+            record(date!(2022 - 12 - 31), &cells, vec![1.0, 3.0, 4.0, 2.0]),
+            record(date!(2023 - 01 - 01), &cells, vec![1.1, 3.1, 4.1, 2.1]),
+            record(date!(2023 - 01 - 02), &cells, vec![1.2, 3.2, 4.2, 2.2]),
+            record(date!(2023 - 01 - 03), &cells, vec![1.3, 3.3, 4.3, 2.3]),
         ],
     });
 
@@ -128,25 +153,13 @@ fn test_format_default() {
 fn test_format_swmm_rainfall_data() {
     common::setup();
 
+    let cells = [Point::new(2, 1)];
     let res = format_swmm_rainfall_data(evaporation::EvaporationResponse {
-        coordinates: vec![Point::new(2, 1)],
         records: vec![
-            evaporation::Record {
-                time: date!(2022 - 12 - 31),
-                data: vec![1.0],
-            },
-            evaporation::Record {
-                time: date!(2023 - 01 - 01),
-                data: vec![2.0],
-            },
-            evaporation::Record {
-                time: date!(2023 - 01 - 02),
-                data: vec![3.0],
-            },
-            evaporation::Record {
-                time: date!(2023 - 01 - 03),
-                data: vec![4.0],
-            },
+            record(date!(2022 - 12 - 31), &cells, vec![1.0]),
+            record(date!(2023 - 01 - 01), &cells, vec![2.0]),
+            record(date!(2023 - 01 - 02), &cells, vec![3.0]),
+            record(date!(2023 - 01 - 03), &cells, vec![4.0]),
         ],
     });
 
@@ -155,3 +168,69 @@ fn test_format_swmm_rainfall_data() {
         res
     );
 }
+
+#[test]
+fn test_write_netcdf() {
+    common::setup();
+
+    // the values are in the order of the cells
+    let cells = [Point::new(3, 2), Point::new(2, 1)];
+    let response = evaporation::EvaporationResponse {
+        records: vec![
+            record(date!(1970 - 01 - 02), &cells, vec![-99.9, 1.0]),
+            record(date!(1970 - 01 - 03), &cells, vec![2.5, 1.5]),
+        ],
+    };
+    let mut out = Vec::new();
+    write_netcdf(&mut out, &mut response.records.into_iter().map(Ok)).unwrap();
+
+    assert_eq!(&out[..4], b"CDF\x02");
+    let contains = |s: &str| out.windows(s.len()).any(|w| w == s.as_bytes());
+    assert!(contains("transverse_mercator"));
+    assert!(contains("days since 1970-01-01"));
+
+    let cube = out[out.len() - 2 * 2 * 2 * 4..]
+        .chunks(4)
+        .map(|b| f32::from_be_bytes(b.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    let fill = -9999.0;
+    assert_eq!(cube, vec![1.0, fill, fill, fill, 1.5, fill, fill, 2.5]);
+
+    // the cells of the second file differ
+    let records = vec![
+        record(date!(1970 - 01 - 02), &cells, vec![-99.9, 1.0]),
+        record(
+            date!(1970 - 01 - 03),
+            &[Point::new(3, 3), Point::new(2, 1)],
+            vec![2.5, 1.5],
+        ),
+    ];
+    let result = write_netcdf(&mut Vec::new(), &mut records.into_iter().map(Ok));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_format_default_cells_of_each_file() {
+    common::setup();
+
+    // the second file has another grid, the coordinate is in another cell
+    let res = format_default(evaporation::EvaporationResponse {
+        records: vec![
+            record(
+                date!(2022 - 12 - 31),
+                &[Point::new(4, 1), Point::new(7, 1)],
+                vec![1.0, 2.0],
+            ),
+            record(
+                date!(2023 - 01 - 01),
+                &[Point::new(5, 1), Point::new(8, 2)],
+                vec![1.1, 2.1],
+            ),
+        ],
+    });
+
+    assert_eq!(
+        "x_y\tDate\tValue\n0004_0001\t2022-12-31\t1.0\n0005_0001\t2023-01-01\t1.1\n0007_0001\t2022-12-31\t2.0\n0008_0002\t2023-01-01\t2.1\n",
+        res
+    );
+}
@@ -15,14 +15,18 @@ fn test_min1() {
     let request = PrecipitationCommonRequestData {
         station: "00020".to_string(),
         resolution,
+        quality: Default::default(),
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval {
                 start: datetime!(2022 - 01 - 31 23:57:00),
                 end: datetime!(2022 - 02 - 01 00:03:00),
             },
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = precipitation::Product.downloadx(request);
+    let response = precipitation::Product.downloadx(request).unwrap();
 
     let assert = PrecipitationResponse {
         station: "00020".to_string(),
@@ -34,6 +38,11 @@ fn test_min1() {
                 )
                 .unwrap(),
                 rs: 0.0,
+                quality: Some(3),
+                indicator: Some(0),
+                rth: Some(0.0),
+                rwh: Some(0.0),
+                flagged: false,
             },
             precipitation::PrecipitationRecord {
                 timespan: dwd_dl::util::interval::Interval::new(
@@ -42,6 +51,11 @@ fn test_min1() {
                 )
                 .unwrap(),
                 rs: 0.0,
+                quality: Some(3),
+                indicator: Some(0),
+                rth: Some(0.0),
+                rwh: Some(0.0),
+                flagged: false,
             },
             precipitation::PrecipitationRecord {
                 timespan: dwd_dl::util::interval::Interval::new(
@@ -50,6 +64,11 @@ fn test_min1() {
                 )
                 .unwrap(),
                 rs: 0.0,
+                quality: Some(3),
+                indicator: Some(0),
+                rth: Some(0.0),
+                rwh: Some(0.0),
+                flagged: false,
             },
             precipitation::PrecipitationRecord {
                 timespan: dwd_dl::util::interval::Interval::new(
@@ -58,6 +77,11 @@ fn test_min1() {
                 )
                 .unwrap(),
                 rs: 0.0,
+                quality: Some(3),
+                indicator: Some(0),
+                rth: Some(0.0),
+                rwh: Some(0.0),
+                flagged: false,
             },
         ],
     };
@@ -71,15 +95,19 @@ fn test_min5() {
     let request = PrecipitationCommonRequestData {
         station: "00020".to_string(),
         resolution,
+        quality: Default::default(),
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval::new(
                 datetime!(2008 - 03 - 31 23:55),
                 datetime!(2008 - 04 - 01 00:06),
             )
             .unwrap(),
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = precipitation::Product.downloadx(request);
+    let response = precipitation::Product.downloadx(request).unwrap();
     // dbg!(&response.records);
     assert_eq!(response.records.len(), 3);
 }
@@ -91,15 +119,19 @@ fn test_min10() {
     let request = PrecipitationCommonRequestData {
         station: "00078".to_string(),
         resolution,
+        quality: Default::default(),
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval::new(
                 datetime!(2009 - 12 - 31 23:40),
                 datetime!(2010 - 01 - 01 01:06),
             )
             .unwrap(),
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = precipitation::Product.downloadx(request);
+    let response = precipitation::Product.downloadx(request).unwrap();
     dbg!(&response.records);
     assert_eq!(response.records.len(), 9);
 }
@@ -111,15 +143,19 @@ fn test_hourly() {
     let request = PrecipitationCommonRequestData {
         station: "00164".to_string(),
         resolution,
+        quality: Default::default(),
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval::new(
                 datetime!(2022 - 12 - 31 19:55),
                 datetime!(2023 - 01 - 01 01:06),
             )
             .unwrap(),
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = precipitation::Product.downloadx(request);
+    let response = precipitation::Product.downloadx(request).unwrap();
     dbg!(&response.records);
     assert_eq!(response.records.len(), 6);
 }
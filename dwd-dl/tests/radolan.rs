@@ -1,9 +1,13 @@
 use dwd_dl::{
     dwd_source::{CommonRequestData, DwdProduct},
+    error::DwdError,
     products::radolan::{
         self,
-        formats::{format_default, format_swmm_rainfall_data, RadolanFormatConfig},
-        RadolanRequest, RadolanResolution,
+        formats::{
+            format_default, format_swmm_rainfall_data, write_default, write_netcdf, NodataPolicy,
+            RadolanFormatConfig,
+        },
+        AreaStatistics, Flag, RadolanRequest, RadolanResolution, Unit,
     },
     util::point::Point,
 };
@@ -17,15 +21,19 @@ fn test_min5() {
     let resolution = RadolanResolution::RadolanMin5;
     let request = RadolanRequest {
         coordinates: vec![Point::new(20, 369), Point::new(21, 369)],
+        area: None,
         resolution,
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval {
                 start: datetime!(2023 - 11 - 17 23:30:00),
                 end: datetime!(2023 - 11 - 18 00:30:00),
             },
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = radolan::Product.downloadx(request);
+    let response = radolan::Product.downloadx(request).unwrap();
     dbg!(&response);
     assert_eq!(response.records.len(), 12);
 }
@@ -36,15 +44,19 @@ fn test_min5_reproc() {
     let resolution = RadolanResolution::RadolanMin5Reproc2017;
     let request = RadolanRequest {
         coordinates: vec![Point::new(20, 369), Point::new(21, 369)],
+        area: None,
         resolution,
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval {
                 start: datetime!(2022 - 12 - 31 23:30:00),
                 end: datetime!(2023 - 01 - 01 00:30:00),
             },
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = radolan::Product.downloadx(request);
+    let response = radolan::Product.downloadx(request).unwrap();
     dbg!(&response);
     assert_eq!(response.records.len(), 12);
 }
@@ -55,15 +67,19 @@ fn test_hourly() {
     let resolution = RadolanResolution::RadolanHourly;
     let request = RadolanRequest {
         coordinates: vec![Point::new(20, 369), Point::new(21, 369)],
+        area: None,
         resolution,
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval {
                 start: datetime!(2022 - 12 - 31 00:00:00),
                 end: datetime!(2023 - 01 - 01 03:00:00),
             },
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = radolan::Product.downloadx(request);
+    let response = radolan::Product.downloadx(request).unwrap();
     dbg!(&response);
     assert_eq!(response.records.len(), 27);
 }
@@ -74,15 +90,19 @@ fn test_hourly_reproc() {
     let resolution = RadolanResolution::RadolanHourlyReproc2017;
     let request = RadolanRequest {
         coordinates: vec![Point::new(20, 369), Point::new(21, 369)],
+        area: None,
         resolution,
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval {
                 start: datetime!(2022 - 12 - 31 00:00:00),
                 end: datetime!(2023 - 01 - 01 06:00:00),
             },
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = radolan::Product.downloadx(request);
+    let response = radolan::Product.downloadx(request).unwrap();
     dbg!(&response);
     assert_eq!(response.records.len(), 30);
 }
@@ -93,15 +113,19 @@ fn test_daily() {
     let resolution = RadolanResolution::RadolanDaily;
     let request = RadolanRequest {
         coordinates: vec![Point::new(20, 369), Point::new(21, 369)],
+        area: None,
         resolution,
         common: CommonRequestData {
             timespan: dwd_dl::util::interval::Interval {
                 start: datetime!(2022 - 12 - 31 00:00:00),
                 end: datetime!(2023 - 01 - 01 06:00:00),
             },
+            progress: Default::default(),
+            cancel: Default::default(),
+            listings: Default::default(),
         },
     };
-    let response = radolan::Product.downloadx(request);
+    let response = radolan::Product.downloadx(request).unwrap();
     dbg!(&response);
     assert_eq!(response.records.len(), 30);
 }
@@ -122,28 +146,47 @@ fn test_format_default() {
                 radolan::Record {
                     time: datetime!(2022 - 12 - 31 00:00:00),
                     data: vec![1.0, 2.0, 3.0, 4.0],
+                    flags: vec![Flag::Normal; 4],
+                    unit: Some(Unit::Mm),
+                    precision: -2,
+                    area: None,
                 },
                 radolan::Record {
                     time: datetime!(2023 - 01 - 01 00:00:00),
                     data: vec![1.1, 2.1, 3.1, 4.1],
+                    flags: vec![Flag::Normal; 4],
+                    unit: Some(Unit::Mm),
+                    precision: -2,
+                    area: None,
                 },
                 radolan::Record {
                     time: datetime!(2023 - 01 - 02 00:00:00),
                     data: vec![1.2, 2.2, 3.2, 4.2],
+                    flags: vec![Flag::Normal; 4],
+                    unit: Some(Unit::Mm),
+                    precision: -2,
+                    area: None,
                 },
                 radolan::Record {
                     time: datetime!(2023 - 01 - 03 00:00:00),
                     data: vec![1.3, 2.3, 3.3, 4.3],
+                    flags: vec![Flag::Normal; 4],
+                    unit: Some(Unit::Mm),
+                    precision: -2,
+                    area: None,
                 },
             ],
+            resolution: RadolanResolution::RadolanDaily,
         },
         RadolanFormatConfig {
             utc_to_berlin: false,
             offset: 0,
+            nodata: NodataPolicy::Sentinel,
+            quality: false,
         },
     );
 
-    let cmp = "Name\tDate\tTime\tValue\n\
+    let cmp = "Name\tDate\tTime\tValue [mm]\n\
     0004_0001\t2022-12-31\t00:00\t1.00\n\
     0004_0001\t2023-01-01\t00:00\t1.10\n\
     0004_0001\t2023-01-02\t00:00\t1.20\n\
@@ -184,28 +227,47 @@ fn test_format_swmm_rainfall_data() {
                 radolan::Record {
                     time: datetime!(2022 - 12 - 31 01:20:00),
                     data: vec![1.0, 2.0, 3.0, 4.0],
+                    flags: vec![Flag::Normal; 4],
+                    unit: Some(Unit::Mm),
+                    precision: -2,
+                    area: None,
                 },
                 radolan::Record {
                     time: datetime!(2023 - 01 - 01 02:20:00),
                     data: vec![1.1, 2.1, 3.1, 4.1],
+                    flags: vec![Flag::Normal; 4],
+                    unit: Some(Unit::Mm),
+                    precision: -2,
+                    area: None,
                 },
                 radolan::Record {
                     time: datetime!(2023 - 01 - 02 03:00:00),
                     data: vec![1.2, 2.2, 3.2, 4.2],
+                    flags: vec![Flag::Normal; 4],
+                    unit: Some(Unit::Mm),
+                    precision: -2,
+                    area: None,
                 },
                 radolan::Record {
                     time: datetime!(2023 - 01 - 03 03:10:00),
                     data: vec![1.3, 2.3, 3.3, 4.3],
+                    flags: vec![Flag::Normal; 4],
+                    unit: Some(Unit::Mm),
+                    precision: -2,
+                    area: None,
                 },
             ],
+            resolution: RadolanResolution::RadolanDaily,
         },
         RadolanFormatConfig {
             utc_to_berlin: true,
             offset: -10,
+            nodata: NodataPolicy::Sentinel,
+            quality: false,
         },
     );
 
-    let cmp = "Name\tJahr\tMonat\tTag\tStunde\tMinute\tWert\n\
+    let cmp = "Name\tJahr\tMonat\tTag\tStunde\tMinute\tWert [mm]\n\
     0004_0001\t2022\t12\t31\t02\t10\t1.00\n\
     0004_0001\t2023\t01\t01\t03\t10\t1.10\n\
     0004_0001\t2023\t01\t02\t03\t50\t1.20\n\
@@ -229,3 +291,186 @@ fn test_format_swmm_rainfall_data() {
         .zip(cmp.lines())
         .for_each(|(a, b)| assert_eq!(a, b));
 }
+
+#[test]
+fn test_write_netcdf() {
+    common::setup();
+
+    let response = radolan::RadolanResponse {
+        coordinates: vec![Point::new(4, 1), Point::new(5, 2)],
+        records: vec![
+            radolan::Record {
+                time: datetime!(1970 - 01 - 01 01:00:00),
+                data: vec![1.0, -9.0],
+                flags: vec![Flag::Normal, Flag::Error],
+                unit: Some(Unit::Mm),
+                precision: -2,
+                area: None,
+            },
+            radolan::Record {
+                time: datetime!(1970 - 01 - 01 02:00:00),
+                data: vec![1.5, 2.5],
+                flags: vec![Flag::Normal; 2],
+                unit: Some(Unit::Mm),
+                precision: -2,
+                area: None,
+            },
+        ],
+        resolution: RadolanResolution::RadolanDaily,
+    };
+    let config = RadolanFormatConfig {
+        utc_to_berlin: false,
+        offset: 0,
+        nodata: NodataPolicy::Sentinel,
+        quality: false,
+    };
+    let mut out = Vec::new();
+    write_netcdf(
+        &mut out,
+        &response.coordinates,
+        response.resolution,
+        &mut response.records.into_iter().map(Ok),
+        config,
+    )
+    .unwrap();
+
+    assert_eq!(&out[..4], b"CDF\x02");
+    let contains = |s: &str| out.windows(s.len()).any(|w| w == s.as_bytes());
+    assert!(contains("polar_stereographic"));
+    assert!(contains("minutes since 1970-01-01 00:00:00"));
+
+    // the cube is the last variable: 2 times, 2 rows, 2 columns
+    let cube = out[out.len() - 2 * 2 * 2 * 4..]
+        .chunks(4)
+        .map(|b| f32::from_be_bytes(b.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    let fill = -9999.0;
+    assert_eq!(cube, vec![1.0, fill, fill, fill, 1.5, fill, fill, 2.5]);
+    // times
+    let times = (60f64.to_be_bytes(), 120f64.to_be_bytes());
+    assert!(out
+        .windows(16)
+        .any(|w| w[..8] == times.0 && w[8..] == times.1));
+}
+
+#[test]
+fn test_write_netcdf_header() {
+    common::setup();
+
+    let record = |unit| radolan::Record {
+        time: datetime!(1970 - 01 - 01 01:00:00),
+        data: vec![1.0],
+        flags: vec![Flag::Normal],
+        unit,
+        precision: -1,
+        area: None,
+    };
+    let config = |utc_to_berlin| RadolanFormatConfig {
+        utc_to_berlin,
+        offset: 0,
+        nodata: NodataPolicy::Sentinel,
+        quality: false,
+    };
+    let write = |records: Vec<radolan::Record>, config| {
+        let mut out = Vec::new();
+        write_netcdf(
+            &mut out,
+            &[Point::new(4, 1)],
+            RadolanResolution::RadolanDaily,
+            &mut records.into_iter().map(Ok),
+            config,
+        )
+        .map(|_| out)
+    };
+
+    // the unit is the one of the file, not of the resolution
+    let out = write(vec![record(Some(Unit::MmPerHour))], config(false)).unwrap();
+    let contains = |s: &[u8]| out.windows(s.len()).any(|w| w == s);
+    assert!(contains(b"mm/h"));
+    assert!(contains(b"least_significant_digit"));
+
+    let mixed = vec![record(Some(Unit::Mm)), record(Some(Unit::MmPerHour))];
+    assert!(write(mixed, config(false)).is_err());
+    assert!(write(vec![record(None)], config(false)).is_err());
+    assert!(write(vec![record(Some(Unit::Mm))], config(true)).is_err());
+    assert!(write(vec![], config(false)).is_err());
+}
+
+#[test]
+fn test_format_quality() {
+    common::setup();
+
+    let response = || radolan::RadolanResponse {
+        coordinates: vec![Point::new(4, 1), Point::new(5, 2)],
+        records: vec![radolan::Record {
+            time: datetime!(2023 - 01 - 01 00:00:00),
+            data: vec![-9.0, -1.5],
+            flags: vec![Flag::Error, Flag::Negative],
+            unit: Some(Unit::Mm),
+            precision: -2,
+            area: Some(AreaStatistics {
+                mean: -9.0,
+                max: -9.0,
+                sum: -9.0,
+            }),
+        }],
+        resolution: RadolanResolution::RadolanDaily,
+    };
+    let config = |nodata| RadolanFormatConfig {
+        utc_to_berlin: false,
+        offset: 0,
+        nodata,
+        quality: true,
+    };
+
+    let res = format_default(response(), config(NodataPolicy::NaN));
+    let cmp = "Name\tDate\tTime\tValue [mm]\tQuality\n\
+    0004_0001\t2023-01-01\t00:00\tNaN\terror\n\
+    0005_0002\t2023-01-01\t00:00\t-1.50\tnegative\n\
+    area\t2023-01-01\t00:00\tNaN\terror\n";
+    assert_eq!(res, cmp);
+
+    let res = format_default(response(), config(NodataPolicy::Skip));
+    let cmp = "Name\tDate\tTime\tValue [mm]\tQuality\n\
+    0005_0002\t2023-01-01\t00:00\t-1.50\tnegative\n";
+    assert_eq!(res, cmp);
+
+    let res = format_default(response(), config(NodataPolicy::Zero));
+    assert!(res.contains("0004_0001\t2023-01-01\t00:00\t0.00\terror\n"));
+}
+
+#[test]
+fn test_write_default_streams() {
+    common::setup();
+
+    let record = radolan::Record {
+        time: datetime!(2023 - 01 - 01 00:00:00),
+        data: vec![1.5],
+        flags: vec![Flag::Normal],
+        unit: Some(Unit::Mm),
+        precision: -2,
+        area: None,
+    };
+    let config = RadolanFormatConfig {
+        utc_to_berlin: false,
+        offset: 0,
+        nodata: NodataPolicy::Sentinel,
+        quality: false,
+    };
+    let mut records = [Ok(record), Err(DwdError::Cancelled)].into_iter();
+    let mut out = Vec::new();
+    let result = write_default(
+        &mut out,
+        &[Point::new(4, 1)],
+        RadolanResolution::RadolanDaily,
+        &mut records,
+        config,
+    );
+
+    // a single coordinate is written before the next record is downloaded
+    assert!(result.is_err());
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "Name\tDate\tTime\tValue [mm]\n0004_0001\t2023-01-01\t00:00\t1.50\n"
+    );
+}
@@ -1,19 +1,37 @@
-use std::{fs, io::Write};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use dwd_dl::{
-    dwd_source::{CommonRequestData, DwdProduct},
+    batch::{self, BatchOutput, StationRequest, StationSelection},
+    cancel::CancellationToken,
+    dwd_source::{Common, CommonRequestData, DwdProduct},
     products::{
-        climate::{self, ClimateCommonRequestData, ClimateResolution},
-        evaporation::{self, EvaporationRequest, EvaporationResolution},
-        precipitation::{self, PrecipitationCommonRequestData, PrecipitationResolution},
-        radolan::{self, formats::RadolanFormatConfig, RadolanRequest, RadolanResolution},
+        climate::{self, ClimateCommonRequestData, ClimateFormatConfig, ClimateResolution},
+        evaporation::{self, decode::GridPosition, EvaporationRequest, EvaporationResolution},
+        observation::{self, ObservationCommonRequestData, ObservationResolution},
+        precipitation::{
+            self, PrecipitationCommonRequestData, PrecipitationFormatConfig,
+            PrecipitationResolution, QualityFilter,
+        },
+        radolan::{
+            self, formats::RadolanFormatConfig, AreaCoordinates, RadolanRequest, RadolanResolution,
+        },
     },
+    progress::{Progress, ProgressUpdate as DwdProgressUpdate},
+    stations::StationProduct,
+    util::{geo::LatLon, point::Point, polygon::MultiPolygon},
 };
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use tauri::{
     window::{ProgressBarState, ProgressBarStatus},
-    Manager, Window,
+    Manager, State, Window,
 };
 use time::format_description::well_known::Iso8601;
 
@@ -25,6 +43,15 @@ pub struct UniversalRequest {
     pub coordinates: String,
     pub product: Product,
     pub file_path: String,
+    /// several stations instead of `station`, only for station products
+    #[serde(default)]
+    pub batch: Option<BatchOptions>,
+}
+
+#[derive(Deserialize, Type, Debug, Clone)]
+pub struct BatchOptions {
+    pub stations: StationSelection,
+    pub output: BatchOutput,
 }
 
 impl UniversalRequest {
@@ -32,8 +59,11 @@ impl UniversalRequest {
         let mut filename = String::from(&self.product.resolution_str());
 
         match &self.product {
-            Product::Climate(_) | Product::Precipitation(_) => {
-                filename.push_str(&format!("_{}", &self.station))
+            Product::Climate(_) | Product::Precipitation(_) | Product::Observation(_) => {
+                match &self.batch {
+                    Some(_) => filename.push_str("_stations"),
+                    None => filename.push_str(&format!("_{}", &self.station)),
+                }
             }
             Product::Evaporation(_) | Product::Radolan(_) => (),
         }
@@ -60,6 +90,9 @@ impl TryInto<ClimateCommonRequestData> for UniversalRequest {
                         end: time::PrimitiveDateTime::parse(&self.end, &Iso8601::DEFAULT)
                             .map_err(|_| ())?,
                     },
+                    progress: Default::default(),
+                    cancel: Default::default(),
+                    listings: Default::default(),
                 },
                 station: self.station,
                 resolution: o.resolution,
@@ -82,6 +115,35 @@ impl TryInto<PrecipitationCommonRequestData> for UniversalRequest {
                         end: time::PrimitiveDateTime::parse(&self.end, &Iso8601::DEFAULT)
                             .map_err(|_| ())?,
                     },
+                    progress: Default::default(),
+                    cancel: Default::default(),
+                    listings: Default::default(),
+                },
+                station: self.station,
+                resolution: o.resolution,
+                quality: o.quality,
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryInto<ObservationCommonRequestData> for UniversalRequest {
+    type Error = ();
+
+    fn try_into(self) -> Result<ObservationCommonRequestData, Self::Error> {
+        match self.product {
+            Product::Observation(o) => Ok(ObservationCommonRequestData {
+                common: CommonRequestData {
+                    timespan: dwd_dl::util::interval::Interval {
+                        start: time::PrimitiveDateTime::parse(&self.start, &Iso8601::DEFAULT)
+                            .map_err(|_| ())?,
+                        end: time::PrimitiveDateTime::parse(&self.end, &Iso8601::DEFAULT)
+                            .map_err(|_| ())?,
+                    },
+                    progress: Default::default(),
+                    cancel: Default::default(),
+                    listings: Default::default(),
                 },
                 station: self.station,
                 resolution: o.resolution,
@@ -104,12 +166,11 @@ impl TryInto<EvaporationRequest> for UniversalRequest {
                         end: time::PrimitiveDateTime::parse(&self.end, &Iso8601::DEFAULT)
                             .map_err(|_| ())?,
                     },
+                    progress: Default::default(),
+                    cancel: Default::default(),
+                    listings: Default::default(),
                 },
-                coordinates: self
-                    .coordinates
-                    .lines()
-                    .map(|s| s.parse().unwrap())
-                    .collect(),
+                coordinates: evaporation_positions(&self.coordinates)?,
                 resolution: o.resolution,
             }),
             _ => Err(()),
@@ -130,12 +191,23 @@ impl TryInto<RadolanRequest> for UniversalRequest {
                         end: time::PrimitiveDateTime::parse(&self.end, &Iso8601::DEFAULT)
                             .map_err(|_| ())?,
                     },
+                    progress: Default::default(),
+                    cancel: Default::default(),
+                    listings: Default::default(),
+                },
+                coordinates: match is_polygon(&self.coordinates) {
+                    true => Vec::new(),
+                    false => radolan_cells(&self.coordinates, o.resolution)?,
+                },
+                area: match is_polygon(&self.coordinates) {
+                    true => {
+                        let polygon = self.coordinates.parse::<MultiPolygon>().map_err(|_| ())?;
+                        let area =
+                            RadolanRequest::area_of(o.resolution, &polygon, AreaCoordinates::Wgs84);
+                        Some(area.map_err(|_| ())?)
+                    }
+                    false => None,
                 },
-                coordinates: self
-                    .coordinates
-                    .lines()
-                    .map(|s| s.parse().unwrap())
-                    .collect(),
                 resolution: o.resolution,
             }),
             _ => Err(()),
@@ -143,12 +215,46 @@ impl TryInto<RadolanRequest> for UniversalRequest {
     }
 }
 
+/// GeoJSON or WKT instead of lines of coordinates
+fn is_polygon(coordinates: &str) -> bool {
+    coordinates
+        .trim_start()
+        .starts_with(|c: char| c == '{' || c.is_ascii_alphabetic())
+}
+
+/// Lines of `x,y` grid cells or of `lat,lon` WGS84 positions
+fn radolan_cells(coordinates: &str, resolution: RadolanResolution) -> Result<Vec<Point<u16>>, ()> {
+    coordinates
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.parse::<Point<u16>>() {
+            Ok(point) => Ok(point),
+            Err(_) => {
+                let position = line.parse::<LatLon>().map_err(|_| ())?;
+                let cells = RadolanRequest::cells_of(resolution, &[position]).map_err(|_| ())?;
+                Ok(cells[0])
+            }
+        })
+        .collect()
+}
+
+/// Lines of grid cells, Gauss-Krüger or WGS84 positions, see [`GridPosition`],
+/// they are resolved against the grid of each downloaded file
+fn evaporation_positions(coordinates: &str) -> Result<Vec<GridPosition>, ()> {
+    coordinates
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.parse::<GridPosition>().map_err(|_| ()))
+        .collect()
+}
+
 #[derive(Deserialize, Type, Debug, Clone)]
 pub enum Product {
     Climate(ClimateOptions),
     Precipitation(PrecipitationOptions),
     Radolan(RadolanOptions),
     Evaporation(EvaporationOptions),
+    Observation(ObservationOptions),
 }
 
 impl Product {
@@ -158,20 +264,27 @@ impl Product {
             Product::Precipitation(o) => format!("{}", o.resolution),
             Product::Radolan(o) => format!("{}", o.resolution),
             Product::Evaporation(o) => format!("{}", o.resolution),
+            Product::Observation(o) => format!("{}", o.resolution),
         }
     }
 }
 
-#[derive(Deserialize, Type, Debug, Clone, Copy)]
+#[derive(Deserialize, Type, Debug, Clone)]
 pub struct ClimateOptions {
     pub resolution: ClimateResolution,
     pub format: climate::ClimateFormat,
+    #[serde(default)]
+    pub format_config: ClimateFormatConfig,
 }
 
 #[derive(Deserialize, Type, Debug, Clone, Copy)]
 pub struct PrecipitationOptions {
     pub resolution: PrecipitationResolution,
     pub format: precipitation::PrecipitationFormat,
+    #[serde(default)]
+    pub quality: QualityFilter,
+    #[serde(default)]
+    pub format_config: PrecipitationFormatConfig,
 }
 
 #[derive(Deserialize, Type, Debug, Clone, Copy)]
@@ -187,58 +300,276 @@ pub struct EvaporationOptions {
     pub format: evaporation::EvaporationFormat,
 }
 
+#[derive(Deserialize, Type, Debug, Clone, Copy)]
+pub struct ObservationOptions {
+    pub resolution: ObservationResolution,
+    pub format: observation::ObservationFormat,
+}
+
+/// Cancellation tokens of the running requests by job id
+#[derive(Default)]
+pub struct Jobs(Mutex<HashMap<u32, CancellationToken>>);
+
 #[tauri::command]
-pub fn dwd_request(request: UniversalRequest) -> String {
-    let file = fs::File::create(&request.file_path).unwrap();
+pub async fn dwd_request(
+    window: Window,
+    jobs: State<'_, Jobs>,
+    job_id: u32,
+    request: UniversalRequest,
+) -> Result<String, String> {
+    let cancel = CancellationToken::default();
+    jobs.0.lock().unwrap().insert(job_id, cancel.clone());
 
-    match &request.product {
-        Product::Climate(o) => {
-            let request: ClimateCommonRequestData = request.clone().try_into().unwrap();
-            let data = climate::ClimateProduct.downloadx(request);
+    let (s, r) = crossbeam_channel::unbounded();
+    let (done_s, done_r) = crossbeam_channel::bounded(1);
 
-            let formatter = o.format.format_method();
-            let response = formatter(data, &o.resolution);
+    let forwarder = std::thread::spawn({
+        let window = window.clone();
+        move || forward_progress(&window, r, done_r)
+    });
 
-            let mut writer = std::io::BufWriter::new(file);
-            writer.write_all(response.as_bytes()).unwrap();
+    // download workers of a cancelled request may still hold a sender until their file is done,
+    // so the forwarding is ended explicitly instead of waiting for all senders to be dropped
+    let result = write_response(request, Progress::new(s), cancel);
+    let _ = done_s.send(());
+    forwarder.join().unwrap();
+    jobs.0.lock().unwrap().remove(&job_id);
+
+    window
+        .set_progress_bar(ProgressBarState {
+            status: Some(ProgressBarStatus::None),
+            progress: Some(0),
+        })
+        .unwrap();
+
+    result.map(|skipped| match skipped.is_empty() {
+        true => "success".to_string(),
+        false => format!("skipped {}", skipped.join(", ")),
+    })
+}
+
+/// Stops a running [`dwd_request`], `false` if the job is already finished
+#[tauri::command]
+pub fn dwd_cancel(jobs: State<'_, Jobs>, job_id: u32) -> bool {
+    match jobs.0.lock().unwrap().get(&job_id) {
+        Some(cancel) => {
+            cancel.cancel();
+            true
         }
-        Product::Precipitation(o) => {
-            let request: PrecipitationCommonRequestData = request.clone().try_into().unwrap();
-            let data = precipitation::Product.downloadx(request);
+        None => false,
+    }
+}
 
-            let formatter = o.format.format_method();
-            let response = formatter(data);
+/// Writes the requested file(s), the errors of the skipped stations of a batch
+fn write_response(
+    request: UniversalRequest,
+    progress: Progress,
+    cancel: CancellationToken,
+) -> Result<Vec<String>, String> {
+    let batch = request.batch.clone();
+    let path = request.file_path.clone();
+    let create = || fs::File::create(&path).map_err(|err| err.to_string());
 
-            let mut writer = std::io::BufWriter::new(file);
-            writer.write_all(response.as_bytes()).unwrap();
+    match &request.product {
+        Product::Climate(o) => {
+            let mut request: ClimateCommonRequestData = request.clone().try_into().unwrap();
+            request.common.progress = progress;
+            request.common.cancel = cancel;
+
+            let write = o.format.write_method();
+            let product = StationProduct::Climate(o.resolution);
+            return write_stations(&request, batch, product, &path, |writer, request| {
+                let mut records = climate::ClimateProduct.download_iter(request);
+                write(writer, &mut records, &o.resolution, &o.format_config)
+            });
+        }
+        Product::Precipitation(o) => {
+            let mut request: PrecipitationCommonRequestData = request.clone().try_into().unwrap();
+            request.common.progress = progress;
+            request.common.cancel = cancel;
+
+            let write = o.format.write_method();
+            let product = StationProduct::Precipitation(o.resolution);
+            return write_stations(&request, batch, product, &path, |writer, request| {
+                let mut records = precipitation::Product.download_iter(request);
+                write(writer, &request.station, &mut records, o.format_config)
+            });
         }
         Product::Radolan(o) => {
-            let request: RadolanRequest = request.clone().try_into().unwrap();
-            let data = radolan::Product.downloadx(request);
+            let mut request: RadolanRequest = request.clone().try_into().unwrap();
+            request.common.progress = progress;
+            request.common.cancel = cancel;
+            let mut records = radolan::Product.download_iter(&request);
+
+            let write = o.format.write_method();
+            let mut writer = std::io::BufWriter::new(create()?);
+            write(
+                &mut writer,
+                &request.coordinates,
+                request.resolution,
+                &mut records,
+                o.format_config,
+            )
+            .map_err(|err| err.to_string())?;
+            writer.flush().map_err(|err| err.to_string())?;
+        }
+        Product::Evaporation(o) => {
+            let mut request: EvaporationRequest = request.clone().try_into().unwrap();
+            request.common.progress = progress;
+            request.common.cancel = cancel;
+            let mut records = evaporation::Product.download_iter(&request);
+
+            let write = o.format.write_method();
+            let mut writer = std::io::BufWriter::new(create()?);
+            write(&mut writer, &mut records).map_err(|err| err.to_string())?;
+            writer.flush().map_err(|err| err.to_string())?;
+        }
+        Product::Observation(o) => {
+            let mut request: ObservationCommonRequestData = request.clone().try_into().unwrap();
+            request.common.progress = progress;
+            request.common.cancel = cancel;
+
+            let write = o.format.write_method();
+            let product = StationProduct::Observation(o.resolution);
+            return write_stations(&request, batch, product, &path, |writer, request| {
+                let mut records = observation::ObservationProduct.download_iter(request);
+                write(writer, &request.station, &mut records, &o.resolution)
+            });
+        }
+    }
 
-            dbg!(&o.format_config);
+    Ok(Vec::new())
+}
 
-            let formatter = o.format.format_method();
-            let response = formatter(data, o.format_config);
+/// Writes the requested station, or every station of the batch into `path` or one file per station
+fn write_stations<R: StationRequest + Common>(
+    request: &R,
+    batch: Option<BatchOptions>,
+    product: StationProduct,
+    path: &str,
+    write: impl FnMut(&mut dyn Write, &R) -> dwd_dl::error::Result<()>,
+) -> Result<Vec<String>, String> {
+    let (stations, output) = match batch {
+        Some(batch) => {
+            let timespan = request.common().timespan;
+            let stations = batch
+                .stations
+                .resolve(product, timespan)
+                .map_err(|err| err.to_string())?;
+            (stations, batch.output)
+        }
+        None => (vec![request.station().to_string()], BatchOutput::Combined),
+    };
 
+    let skipped = match output {
+        BatchOutput::Combined => {
+            let file = fs::File::create(path).map_err(|err| err.to_string())?;
             let mut writer = std::io::BufWriter::new(file);
-            writer.write_all(response.as_bytes()).unwrap();
+            let skipped = batch::write_combined(&mut writer, request, &stations, write)
+                .map_err(|err| err.to_string())?;
+            writer.flush().map_err(|err| err.to_string())?;
+            skipped
         }
-        Product::Evaporation(o) => {
-            let request: EvaporationRequest = request.clone().try_into().unwrap();
-            let data = evaporation::Product.downloadx(request);
-
-            let formatter = o.format.format_method();
-            let response = formatter(data);
+        BatchOutput::PerStation => {
+            batch::write_per_station(Path::new(path), request, &stations, write)
+                .map_err(|err| err.to_string())?
+                .1
+        }
+    };
+    Ok(skipped.iter().map(|err| err.to_string()).collect())
+}
 
-            let mut writer = std::io::BufWriter::new(file);
-            writer.write_all(response.as_bytes()).unwrap();
+/// Shows the progress of `dwd-dl` in the progress bar
+///
+/// Every station of a batch and every source of a station gets an equal share of the bar,
+/// which is filled by the finished files of its listing. Updates are only emitted when the
+/// percentage changes or [`PROGRESS_INTERVAL`] passed, not for every downloaded chunk.
+/// Ends once `done` receives, the progress bar is reset afterwards anyway.
+fn forward_progress(
+    window: &Window,
+    r: crossbeam_channel::Receiver<DwdProgressUpdate>,
+    done: crossbeam_channel::Receiver<()>,
+) {
+    let mut station = (0, 1);
+    let mut source = (0, 1);
+    let mut files = 0;
+    let mut finished = 0;
+    let mut last_emit: Option<(Instant, Option<u64>)> = None;
+    loop {
+        let update = crossbeam_channel::select! {
+            recv(r) -> update => update,
+            recv(done) -> _ => break,
+        };
+        let Ok(update) = update else {
+            break;
+        };
+        let message = match update {
+            DwdProgressUpdate::Station {
+                station: id,
+                index,
+                count,
+            } => {
+                station = (index, count);
+                source = (0, 1);
+                (files, finished) = (0, 0);
+                Some(format!("Station {} ({}/{})", id, index + 1, count))
+            }
+            DwdProgressUpdate::Source { index, count } => {
+                source = (index, count);
+                (files, finished) = (0, 0);
+                None
+            }
+            DwdProgressUpdate::Listing { files: n } => {
+                files = n;
+                None
+            }
+            DwdProgressUpdate::Download { url, bytes, .. } => {
+                let name = url.rsplit('/').next().unwrap_or(&url);
+                Some(format!("{} ({} kB)", name, bytes / 1000))
+            }
+            DwdProgressUpdate::Member { member, .. } => Some(member),
+            DwdProgressUpdate::Records { .. } => {
+                finished += 1;
+                None
+            }
+        };
+        let files_done = match files {
+            0 => 0.0,
+            _ => finished as f32 / files as f32,
+        };
+        let source_done = (source.0 as f32 + files_done) / source.1 as f32;
+        let progress = (station.0 as f32 + source_done) / station.1 as f32 * 100.0;
+
+        let percent = Some(progress as u64);
+        let due = last_emit.is_none_or(|(instant, last)| {
+            last != percent || instant.elapsed() >= PROGRESS_INTERVAL
+        });
+        if !due {
+            continue;
         }
+        last_emit = Some((Instant::now(), percent));
+
+        window
+            .set_progress_bar(ProgressBarState {
+                status: Some(ProgressBarStatus::Normal),
+                progress: percent,
+            })
+            .unwrap();
+        window
+            .emit(
+                "dwd-progress-update",
+                ProgressUpdate {
+                    progress: Some(progress),
+                    message,
+                },
+            )
+            .unwrap();
     }
-
-    "success".to_string()
 }
 
+/// Least time between two progress updates with the same percentage
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
 #[tauri::command]
 pub fn dwd_filename_suggestion(request: UniversalRequest) -> String {
     request.filename()
@@ -2,8 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use build_info::build_infos;
-use dwd_command::{async_test, dwd_filename_suggestion, dwd_request, test_open};
+use dwd_command::{async_test, dwd_cancel, dwd_filename_suggestion, dwd_request, test_open, Jobs};
 use specta::export;
+use tauri::Manager;
 
 mod build_info;
 mod dwd_command;
@@ -16,6 +17,9 @@ fn main() {
         .setup(|app| {
             app.handle()
                 .plugin(tauri_plugin_updater::Builder::new().build())?;
+            if std::env::var_os("DWD_CACHE_DIR").is_none() {
+                let _ = dwd_dl::set_cache_dir(app.path().app_cache_dir()?.join("dwd"));
+            }
             Ok(())
         })
         .plugin(tauri_plugin_process::init())
@@ -24,9 +28,11 @@ fn main() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_window_state::Builder::default().build())
+        .manage(Jobs::default())
         .invoke_handler(tauri::generate_handler![
             build_infos,
             dwd_request,
+            dwd_cancel,
             dwd_filename_suggestion,
             async_test,
             test_open
@@ -10,14 +10,31 @@ export const use_dwd_request_form_store = defineStore("dwdRequestForm", () => {
       station_id: "",
       coordinates: "",
       path: "",
+      batch: {
+        mode: "Single",
+        ids: "",
+        state: "",
+        south_west: "",
+        north_east: "",
+        output: "Combined",
+      } as BatchForm,
 
       climate: {
         format: "Standard",
         resolution: "ClimateDaily",
+        format_config: {
+          columns: [],
+          decimal_separator: "Point",
+          date_format: "Iso",
+        },
       } as ClimateOptions,
       precipitation: {
         format: "DateTogether",
         resolution: "PrecipitationMin1",
+        quality: "All",
+        format_config: {
+          quality: false,
+        },
       } as PrecipitationOptions,
       radolan: {
         format: "Default",
@@ -25,12 +42,18 @@ export const use_dwd_request_form_store = defineStore("dwdRequestForm", () => {
         format_config: {
           utc_to_berlin: false,
           offset: 0,
+          nodata: "Sentinel",
+          quality: false,
         },
       } as RadolanOptions,
       evaporation: {
         format: "Default",
         resolution: "EvaporationDailyP",
       } as EvaporationOptions,
+      observation: {
+        format: "Standard",
+        resolution: "AirTemperatureHourly",
+      } as ObservationOptions,
     },
     localStorage,
     {
@@ -0,0 +1,38 @@
+import { assert, describe, it } from "vitest";
+import { assembleBatch, type BatchForm } from "./assembleBatch";
+
+const form: BatchForm = {
+  mode: "Single",
+  ids: "44, 73\n04271",
+  state: " Bayern ",
+  south_west: "52.3,13.0",
+  north_east: "52.7, 13.8",
+  output: "PerStation",
+};
+
+describe("assembleBatch", () => {
+  it("single station", () => {
+    assert.isNull(assembleBatch(form));
+  });
+
+  it("ids", () => {
+    assert.deepEqual(assembleBatch({ ...form, mode: "Ids" }), {
+      stations: { Ids: ["44", "73", "04271"] },
+      output: "PerStation",
+    });
+  });
+
+  it("bounding box", () => {
+    assert.deepEqual(assembleBatch({ ...form, mode: "BoundingBox" }), {
+      stations: {
+        BoundingBox: {
+          south_west: { lat: 52.3, lon: 13.0 },
+          north_east: { lat: 52.7, lon: 13.8 },
+        },
+      },
+      output: "PerStation",
+    });
+    const invalid = { ...form, mode: "BoundingBox", north_east: "52.7" } as const;
+    assert.isUndefined(assembleBatch(invalid));
+  });
+});
@@ -0,0 +1,50 @@
+/** Station selection of the station products, kept in the form store */
+export type BatchForm = {
+  mode: "Single" | "Ids" | "State" | "BoundingBox";
+  ids: string;
+  state: string;
+  south_west: string;
+  north_east: string;
+  output: BatchOutput;
+};
+
+/**
+ * Assembles the batch options of a request.
+ *
+ * @param form - The station selection of the form.
+ * @returns `null` for a single station, or undefined if a position of the bounding box is invalid.
+ *
+ * @example
+ * assembleBatch({ ...form, mode: "Ids", ids: "44, 73" }); // Returns { stations: { Ids: ["44", "73"] }, output: form.output }
+ */
+export function assembleBatch(form: BatchForm): BatchOptions | null | undefined {
+  const output = form.output;
+  switch (form.mode) {
+    case "Ids":
+      return {
+        stations: { Ids: form.ids.split(/[\s,;]+/).filter((id) => id !== "") },
+        output,
+      };
+    case "State":
+      return { stations: { State: form.state.trim() }, output };
+    case "BoundingBox": {
+      const south_west = parseLatLon(form.south_west);
+      const north_east = parseLatLon(form.north_east);
+      if (south_west == null || north_east == null) {
+        return undefined;
+      }
+      return { stations: { BoundingBox: { south_west, north_east } }, output };
+    }
+    default:
+      return null;
+  }
+}
+
+/** `lat,lon`, e.g. `52.52,13.405` */
+function parseLatLon(s: string): LatLon | undefined {
+  const parts = s.split(",").map((n) => Number(n.trim()));
+  if (parts.length !== 2 || parts.some((n) => !Number.isFinite(n))) {
+    return undefined;
+  }
+  return { lat: parts[0], lon: parts[1] };
+}
@@ -1,36 +1,64 @@
  // This file has been generated by Specta. DO NOT EDIT.
 
+export type BatchOptions = { stations: StationSelection; output: BatchOutput }
+
+export type BatchOutput = "Combined" | "PerStation"
+
 export type BuildInfos = { rust_version: string; build_time: string; build_os: string }
 
-export type ClimateFormat = "Standard"
+export type ClimateColumn = { name: string; header: string | null }
+
+export type ClimateFormat = "Standard" | "Columns"
+
+export type ClimateFormatConfig = { columns: ClimateColumn[]; decimal_separator: DecimalSeparator; date_format: DateFormat }
 
-export type ClimateOptions = { resolution: ClimateResolution; format: ClimateFormat }
+export type ClimateOptions = { resolution: ClimateResolution; format: ClimateFormat; format_config: ClimateFormatConfig }
 
 export type ClimateResolution = "ClimateDaily" | "ClimateMonthly" | "ClimateAnnual"
 
-export type EvaporationFormat = "Default" | "SwmmRainfallData"
+export type DateFormat = "Iso" | "German" | "Dwd"
+
+export type DecimalSeparator = "Point" | "Comma"
+
+export type EvaporationFormat = "Default" | "SwmmRainfallData" | "NetCdf"
 
 export type EvaporationOptions = { resolution: EvaporationResolution; format: EvaporationFormat }
 
 export type EvaporationResolution = "EvaporationDailyP" | "EvaporationDailyR" | "EvaporationMonthlyP" | "EvaporationMonthlyR"
 
+export type LatLon = { lat: number; lon: number }
+
+export type NodataPolicy = "Sentinel" | "NaN" | "Skip" | "Zero"
+
+export type ObservationFormat = "Standard"
+
+export type ObservationOptions = { resolution: ObservationResolution; format: ObservationFormat }
+
+export type ObservationResolution = "AirTemperatureMin10" | "AirTemperatureHourly" | "WindMin10" | "WindHourly" | "SolarMin10" | "SolarHourly" | "SunHourly" | "SoilTemperatureHourly" | "PressureHourly" | "VisibilityHourly"
+
 export type PrecipitationFormat = "DateTogether" | "DateSeparated"
 
-export type PrecipitationOptions = { resolution: PrecipitationResolution; format: PrecipitationFormat }
+export type PrecipitationFormatConfig = { quality: boolean }
+
+export type PrecipitationOptions = { resolution: PrecipitationResolution; format: PrecipitationFormat; quality: QualityFilter; format_config: PrecipitationFormatConfig }
 
 export type PrecipitationResolution = "PrecipitationMin1" | "PrecipitationMin5" | "PrecipitationMin10" | "PrecipitationHourly"
 
-export type Product = { Climate: ClimateOptions } | { Precipitation: PrecipitationOptions } | { Radolan: RadolanOptions } | { Evaporation: EvaporationOptions }
+export type Product = { Climate: ClimateOptions } | { Precipitation: PrecipitationOptions } | { Radolan: RadolanOptions } | { Evaporation: EvaporationOptions } | { Observation: ObservationOptions }
 
 export type ProgressUpdate = { progress: number | null; message: string | null }
 
-export type RadolanFormat = "Default" | "SwmmRainfallData"
+export type QualityFilter = "All" | { Drop: number } | { Flag: number }
+
+export type RadolanFormat = "Default" | "SwmmRainfallData" | "AreaStatistics" | "NetCdf"
 
-export type RadolanFormatConfig = { utc_to_berlin: boolean; offset: number }
+export type RadolanFormatConfig = { utc_to_berlin: boolean; offset: number; nodata: NodataPolicy; quality: boolean }
 
 export type RadolanOptions = { resolution: RadolanResolution; format: RadolanFormat; format_config: RadolanFormatConfig }
 
 export type RadolanResolution = "RadolanDaily" | "RadolanHourly" | "RadolanHourlyReproc2017" | "RadolanMin5" | "RadolanMin5Reproc2017"
 
-export type UniversalRequest = { start: string; end: string; station: string; coordinates: string; product: Product; file_path: string }
+export type StationSelection = { Ids: string[] } | { State: string } | { BoundingBox: { south_west: LatLon; north_east: LatLon } }
+
+export type UniversalRequest = { start: string; end: string; station: string; coordinates: string; product: Product; file_path: string; batch: BatchOptions | null }
 